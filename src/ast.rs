@@ -0,0 +1,783 @@
+//! Abstract syntax tree for Ruby source, produced by [`crate::parser`].
+//!
+//! Node kinds loosely follow the `parser` gem used by RuboCop, so cops can
+//! be ported with familiar structure: method calls are `Send` nodes,
+//! `unless` is an `If` with [`IfKeyword::Unless`], and so on.
+
+use std::fmt::Write;
+
+/// A node in the syntax tree with the byte range it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    /// Byte offset of the first byte of the node.
+    pub start: usize,
+    /// Byte offset one past the last byte of the node.
+    pub end: usize,
+}
+
+/// Keyword that introduced an `If` node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfKeyword {
+    If,
+    Unless,
+    Elsif,
+    Ternary,
+}
+
+/// Kind of a method or block parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// `a`
+    Required,
+    /// `a = 1`
+    Optional,
+    /// `*rest`
+    Rest,
+    /// `key:`
+    Keyword,
+    /// `key: 1`
+    OptionalKeyword,
+    /// `**opts`
+    KeywordRest,
+    /// `**nil`
+    NoKeywords,
+    /// `&block`
+    Block,
+    /// `...`
+    Forward,
+    /// Block-local variable after `;` in `|a; b|`.
+    Shadow,
+}
+
+type Child = Option<Box<Node>>;
+
+/// The different kinds of syntax nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    /// A sequence of statements, e.g. a file or a multi-statement body.
+    Statements(Vec<Node>),
+    /// A parenthesized expression `( ... )`.
+    Parens(Vec<Node>),
+    /// An explicit `begin ... end` block.
+    Kwbegin(Child),
+
+    Nil,
+    True,
+    False,
+    SelfRef,
+    Int,
+    Float,
+    /// A character literal, e.g. `?a`.
+    Char,
+    /// A string. `parts` holds `StrPart` and `Interpolation` nodes, or
+    /// nested strings for adjacent-literal concatenation; a string with only
+    /// `StrPart`s is a plain `str`. For heredocs the node covers the
+    /// `<<~EOS` marker only.
+    Str { parts: Vec<Node>, heredoc: bool },
+    /// A run of literal text inside a string-like literal.
+    StrPart,
+    /// `#{ ... }` inside a string-like literal.
+    Interpolation(Vec<Node>),
+    /// A simple symbol, e.g. `:foo`. `name` excludes the colon.
+    Sym { name: String },
+    /// A symbol with quotes or interpolation, e.g. `:"a#{b}"`.
+    Dsym { parts: Vec<Node> },
+    /// A backtick or `%x` command string.
+    Xstr { parts: Vec<Node> },
+    Regexp { parts: Vec<Node>, flags: String },
+    Array { elements: Vec<Node> },
+    /// A hash literal, or trailing keyword arguments when `braces` is false.
+    Hash { pairs: Vec<Node>, braces: bool },
+    Pair { key: Box<Node>, value: Box<Node> },
+    Splat(Child),
+    DoubleSplat(Child),
+    BlockPass(Child),
+    Range { from: Child, to: Child, exclusive: bool },
+
+    Lvar(String),
+    Ivar(String),
+    Cvar(String),
+    Gvar(String),
+    /// A constant reference. `scope` is the `Foo` in `Foo::Bar`, or `Cbase`
+    /// for `::Bar`.
+    Const { scope: Child, name: String },
+    /// The leading `::` of a top-level constant reference.
+    Cbase,
+
+    /// Simple assignment to a variable or constant.
+    Assign { target: Box<Node>, value: Box<Node> },
+    /// Operator assignment such as `a += 1` or `a ||= b`. `operator`
+    /// excludes the `=`.
+    OpAssign { target: Box<Node>, operator: String, value: Box<Node> },
+    /// Multiple assignment, e.g. `a, b = b, a`.
+    Masgn { targets: Box<Node>, value: Box<Node> },
+    /// Left-hand side of a multiple assignment or destructuring parameter.
+    Mlhs(Vec<Node>),
+
+    /// A method call. Operators, attribute writers (`a.b = 1`) and index
+    /// access (`a[1]`) are sends too.
+    Send {
+        receiver: Child,
+        method: String,
+        arguments: Vec<Node>,
+        safe_navigation: bool,
+        parenthesized: bool,
+    },
+    /// `super` with explicit arguments, or bare `super` when `arguments` is
+    /// `None`.
+    Super { arguments: Option<Vec<Node>> },
+    Yield { arguments: Vec<Node> },
+    /// A call with a block attached. `call` is the `Send`, `Super` or
+    /// `Lambda` the block belongs to.
+    Block {
+        call: Box<Node>,
+        parameters: Child,
+        body: Child,
+        braces: bool,
+    },
+    /// The `->` of a stabby lambda.
+    Lambda,
+    /// A parameter list of a method or block.
+    Params(Vec<Node>),
+    Param {
+        kind: ParamKind,
+        name: Option<String>,
+        default: Child,
+    },
+    Defined(Box<Node>),
+    And { left: Box<Node>, right: Box<Node> },
+    Or { left: Box<Node>, right: Box<Node> },
+
+    /// A method definition; `receiver` is set for `def self.foo`.
+    Def {
+        receiver: Child,
+        name: String,
+        parameters: Child,
+        body: Child,
+        endless: bool,
+    },
+    Class {
+        name: Box<Node>,
+        superclass: Child,
+        body: Child,
+    },
+    /// `class << self`.
+    SingletonClass { target: Box<Node>, body: Child },
+    Module { name: Box<Node>, body: Child },
+    Alias { new_name: Box<Node>, old_name: Box<Node> },
+    Undef(Vec<Node>),
+
+    If {
+        condition: Box<Node>,
+        then_body: Child,
+        else_body: Child,
+        keyword: IfKeyword,
+        modifier: bool,
+    },
+    /// `while` or `until` loop, including modifier forms.
+    While {
+        condition: Box<Node>,
+        body: Child,
+        until: bool,
+        modifier: bool,
+    },
+    For {
+        variable: Box<Node>,
+        iterable: Box<Node>,
+        body: Child,
+    },
+    Case {
+        subject: Child,
+        whens: Vec<Node>,
+        else_body: Child,
+    },
+    When { conditions: Vec<Node>, body: Child },
+    /// `case ... in` pattern matching.
+    CaseMatch {
+        subject: Box<Node>,
+        clauses: Vec<Node>,
+        else_body: Child,
+    },
+    InClause {
+        pattern: Box<Node>,
+        guard: Child,
+        body: Child,
+    },
+    /// `if` guard of an `in` clause.
+    IfGuard(Box<Node>),
+    /// `unless` guard of an `in` clause.
+    UnlessGuard(Box<Node>),
+    /// One-line pattern match: `expr => pattern` or `expr in pattern`.
+    MatchPattern {
+        value: Box<Node>,
+        pattern: Box<Node>,
+        raises: bool,
+    },
+    ArrayPattern { constant: Child, elements: Vec<Node> },
+    FindPattern { constant: Child, elements: Vec<Node> },
+    HashPattern { constant: Child, pairs: Vec<Node> },
+    /// A variable binding in a pattern.
+    MatchVar(String),
+    /// `*rest` or `**rest` in a pattern.
+    MatchRest(Option<String>),
+    /// `**nil` in a hash pattern.
+    MatchNil,
+    /// `^expr` in a pattern.
+    Pin(Box<Node>),
+    MatchAlt { left: Box<Node>, right: Box<Node> },
+    /// `pattern => name`.
+    MatchAs { pattern: Box<Node>, name: String },
+
+    /// A body with `rescue` clauses.
+    Rescue {
+        body: Child,
+        handlers: Vec<Node>,
+        else_body: Child,
+    },
+    /// A single `rescue` clause.
+    RescueBody {
+        exceptions: Vec<Node>,
+        variable: Child,
+        body: Child,
+    },
+    Ensure { body: Child, ensure_body: Child },
+    Return(Vec<Node>),
+    Break(Vec<Node>),
+    Next(Vec<Node>),
+    Redo,
+    Retry,
+    /// `BEGIN { ... }`.
+    Preexe(Child),
+    /// `END { ... }`.
+    Postexe(Child),
+
+    /// Tokens the parser could not make sense of.
+    Error,
+}
+
+impl Node {
+    pub fn new(kind: NodeKind, start: usize, end: usize) -> Self {
+        Self { kind, start, end }
+    }
+
+    /// Returns the source text covered by this node.
+    pub fn source<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    /// The `parser`-gem style type name, e.g. `"send"` or `"def"`.
+    pub fn type_name(&self) -> &'static str {
+        match &self.kind {
+            NodeKind::Statements(_) => "begin",
+            NodeKind::Parens(_) => "parens",
+            NodeKind::Kwbegin(_) => "kwbegin",
+            NodeKind::Nil => "nil",
+            NodeKind::True => "true",
+            NodeKind::False => "false",
+            NodeKind::SelfRef => "self",
+            NodeKind::Int => "int",
+            NodeKind::Float => "float",
+            NodeKind::Char => "char",
+            NodeKind::Str { parts, .. } if is_plain(parts) => "str",
+            NodeKind::Str { .. } => "dstr",
+            NodeKind::StrPart => "str_part",
+            NodeKind::Interpolation(_) => "interpolation",
+            NodeKind::Sym { .. } => "sym",
+            NodeKind::Dsym { .. } => "dsym",
+            NodeKind::Xstr { .. } => "xstr",
+            NodeKind::Regexp { .. } => "regexp",
+            NodeKind::Array { .. } => "array",
+            NodeKind::Hash { .. } => "hash",
+            NodeKind::Pair { .. } => "pair",
+            NodeKind::Splat(_) => "splat",
+            NodeKind::DoubleSplat(_) => "kwsplat",
+            NodeKind::BlockPass(_) => "block_pass",
+            NodeKind::Range { exclusive: false, .. } => "irange",
+            NodeKind::Range { exclusive: true, .. } => "erange",
+            NodeKind::Lvar(_) => "lvar",
+            NodeKind::Ivar(_) => "ivar",
+            NodeKind::Cvar(_) => "cvar",
+            NodeKind::Gvar(_) => "gvar",
+            NodeKind::Const { .. } => "const",
+            NodeKind::Cbase => "cbase",
+            NodeKind::Assign { .. } => "asgn",
+            NodeKind::OpAssign { .. } => "op_asgn",
+            NodeKind::Masgn { .. } => "masgn",
+            NodeKind::Mlhs(_) => "mlhs",
+            NodeKind::Send {
+                safe_navigation: true,
+                ..
+            } => "csend",
+            NodeKind::Send { .. } => "send",
+            NodeKind::Super { arguments: None } => "zsuper",
+            NodeKind::Super { .. } => "super",
+            NodeKind::Yield { .. } => "yield",
+            NodeKind::Block { .. } => "block",
+            NodeKind::Lambda => "lambda",
+            NodeKind::Params(_) => "args",
+            NodeKind::Param { kind, .. } => match kind {
+                ParamKind::Required => "arg",
+                ParamKind::Optional => "optarg",
+                ParamKind::Rest => "restarg",
+                ParamKind::Keyword => "kwarg",
+                ParamKind::OptionalKeyword => "kwoptarg",
+                ParamKind::KeywordRest => "kwrestarg",
+                ParamKind::NoKeywords => "kwnilarg",
+                ParamKind::Block => "blockarg",
+                ParamKind::Forward => "forward_arg",
+                ParamKind::Shadow => "shadowarg",
+            },
+            NodeKind::Defined(_) => "defined?",
+            NodeKind::And { .. } => "and",
+            NodeKind::Or { .. } => "or",
+            NodeKind::Def { receiver: None, .. } => "def",
+            NodeKind::Def { .. } => "defs",
+            NodeKind::Class { .. } => "class",
+            NodeKind::SingletonClass { .. } => "sclass",
+            NodeKind::Module { .. } => "module",
+            NodeKind::Alias { .. } => "alias",
+            NodeKind::Undef(_) => "undef",
+            NodeKind::If { .. } => "if",
+            NodeKind::While { until: false, .. } => "while",
+            NodeKind::While { .. } => "until",
+            NodeKind::For { .. } => "for",
+            NodeKind::Case { .. } => "case",
+            NodeKind::When { .. } => "when",
+            NodeKind::CaseMatch { .. } => "case_match",
+            NodeKind::InClause { .. } => "in_pattern",
+            NodeKind::IfGuard(_) => "if_guard",
+            NodeKind::UnlessGuard(_) => "unless_guard",
+            NodeKind::MatchPattern { raises: true, .. } => "match_pattern",
+            NodeKind::MatchPattern { .. } => "match_pattern_p",
+            NodeKind::ArrayPattern { .. } => "array_pattern",
+            NodeKind::FindPattern { .. } => "find_pattern",
+            NodeKind::HashPattern { .. } => "hash_pattern",
+            NodeKind::MatchVar(_) => "match_var",
+            NodeKind::MatchRest(_) => "match_rest",
+            NodeKind::MatchNil => "match_nil_pattern",
+            NodeKind::Pin(_) => "pin",
+            NodeKind::MatchAlt { .. } => "match_alt",
+            NodeKind::MatchAs { .. } => "match_as",
+            NodeKind::Rescue { .. } => "rescue",
+            NodeKind::RescueBody { .. } => "resbody",
+            NodeKind::Ensure { .. } => "ensure",
+            NodeKind::Return(_) => "return",
+            NodeKind::Break(_) => "break",
+            NodeKind::Next(_) => "next",
+            NodeKind::Redo => "redo",
+            NodeKind::Retry => "retry",
+            NodeKind::Preexe(_) => "preexe",
+            NodeKind::Postexe(_) => "postexe",
+            NodeKind::Error => "error",
+        }
+    }
+
+    /// Calls `f` for each direct child, in source order.
+    pub fn for_each_child<'a>(&'a self, mut f: impl FnMut(&'a Node)) {
+        fn opt<'a>(child: &'a Child, f: &mut impl FnMut(&'a Node)) {
+            if let Some(node) = child {
+                f(node);
+            }
+        }
+        fn all<'a>(nodes: &'a [Node], f: &mut impl FnMut(&'a Node)) {
+            nodes.iter().for_each(&mut *f);
+        }
+        let f = &mut f;
+        match &self.kind {
+            NodeKind::Statements(nodes)
+            | NodeKind::Parens(nodes)
+            | NodeKind::Interpolation(nodes)
+            | NodeKind::Mlhs(nodes)
+            | NodeKind::Params(nodes)
+            | NodeKind::Undef(nodes)
+            | NodeKind::Return(nodes)
+            | NodeKind::Break(nodes)
+            | NodeKind::Next(nodes) => all(nodes, f),
+            NodeKind::Str { parts, .. }
+            | NodeKind::Dsym { parts }
+            | NodeKind::Xstr { parts }
+            | NodeKind::Regexp { parts, .. } => all(parts, f),
+            NodeKind::Array { elements } => all(elements, f),
+            NodeKind::Hash { pairs, .. } => all(pairs, f),
+            NodeKind::Yield { arguments } => all(arguments, f),
+            NodeKind::Super { arguments } => {
+                if let Some(arguments) = arguments {
+                    all(arguments, f);
+                }
+            }
+            NodeKind::Kwbegin(child)
+            | NodeKind::Splat(child)
+            | NodeKind::DoubleSplat(child)
+            | NodeKind::BlockPass(child)
+            | NodeKind::Preexe(child)
+            | NodeKind::Postexe(child) => opt(child, f),
+            NodeKind::Defined(node)
+            | NodeKind::IfGuard(node)
+            | NodeKind::UnlessGuard(node)
+            | NodeKind::Pin(node) => f(node),
+            NodeKind::Pair { key, value } => {
+                f(key);
+                f(value);
+            }
+            NodeKind::Range { from, to, .. } => {
+                opt(from, f);
+                opt(to, f);
+            }
+            NodeKind::Const { scope, .. } => opt(scope, f),
+            NodeKind::Assign { target, value } | NodeKind::OpAssign { target, value, .. } => {
+                f(target);
+                f(value);
+            }
+            NodeKind::Masgn { targets, value } => {
+                f(targets);
+                f(value);
+            }
+            NodeKind::Send {
+                receiver,
+                arguments,
+                ..
+            } => {
+                opt(receiver, f);
+                all(arguments, f);
+            }
+            NodeKind::Block {
+                call,
+                parameters,
+                body,
+                ..
+            } => {
+                f(call);
+                opt(parameters, f);
+                opt(body, f);
+            }
+            NodeKind::Param { default, .. } => opt(default, f),
+            NodeKind::And { left, right }
+            | NodeKind::Or { left, right }
+            | NodeKind::MatchAlt { left, right } => {
+                f(left);
+                f(right);
+            }
+            NodeKind::Def {
+                receiver,
+                parameters,
+                body,
+                ..
+            } => {
+                opt(receiver, f);
+                opt(parameters, f);
+                opt(body, f);
+            }
+            NodeKind::Class {
+                name,
+                superclass,
+                body,
+            } => {
+                f(name);
+                opt(superclass, f);
+                opt(body, f);
+            }
+            NodeKind::SingletonClass { target, body } => {
+                f(target);
+                opt(body, f);
+            }
+            NodeKind::Module { name, body } => {
+                f(name);
+                opt(body, f);
+            }
+            NodeKind::Alias { new_name, old_name } => {
+                f(new_name);
+                f(old_name);
+            }
+            NodeKind::If {
+                condition,
+                then_body,
+                else_body,
+                ..
+            } => {
+                f(condition);
+                opt(then_body, f);
+                opt(else_body, f);
+            }
+            NodeKind::While {
+                condition, body, ..
+            } => {
+                f(condition);
+                opt(body, f);
+            }
+            NodeKind::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                f(variable);
+                f(iterable);
+                opt(body, f);
+            }
+            NodeKind::Case {
+                subject,
+                whens,
+                else_body,
+            } => {
+                opt(subject, f);
+                all(whens, f);
+                opt(else_body, f);
+            }
+            NodeKind::When { conditions, body } => {
+                all(conditions, f);
+                opt(body, f);
+            }
+            NodeKind::CaseMatch {
+                subject,
+                clauses,
+                else_body,
+            } => {
+                f(subject);
+                all(clauses, f);
+                opt(else_body, f);
+            }
+            NodeKind::InClause {
+                pattern,
+                guard,
+                body,
+            } => {
+                f(pattern);
+                opt(guard, f);
+                opt(body, f);
+            }
+            NodeKind::MatchPattern { value, pattern, .. } => {
+                f(value);
+                f(pattern);
+            }
+            NodeKind::ArrayPattern { constant, elements }
+            | NodeKind::FindPattern { constant, elements } => {
+                opt(constant, f);
+                all(elements, f);
+            }
+            NodeKind::HashPattern { constant, pairs } => {
+                opt(constant, f);
+                all(pairs, f);
+            }
+            NodeKind::MatchAs { pattern, .. } => f(pattern),
+            NodeKind::Rescue {
+                body,
+                handlers,
+                else_body,
+            } => {
+                opt(body, f);
+                all(handlers, f);
+                opt(else_body, f);
+            }
+            NodeKind::RescueBody {
+                exceptions,
+                variable,
+                body,
+            } => {
+                all(exceptions, f);
+                opt(variable, f);
+                opt(body, f);
+            }
+            NodeKind::Ensure { body, ensure_body } => {
+                opt(body, f);
+                opt(ensure_body, f);
+            }
+            NodeKind::Nil
+            | NodeKind::True
+            | NodeKind::False
+            | NodeKind::SelfRef
+            | NodeKind::Int
+            | NodeKind::Float
+            | NodeKind::Char
+            | NodeKind::StrPart
+            | NodeKind::Sym { .. }
+            | NodeKind::Lvar(_)
+            | NodeKind::Ivar(_)
+            | NodeKind::Cvar(_)
+            | NodeKind::Gvar(_)
+            | NodeKind::Cbase
+            | NodeKind::Lambda
+            | NodeKind::MatchVar(_)
+            | NodeKind::MatchRest(_)
+            | NodeKind::MatchNil
+            | NodeKind::Redo
+            | NodeKind::Retry
+            | NodeKind::Error => {}
+        }
+    }
+
+    /// Returns the direct children of this node, in source order.
+    pub fn children(&self) -> Vec<&Node> {
+        let mut children = Vec::new();
+        self.for_each_child(|child| children.push(child));
+        children
+    }
+
+    /// Visits this node and all of its descendants in depth-first pre-order.
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Node)) {
+        f(self);
+        self.for_each_child(|child| child.walk(f));
+    }
+
+    /// Method name if this is a `Send` node.
+    pub fn method_name(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Send { method, .. } => Some(method),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a call to the given method name.
+    pub fn is_send_to(&self, name: &str) -> bool {
+        self.method_name() == Some(name)
+    }
+
+    /// Receiver of a `Send` node, if any.
+    pub fn receiver(&self) -> Option<&Node> {
+        match &self.kind {
+            NodeKind::Send { receiver, .. } => receiver.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Arguments of a `Send`, `Super` or `Yield` node.
+    pub fn arguments(&self) -> &[Node] {
+        match &self.kind {
+            NodeKind::Send { arguments, .. }
+            | NodeKind::Yield { arguments }
+            | NodeKind::Super {
+                arguments: Some(arguments),
+            } => arguments,
+            _ => &[],
+        }
+    }
+
+    /// Body of a definition, block, loop or `begin` node.
+    pub fn body(&self) -> Option<&Node> {
+        match &self.kind {
+            NodeKind::Def { body, .. }
+            | NodeKind::Class { body, .. }
+            | NodeKind::SingletonClass { body, .. }
+            | NodeKind::Module { body, .. }
+            | NodeKind::Block { body, .. }
+            | NodeKind::While { body, .. }
+            | NodeKind::For { body, .. }
+            | NodeKind::When { body, .. }
+            | NodeKind::InClause { body, .. }
+            | NodeKind::RescueBody { body, .. }
+            | NodeKind::Kwbegin(body) => body.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Renders the node as a `parser`-gem style S-expression, which is handy
+    /// in tests and when debugging cops.
+    pub fn to_sexp(&self, source: &str) -> String {
+        let mut out = String::new();
+        self.write_sexp(source, &mut out);
+        out
+    }
+
+    fn write_sexp(&self, source: &str, out: &mut String) {
+        out.push('(');
+        out.push_str(self.type_name());
+        match &self.kind {
+            NodeKind::Int | NodeKind::Float | NodeKind::Char | NodeKind::StrPart => {
+                let _ = write!(out, " {:?}", self.source(source));
+            }
+            NodeKind::Str { parts, .. } if is_plain(parts) => {
+                let _ = write!(out, " {:?})", self.source(source));
+                return;
+            }
+            NodeKind::Sym { name } => {
+                let _ = write!(out, " :{}", name);
+            }
+            NodeKind::Lvar(name)
+            | NodeKind::Ivar(name)
+            | NodeKind::Cvar(name)
+            | NodeKind::Gvar(name)
+            | NodeKind::MatchVar(name) => {
+                let _ = write!(out, " {}", name);
+            }
+            NodeKind::Const { name, .. } => {
+                let _ = write!(out, " {}", name);
+            }
+            NodeKind::Send { method, receiver, .. } => {
+                if receiver.is_none() {
+                    out.push_str(" nil");
+                }
+                let _ = write!(out, " :{}", method);
+            }
+            NodeKind::Def { name, .. } => {
+                let _ = write!(out, " {}", name);
+            }
+            NodeKind::Param {
+                name: Some(name), ..
+            } => {
+                let _ = write!(out, " {}", name);
+            }
+            NodeKind::OpAssign { operator, .. } => {
+                let _ = write!(out, " {}", operator);
+            }
+            NodeKind::MatchRest(Some(name)) | NodeKind::MatchAs { name, .. } => {
+                let _ = write!(out, " {}", name);
+            }
+            NodeKind::Regexp { flags, .. } if !flags.is_empty() => {
+                let _ = write!(out, " /{}", flags);
+            }
+            _ => {}
+        }
+        self.for_each_child(|child| {
+            out.push(' ');
+            child.write_sexp(source, out);
+        });
+        out.push(')');
+    }
+}
+
+/// Whether string parts contain only literal text.
+fn is_plain(parts: &[Node]) -> bool {
+    parts.iter().all(|part| part.kind == NodeKind::StrPart)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(kind: NodeKind, start: usize, end: usize) -> Node {
+        Node::new(kind, start, end)
+    }
+
+    #[test]
+    fn test_children_in_source_order() {
+        let source = "a + 1";
+        let node = Node::new(
+            NodeKind::Send {
+                receiver: Some(Box::new(leaf(NodeKind::Lvar("a".into()), 0, 1))),
+                method: "+".into(),
+                arguments: vec![leaf(NodeKind::Int, 4, 5)],
+                safe_navigation: false,
+                parenthesized: false,
+            },
+            0,
+            5,
+        );
+        let children: Vec<&str> = node.children().iter().map(|c| c.source(source)).collect();
+        assert_eq!(children, vec!["a", "1"]);
+        assert_eq!(node.to_sexp(source), "(send :+ (lvar a) (int \"1\"))");
+    }
+
+    #[test]
+    fn test_walk_visits_descendants() {
+        let node = Node::new(
+            NodeKind::Statements(vec![
+                leaf(NodeKind::Nil, 0, 3),
+                Node::new(NodeKind::Parens(vec![leaf(NodeKind::True, 5, 9)]), 4, 10),
+            ]),
+            0,
+            10,
+        );
+        let mut names = Vec::new();
+        node.walk(&mut |n| names.push(n.type_name()));
+        assert_eq!(names, vec!["begin", "nil", "parens", "true"]);
+    }
+}
@@ -0,0 +1,258 @@
+//! Baselines: recording today's offenses so later runs report only new ones.
+//!
+//! Each offense is identified by a fingerprint hashing its cop, its file,
+//! the whitespace-normalized content of its line and its index among the
+//! offenses sharing those three. Adding or removing unrelated lines around
+//! an offense leaves its fingerprint unchanged.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::offense::Offense;
+use crate::runner::RunResult;
+use crate::source::SourceFile;
+
+/// Version of the baseline file format.
+const FORMAT_VERSION: u32 = 1;
+
+/// The offenses recorded in a baseline file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    version: u32,
+    offenses: Vec<BaselineEntry>,
+}
+
+/// One recorded offense. Only the fingerprint is matched; the rest helps
+/// reviewing the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub cop_name: String,
+    pub path: String,
+    pub line: usize,
+    pub message: String,
+}
+
+/// What filtering a run through a baseline did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaselineMatch {
+    /// Offenses left out because the baseline records them.
+    pub suppressed: usize,
+    /// Recorded offenses in the inspected files that no longer occur.
+    pub fixed: usize,
+}
+
+impl Baseline {
+    /// Records every uncorrected offense of `result`.
+    pub fn from_result(result: &RunResult) -> Self {
+        let offenses = result
+            .file_results
+            .iter()
+            .flat_map(|file_result| {
+                let path = baseline_path(&file_result.path);
+                file_result
+                    .offenses
+                    .iter()
+                    .filter(|offense| !offense.corrected)
+                    .filter_map(move |offense| {
+                        Some(BaselineEntry {
+                            fingerprint: offense.fingerprint.clone()?,
+                            cop_name: offense.cop_name.clone(),
+                            path: path.clone(),
+                            line: offense.location.line,
+                            message: offense.message.clone(),
+                        })
+                    })
+            })
+            .collect();
+        Self {
+            version: FORMAT_VERSION,
+            offenses,
+        }
+    }
+
+    /// Reads a baseline file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let baseline: Self = serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if baseline.version != FORMAT_VERSION {
+            return Err(format!(
+                "{}: unsupported baseline version {}",
+                path.display(),
+                baseline.version
+            ));
+        }
+        Ok(baseline)
+    }
+
+    /// Writes the baseline as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    /// Number of recorded offenses.
+    pub fn len(&self) -> usize {
+        self.offenses.len()
+    }
+
+    /// Whether no offenses are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.offenses.is_empty()
+    }
+
+    /// Removes the offenses the baseline records from `result`.
+    pub fn filter(&self, result: &mut RunResult) -> BaselineMatch {
+        let known: HashSet<&str> = self.offenses.iter().map(|e| e.fingerprint.as_str()).collect();
+        let mut seen = HashSet::new();
+        let mut suppressed = 0;
+        for file_result in &mut result.file_results {
+            file_result.offenses.retain(|offense| {
+                let recorded = offense
+                    .fingerprint
+                    .as_deref()
+                    .filter(|fingerprint| known.contains(fingerprint));
+                match recorded {
+                    Some(fingerprint) => {
+                        seen.insert(fingerprint.to_string());
+                        suppressed += 1;
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        result.total_offenses = result.file_results.iter().map(|r| r.offenses.len()).sum();
+
+        let inspected: HashSet<String> = result.file_results.iter().map(|r| baseline_path(&r.path)).collect();
+        let fixed = self
+            .offenses
+            .iter()
+            .filter(|e| inspected.contains(&e.path) && !seen.contains(&e.fingerprint))
+            .count();
+        BaselineMatch { suppressed, fixed }
+    }
+}
+
+/// Sets the fingerprint of each of a file's offenses, which must be sorted
+/// by location.
+pub fn assign_fingerprints(offenses: &mut [Offense], path: &Path, source: &SourceFile) {
+    let path = baseline_path(path);
+    let mut occurrences: HashMap<(String, String), usize> = HashMap::new();
+    for offense in offenses {
+        let line = source
+            .lines
+            .get(offense.location.line.wrapping_sub(1))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        let line_hash = hex(&Sha256::digest(line.as_bytes()));
+        let occurrence = occurrences
+            .entry((offense.cop_name.clone(), line_hash.clone()))
+            .or_insert(0);
+
+        let mut hasher = Sha256::new();
+        for part in [offense.cop_name.as_str(), &path, &line_hash, &occurrence.to_string()] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        offense.fingerprint = Some(hex(&hasher.finalize()[..16]));
+        *occurrence += 1;
+    }
+}
+
+/// The path recorded for a file: relative to the working directory when
+/// inside it, with `/` separators.
+fn baseline_path(path: &Path) -> String {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| absolute.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or(absolute);
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cop::Severity;
+    use crate::offense::Location;
+    use crate::runner::FileResult;
+    use std::path::PathBuf;
+
+    fn fingerprinted(content: &str, lines: &[usize]) -> Vec<Offense> {
+        let source = SourceFile::from_string(PathBuf::from("app/a.rb"), content.to_string());
+        let mut offenses: Vec<Offense> = lines
+            .iter()
+            .map(|&line| Offense::new("Lint/Debugger", "Remove debugger.", Severity::Warning, Location::new(line, 1, 1)))
+            .collect();
+        assign_fingerprints(&mut offenses, Path::new("app/a.rb"), &source);
+        offenses
+    }
+
+    fn fingerprints(offenses: &[Offense]) -> Vec<String> {
+        offenses.iter().map(|o| o.fingerprint.clone().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_fingerprints_survive_line_shifts() {
+        let before = fingerprinted("a = 1\nbinding.pry\nbinding.pry\n", &[2, 3]);
+        let after = fingerprinted("# comment\n\na = 1\n  binding.pry\nbinding.pry  \n", &[4, 5]);
+        assert_eq!(fingerprints(&before), fingerprints(&after));
+        // Identical lines are told apart by their occurrence index.
+        assert_ne!(before[0].fingerprint, before[1].fingerprint);
+
+        let changed = fingerprinted("a = 1\nbyebug\n", &[2]);
+        assert_ne!(changed[0].fingerprint, before[0].fingerprint);
+    }
+
+    #[test]
+    fn test_filter_reports_new_and_fixed() {
+        let result = |offenses: Vec<Offense>| RunResult {
+            total_files: 1,
+            total_offenses: offenses.len(),
+            file_results: vec![FileResult {
+                path: PathBuf::from("app/a.rb"),
+                offenses,
+                source: None,
+            }],
+        };
+        let baseline = Baseline::from_result(&result(fingerprinted("binding.pry\nbyebug\n", &[1, 2])));
+        assert_eq!(baseline.len(), 2);
+
+        let mut later = result(fingerprinted("x = 1\nbinding.pry\ndebugger\n", &[2, 3]));
+        let matched = baseline.filter(&mut later);
+        assert_eq!(matched, BaselineMatch { suppressed: 1, fixed: 1 });
+        assert_eq!(later.total_offenses, 1);
+        assert_eq!(later.file_results[0].offenses[0].location.line, 3);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join("oxicop_baseline_roundtrip.json");
+        let result = RunResult {
+            total_files: 1,
+            total_offenses: 1,
+            file_results: vec![FileResult {
+                path: PathBuf::from("app/a.rb"),
+                offenses: fingerprinted("binding.pry\n", &[1]),
+                source: None,
+            }],
+        };
+        Baseline::from_result(&result).save(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded.offenses[0].path, "app/a.rb");
+        assert_eq!(loaded.offenses[0].cop_name, "Lint/Debugger");
+
+        std::fs::write(&path, "{\"version\": 9, \"offenses\": []}").unwrap();
+        assert!(Baseline::load(&path).unwrap_err().contains("unsupported baseline version 9"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
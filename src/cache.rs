@@ -0,0 +1,238 @@
+//! On-disk cache of each file's offenses.
+//!
+//! An entry is keyed by a hash of the oxicop version, the file's path and
+//! content, the cops enabled for it and the effective config of each. A
+//! file that hasn't changed since the last run under the same config is
+//! not linted again. Entries live under `<root>/oxicop_cache/<version>/`;
+//! caches of other versions are removed, and once there are more than
+//! [`MAX_FILES_IN_CACHE`] entries the least recently used half goes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+use crate::config_store::ResolvedConfig;
+use crate::offense::Offense;
+
+/// Entries kept before the least recently used are pruned, as RuboCop's
+/// `MaxFilesInCache` default.
+pub const MAX_FILES_IN_CACHE: usize = 20_000;
+
+const CACHE_DIR_NAME: &str = "oxicop_cache";
+
+/// Offenses of previous runs, stored one file per entry.
+pub struct ResultCache {
+    /// Directory of this version's entries.
+    dir: PathBuf,
+    /// Hash of each resolved config, keyed by its file.
+    config_keys: RwLock<HashMap<Option<PathBuf>, String>>,
+}
+
+impl ResultCache {
+    /// Uses the cache under `root`, by default `$XDG_CACHE_HOME` or
+    /// `~/.cache`.
+    pub fn new(root: Option<&Path>) -> Self {
+        let root = root.map(Path::to_path_buf).unwrap_or_else(default_root);
+        Self {
+            dir: root.join(CACHE_DIR_NAME).join(env!("CARGO_PKG_VERSION")),
+            config_keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The key of `path` with `content`, checked under `resolved`.
+    pub fn key(&self, resolved: &ResolvedConfig, path: &Path, content: &str) -> String {
+        let config_key = self.config_key(resolved);
+        let mut hasher = Sha256::new();
+        hasher.update(config_key.as_bytes());
+        // Offense fingerprints hold the path relative to the working directory
+        let cwd = std::env::current_dir().unwrap_or_default();
+        for part in [cwd.as_path(), path] {
+            hasher.update(part.to_string_lossy().as_bytes());
+            hasher.update([0]);
+        }
+        for cop in resolved.registry.enabled_cops_for(path) {
+            hasher.update(cop.name().as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update(content.as_bytes());
+        hex(&hasher.finalize())
+    }
+
+    /// The offenses stored under `key`, marking the entry as used.
+    pub fn load(&self, key: &str) -> Option<Vec<Offense>> {
+        let path = self.entry_path(key);
+        let offenses = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if let Ok(file) = fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(offenses)
+    }
+
+    /// Stores `offenses` under `key`. Failures are ignored; the file is
+    /// simply linted again next time.
+    pub fn save(&self, key: &str, offenses: &[Offense]) {
+        let path = self.entry_path(key);
+        let Ok(json) = serde_json::to_vec(offenses) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // Write to a temporary file first so concurrent runs never read a
+        // partial entry
+        let temp = path.with_extension(format!("tmp{}", std::process::id()));
+        if fs::write(&temp, json).is_ok() && fs::rename(&temp, &path).is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+
+    /// Removes the caches of other oxicop versions, and the least recently
+    /// used half of the entries once there are more than `max_files`.
+    pub fn prune(&self, max_files: usize) {
+        if let Some(versions) = self.dir.parent().and_then(|root| fs::read_dir(root).ok()) {
+            for version in versions.flatten() {
+                if version.path() != self.dir {
+                    let _ = fs::remove_dir_all(version.path());
+                }
+            }
+        }
+
+        let Ok(buckets) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let entries: Vec<PathBuf> = buckets
+            .flatten()
+            .filter_map(|bucket| fs::read_dir(bucket.path()).ok())
+            .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+            .collect();
+        if entries.len() <= max_files {
+            return;
+        }
+        let mut by_use: Vec<(SystemTime, PathBuf)> = entries
+            .into_iter()
+            .map(|path| {
+                let used = fs::metadata(&path).and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+                (used, path)
+            })
+            .collect();
+        by_use.sort();
+        let remove = by_use.len() / 2 + 1;
+        for (_, path) in by_use.into_iter().take(remove) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(&key[2..])
+    }
+
+    fn config_key(&self, resolved: &ResolvedConfig) -> String {
+        if let Some(key) = self.config_keys.read().unwrap().get(&resolved.path) {
+            return key.clone();
+        }
+        let key = hash_config(resolved);
+        self.config_keys
+            .write()
+            .unwrap()
+            .insert(resolved.path.clone(), key.clone());
+        key
+    }
+}
+
+/// Hashes what decides a config's offenses: the enabled cops with their
+/// severities and settings, and the target Ruby version.
+fn hash_config(resolved: &ResolvedConfig) -> String {
+    let registry = &resolved.registry;
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{:?}", registry.target_ruby_version()).as_bytes());
+    for cop in registry.enabled_cops() {
+        let name = cop.name();
+        hasher.update(name.as_bytes());
+        hasher.update(format!("{:?}", registry.severity_override(name)).as_bytes());
+        if let Some(config) = resolved.config.as_ref().and_then(|config| config.cop_config(name)) {
+            // Sort the parameters, which are held in a hash map
+            let params: BTreeMap<_, _> = config.params.iter().collect();
+            let settings = (&config.enabled, &config.severity, &config.include, &config.exclude, params);
+            hasher.update(format!("{:?}", settings).as_bytes());
+        }
+        hasher.update([0]);
+    }
+    hex(&hasher.finalize())
+}
+
+fn default_root() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_store::ConfigStore;
+    use crate::cop::Severity;
+    use crate::offense::Location;
+    use crate::registry::CopRegistry;
+
+    #[test]
+    fn test_key_load_and_save() {
+        let root = std::env::temp_dir().join("oxicop_result_cache");
+        let _ = fs::remove_dir_all(&root);
+        let cache = ResultCache::new(Some(&root));
+        let store = ConfigStore::fixed(CopRegistry::new());
+        let resolved = store.for_file(Path::new("a.rb")).unwrap();
+
+        let key = cache.key(&resolved, Path::new("a.rb"), "x = 1\n");
+        assert_eq!(key, cache.key(&resolved, Path::new("a.rb"), "x = 1\n"));
+        assert_ne!(key, cache.key(&resolved, Path::new("a.rb"), "x = 2\n"));
+        assert_ne!(key, cache.key(&resolved, Path::new("b.rb"), "x = 1\n"));
+
+        let mut registry = CopRegistry::new();
+        registry.disable("Layout/TrailingWhitespace");
+        let other = ConfigStore::fixed(registry);
+        let other = other.for_file(Path::new("a.rb")).unwrap();
+        assert_ne!(key, ResultCache::new(Some(&root)).key(&other, Path::new("a.rb"), "x = 1\n"));
+
+        assert!(cache.load(&key).is_none());
+        let offense = Offense::new("Lint/Debugger", "Remove debugger.", Severity::Warning, Location::new(2, 3, 4));
+        cache.save(&key, &[offense]);
+        let loaded = cache.load(&key).unwrap();
+        assert_eq!(loaded[0].cop_name, "Lint/Debugger");
+        assert_eq!(loaded[0].location, Location::new(2, 3, 4));
+        assert_eq!(loaded[0].severity, Severity::Warning);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_prune() {
+        let root = std::env::temp_dir().join("oxicop_result_cache_prune");
+        let _ = fs::remove_dir_all(&root);
+        let old_version = root.join(CACHE_DIR_NAME).join("0.0.1");
+        fs::create_dir_all(&old_version).unwrap();
+
+        let cache = ResultCache::new(Some(&root));
+        let keys: Vec<String> = (0..4).map(|i| hex(&Sha256::digest([i]))).collect();
+        for key in &keys {
+            cache.save(key, &[]);
+        }
+        cache.load(&keys[0]);
+
+        cache.prune(3);
+        assert!(!old_version.exists());
+        let left = keys.iter().filter(|key| cache.entry_path(key).exists()).count();
+        assert_eq!(left, 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -4,7 +4,11 @@ use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
 
 /// Main configuration structure matching RuboCop's format.
 #[derive(Debug, Default, Deserialize)]
@@ -13,40 +17,203 @@ pub struct Config {
     pub all_cops: Option<AllCopsConfig>,
     #[serde(flatten)]
     pub cops: HashMap<String, CopConfig>,
+    /// Directory of the file this config was loaded from; relative
+    /// Include/Exclude patterns are resolved against it.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
 }
 
 /// Global configuration under the AllCops key.
 #[derive(Debug, Deserialize)]
 pub struct AllCopsConfig {
+    #[serde(rename = "Include")]
+    pub include: Option<Vec<String>>,
     #[serde(rename = "Exclude")]
     pub exclude: Option<Vec<String>>,
     #[serde(rename = "TargetRubyVersion")]
     pub target_ruby_version: Option<f64>,
+    /// Disables every cop not explicitly enabled by its own or its
+    /// department's section.
+    #[serde(rename = "DisabledByDefault")]
+    pub disabled_by_default: Option<bool>,
+    /// Enables every cop not explicitly disabled.
+    #[serde(rename = "EnabledByDefault")]
+    pub enabled_by_default: Option<bool>,
+    /// What to do with cops whose default is `Enabled: pending`.
+    #[serde(rename = "NewCops")]
+    pub new_cops: Option<NewCops>,
+}
+
+/// The values of `AllCops: NewCops`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewCops {
+    Enable,
+    Disable,
+    /// Leave pending cops off and list them in a notice.
+    Pending,
+}
+
+/// A cop's `Enabled` setting: `true`, `false` or `pending`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Value")]
+pub enum CopState {
+    Enabled,
+    Disabled,
+    /// Added since the last major release; `AllCops: NewCops` decides.
+    Pending,
+}
+
+impl TryFrom<Value> for CopState {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Bool(true) => Ok(CopState::Enabled),
+            Value::Bool(false) => Ok(CopState::Disabled),
+            Value::String(s) if s == "pending" => Ok(CopState::Pending),
+            other => Err(format!(
+                "`Enabled` must be true, false or pending, got `{}`",
+                serde_yaml::to_string(&other).unwrap_or_default().trim_end()
+            )),
+        }
+    }
 }
 
 /// Per-cop configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct CopConfig {
     #[serde(rename = "Enabled")]
-    pub enabled: Option<bool>,
+    pub enabled: Option<CopState>,
     #[serde(rename = "Severity")]
     pub severity: Option<String>,
+    #[serde(rename = "Include")]
+    pub include: Option<Vec<String>>,
+    #[serde(rename = "Exclude")]
+    pub exclude: Option<Vec<String>>,
+    /// Cop-specific parameters such as `Max` or `EnforcedStyle`.
+    #[serde(flatten)]
+    pub params: HashMap<String, serde_yaml::Value>,
+}
+
+impl CopConfig {
+    /// Returns a raw parameter value.
+    pub fn param(&self, key: &str) -> Option<&serde_yaml::Value> {
+        self.params.get(key)
+    }
+
+    /// Reads a non-negative integer parameter.
+    pub fn usize_param(&self, key: &str) -> Result<Option<usize>, String> {
+        self.typed_param(key, "a non-negative integer", |v| {
+            v.as_u64().and_then(|n| usize::try_from(n).ok())
+        })
+    }
+
+    /// Reads a boolean parameter.
+    pub fn bool_param(&self, key: &str) -> Result<Option<bool>, String> {
+        self.typed_param(key, "true or false", serde_yaml::Value::as_bool)
+    }
+
+    /// Reads a string parameter.
+    pub fn str_param(&self, key: &str) -> Result<Option<&str>, String> {
+        self.typed_param(key, "a string", serde_yaml::Value::as_str)
+    }
+
+    /// Reads a list of strings. A `~` value reads as an empty list.
+    pub fn string_list_param(&self, key: &str) -> Result<Option<Vec<String>>, String> {
+        self.typed_param(key, "a list of strings", |v| {
+            if v.is_null() {
+                return Some(Vec::new());
+            }
+            v.as_sequence()?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+    }
+
+    /// `Max`: the largest allowed value of the cop's metric.
+    pub fn max(&self) -> Result<Option<usize>, String> {
+        self.usize_param("Max")
+    }
+
+    /// `EnforcedStyle`, checked against the styles the cop supports.
+    pub fn enforced_style(&self, supported: &[&str]) -> Result<Option<&str>, String> {
+        match self.str_param("EnforcedStyle")? {
+            Some(style) if !supported.contains(&style) => Err(format!(
+                "invalid EnforcedStyle `{}`; supported styles are {}",
+                style,
+                supported.join(", ")
+            )),
+            style => Ok(style),
+        }
+    }
+
+    /// `AllowedMethods`: method names the cop ignores.
+    pub fn allowed_methods(&self) -> Result<Vec<String>, String> {
+        Ok(self.string_list_param("AllowedMethods")?.unwrap_or_default())
+    }
+
+    /// `AllowedPatterns`: regular expressions for code the cop ignores.
+    pub fn allowed_patterns(&self) -> Result<Vec<Regex>, String> {
+        self.string_list_param("AllowedPatterns")?
+            .unwrap_or_default()
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| format!("invalid AllowedPatterns entry `{}`: {}", pattern, e))
+            })
+            .collect()
+    }
+
+    /// `CountComments`: whether comment lines count towards a length.
+    pub fn count_comments(&self) -> Result<Option<bool>, String> {
+        self.bool_param("CountComments")
+    }
+
+    fn typed_param<'a, T>(
+        &'a self,
+        key: &str,
+        expected: &str,
+        read: impl FnOnce(&'a serde_yaml::Value) -> Option<T>,
+    ) -> Result<Option<T>, String> {
+        let Some(value) = self.params.get(key) else {
+            return Ok(None);
+        };
+        read(value).map(Some).ok_or_else(|| {
+            let shown = serde_yaml::to_string(value).unwrap_or_default();
+            format!("`{}` must be {}, got `{}`", key, expected, shown.trim())
+        })
+    }
 }
 
 impl Config {
-    /// Loads configuration from a specific file.
+    /// Loads configuration from a specific file, along with the files it
+    /// names in `inherit_from`.
     pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
+        Self::load(path, false)
+    }
+
+    /// Like [`Config::from_file`], but leaves [`TODO_FILE_NAME`] out of the
+    /// `inherit_from` chain, so offenses it currently hides are found again
+    /// when regenerating it.
+    pub fn from_file_without_todo(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load(path, true)
+    }
+
+    fn load(path: &Path, skip_todo: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let merged = load_with_inheritance(path, None, &mut Vec::new(), skip_todo)?;
+        let mut config: Config = serde_yaml::from_value(Value::Mapping(merged))?;
+        config.base_dir = path_parameters_base_dir(path, None)?;
         Ok(config)
     }
 
     /// Searches up the directory tree for .rubocop.yml and loads it.
     /// Starts from the current working directory.
-    pub fn find_and_load() -> Option<Self> {
-        let current_dir = env::current_dir().ok()?;
+    pub fn find_and_load() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let current_dir = env::current_dir()?;
         Self::find_config_file(&current_dir)
-            .and_then(|path| Self::from_file(&path).ok())
+            .map(|path| Self::from_file(&path))
+            .transpose()
     }
 
     /// Searches for .rubocop.yml starting from the given directory and going up.
@@ -68,14 +235,453 @@ impl Config {
         None
     }
 
-    /// Checks if a cop is explicitly enabled in the config.
+    /// Checks if the config enables or disables a cop. The cop's own
+    /// `Enabled` wins over a disabled department, which wins over the
+    /// AllCops `DisabledByDefault`/`EnabledByDefault` baseline. `None` when
+    /// the config leaves the cop at its default.
     pub fn is_cop_enabled(&self, cop_name: &str) -> Option<bool> {
-        self.cops.get(cop_name)?.enabled
+        self.explicit_state(cop_name).map(|state| self.resolve(state))
+    }
+
+    /// Checks if a cop runs under this config, falling back to its
+    /// built-in default. `None` for cops without a default.
+    pub fn resolve_enabled(&self, cop_name: &str) -> Option<bool> {
+        self.state(cop_name).map(|state| self.resolve(state))
+    }
+
+    /// Checks if a cop is left pending: neither the config nor
+    /// `AllCops: NewCops` decides whether it runs.
+    pub fn is_cop_pending(&self, cop_name: &str) -> bool {
+        self.state(cop_name) == Some(CopState::Pending)
+            && !matches!(self.new_cops(), Some(NewCops::Enable | NewCops::Disable))
+    }
+
+    /// Returns a cop's section with the built-in default parameters filled
+    /// in. Map parameters such as `DebuggerMethods` merge key by key.
+    pub fn cop_config(&self, cop_name: &str) -> Option<CopConfig> {
+        let defaults = default_cop_config(cop_name);
+        let Some(own) = self.cops.get(cop_name) else {
+            return defaults.cloned();
+        };
+        let mut merged = own.clone();
+        for (key, default) in defaults.iter().flat_map(|d| &d.params) {
+            match (merged.params.get_mut(key), default) {
+                (None, _) => {
+                    merged.params.insert(key.clone(), default.clone());
+                }
+                (Some(Value::Mapping(value)), Value::Mapping(default)) => {
+                    for (k, v) in default {
+                        if !value.contains_key(k) {
+                            value.insert(k.clone(), v.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(merged)
+    }
+
+    fn new_cops(&self) -> Option<NewCops> {
+        self.all_cops.as_ref()?.new_cops
     }
 
-    /// Gets the severity override for a cop, if any.
+    /// The `Enabled` state this config sets for a cop, if any. As in
+    /// RuboCop, a department can only turn its cops off; enabling it under
+    /// `DisabledByDefault` brings back its cops' built-in defaults.
+    fn explicit_state(&self, cop_name: &str) -> Option<CopState> {
+        if let Some(state) = self.cops.get(cop_name).and_then(|cop| cop.enabled) {
+            return Some(state);
+        }
+        let department = self.department_config(cop_name).and_then(|department| department.enabled);
+        if department == Some(CopState::Disabled) {
+            return Some(CopState::Disabled);
+        }
+        let all_cops = self.all_cops.as_ref()?;
+        if all_cops.disabled_by_default == Some(true) {
+            (department != Some(CopState::Enabled)).then_some(CopState::Disabled)
+        } else if all_cops.enabled_by_default == Some(true) {
+            Some(CopState::Enabled)
+        } else {
+            None
+        }
+    }
+
+    fn state(&self, cop_name: &str) -> Option<CopState> {
+        self.explicit_state(cop_name)
+            .or_else(|| default_cop_config(cop_name)?.enabled)
+    }
+
+    fn resolve(&self, state: CopState) -> bool {
+        match state {
+            CopState::Enabled => true,
+            CopState::Disabled => false,
+            CopState::Pending => self.new_cops() == Some(NewCops::Enable),
+        }
+    }
+
+    /// Gets the severity override for a cop, if any, falling back to its
+    /// department's.
     pub fn cop_severity(&self, cop_name: &str) -> Option<&str> {
-        self.cops.get(cop_name)?.severity.as_deref()
+        self.cops
+            .get(cop_name)
+            .and_then(|cop| cop.severity.as_deref())
+            .or_else(|| self.department_config(cop_name)?.severity.as_deref())
+    }
+
+    /// Builds the file filter for a cop's Include/Exclude patterns, if it or
+    /// its department has any. The cop's own lists replace the department's.
+    pub fn cop_file_filter(&self, cop_name: &str) -> Option<Result<FileFilter, globset::Error>> {
+        let cop = self.cops.get(cop_name);
+        let department = self.department_config(cop_name);
+        let patterns = |get: fn(&CopConfig) -> Option<&Vec<String>>| {
+            cop.and_then(get).or_else(|| department.and_then(get))
+        };
+        let include = patterns(|c| c.include.as_ref());
+        let exclude = patterns(|c| c.exclude.as_ref());
+        if include.is_none() && exclude.is_none() {
+            return None;
+        }
+        Some(FileFilter::new(
+            &self.base_dir,
+            include.map(Vec::as_slice).unwrap_or_default(),
+            exclude.map(Vec::as_slice).unwrap_or_default(),
+        ))
+    }
+
+    /// Returns the section of the department a cop belongs to, such as
+    /// `Metrics` for `Metrics/AbcSize`. Nested departments are looked up
+    /// innermost first.
+    fn department_config(&self, cop_name: &str) -> Option<&CopConfig> {
+        let mut name = cop_name;
+        while let Some((department, _)) = name.rsplit_once('/') {
+            if let Some(config) = self.cops.get(department) {
+                return Some(config);
+            }
+            name = department;
+        }
+        None
+    }
+
+    /// Builds the file filter for the AllCops Include/Exclude patterns,
+    /// each list falling back to the built-in one when not configured.
+    pub fn file_filter(&self) -> Result<FileFilter, globset::Error> {
+        let pattern_list = |list: fn(&AllCopsConfig) -> Option<&[String]>| {
+            self.all_cops
+                .as_ref()
+                .and_then(list)
+                .or_else(|| DEFAULTS.all_cops.as_ref().and_then(list))
+                .unwrap_or_default()
+        };
+        let include = pattern_list(|a| a.include.as_deref());
+        let exclude = pattern_list(|a| a.exclude.as_deref());
+        FileFilter::new(&self.base_dir, include, exclude)
+    }
+}
+
+/// The built-in config, following RuboCop's `default.yml`.
+static DEFAULTS: Lazy<Config> = Lazy::new(|| {
+    serde_yaml::from_str(include_str!("../config/default.yml")).expect("config/default.yml is valid")
+});
+
+/// Returns the built-in default section of a cop.
+pub fn default_cop_config(cop_name: &str) -> Option<&'static CopConfig> {
+    DEFAULTS.cops.get(cop_name)
+}
+
+/// The file filter of the built-in AllCops Include/Exclude patterns, for
+/// files no config governs. Patterns are relative to the working directory.
+pub fn default_file_filter() -> Result<FileFilter, String> {
+    let base_dir = env::current_dir().map_err(|e| e.to_string())?;
+    let all_cops = DEFAULTS.all_cops.as_ref();
+    let include = all_cops.and_then(|a| a.include.as_deref()).unwrap_or_default();
+    let exclude = all_cops.and_then(|a| a.exclude.as_deref()).unwrap_or_default();
+    FileFilter::new(&base_dir, include, exclude).map_err(|e| e.to_string())
+}
+
+/// Include/Exclude glob patterns, matched like RuboCop's `File.fnmatch?`
+/// with `FNM_PATHNAME | FNM_EXTGLOB`: `*` stays within one path component,
+/// `**/` spans directories and `{a,b}` alternates. Relative patterns are
+/// anchored at the directory of the config that declared them.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+    /// Directory prefixes of `dir/**` style excludes, used to prune walks.
+    excluded_dirs: GlobSet,
+}
+
+impl FileFilter {
+    /// Compiles patterns relative to `base_dir`.
+    pub fn new(base_dir: &Path, include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        let mut dirs = GlobSetBuilder::new();
+        for pattern in exclude {
+            let prefix = pattern
+                .strip_suffix("/**/*")
+                .or_else(|| pattern.strip_suffix("/**"));
+            if let Some(prefix) = prefix {
+                dirs.add(compile(base_dir, prefix)?);
+            }
+        }
+        Ok(Self {
+            include: compile_set(base_dir, include)?,
+            exclude: compile_set(base_dir, exclude)?,
+            excluded_dirs: dirs.build()?,
+        })
+    }
+
+    /// Whether `path` matches an Include pattern.
+    pub fn is_included(&self, path: &Path) -> bool {
+        absolute(path).is_some_and(|p| self.include.is_match(p))
+    }
+
+    /// Whether `path` matches an Exclude pattern.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        absolute(path).is_some_and(|p| self.exclude.is_match(p))
+    }
+
+    /// Whether `path` is in scope: it matches an Include pattern (or there
+    /// are none) and no Exclude pattern.
+    pub fn matches(&self, path: &Path) -> bool {
+        (self.include.is_empty() || self.is_included(path)) && !self.is_excluded(path)
+    }
+
+    /// Whether everything under the directory `dir` is excluded.
+    pub fn is_excluded_dir(&self, dir: &Path) -> bool {
+        absolute(dir).is_some_and(|p| self.excluded_dirs.is_match(p))
+    }
+}
+
+fn absolute(path: &Path) -> Option<PathBuf> {
+    std::path::absolute(path).ok()
+}
+
+/// Makes a relative glob absolute by prefixing the escaped `base_dir`.
+fn anchor_pattern(base_dir: &Path, pattern: &str) -> String {
+    if Path::new(pattern).is_absolute() {
+        pattern.to_string()
+    } else {
+        let base = globset::escape(&base_dir.to_string_lossy());
+        format!("{}/{}", base.trim_end_matches('/'), pattern.trim_start_matches("./"))
+    }
+}
+
+fn compile(base_dir: &Path, pattern: &str) -> Result<globset::Glob, globset::Error> {
+    GlobBuilder::new(&anchor_pattern(base_dir, pattern))
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()
+}
+
+fn compile_set(base_dir: &Path, patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(compile(base_dir, pattern)?);
+    }
+    builder.build()
+}
+
+/// The file `--auto-gen-config` writes, conventionally inherited by
+/// `.rubocop.yml`.
+pub const TODO_FILE_NAME: &str = ".rubocop_todo.yml";
+
+/// Top-level keys that configure loading rather than a cop.
+const LOADER_KEYS: &[&str] = &["inherit_from", "inherit_mode", "inherit_gem", "require", "plugins"];
+
+/// The directory the `Include`/`Exclude` patterns of the config file at
+/// `path` are relative to. As in RuboCop, that is the file's own directory
+/// for files named `.rubocop*`. Other files, such as a shared
+/// `config/rubocop/base.yml`, use the directory of the config inheriting
+/// them, or the working directory when loaded directly.
+fn path_parameters_base_dir(path: &Path, inheriting: Option<&Path>) -> std::io::Result<PathBuf> {
+    let is_dotfile = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with(".rubocop"));
+    if is_dotfile {
+        let path = std::path::absolute(path)?;
+        Ok(path.parent().unwrap_or(Path::new("/")).to_path_buf())
+    } else {
+        match inheriting {
+            Some(dir) => Ok(dir.to_path_buf()),
+            None => env::current_dir(),
+        }
+    }
+}
+
+/// Reads `path` and the files it inherits from into one mapping. Inherited
+/// files are merged in order, each overriding the previous, and `path`
+/// itself is merged last. `inheriting` is the base directory of the file
+/// that inherits `path`, if any. `chain` holds the files being loaded, to
+/// report cycles. With `skip_todo`, inherited [`TODO_FILE_NAME`] files are
+/// left out.
+fn load_with_inheritance(
+    path: &Path,
+    inheriting: Option<&Path>,
+    chain: &mut Vec<PathBuf>,
+    skip_todo: bool,
+) -> Result<Mapping, Box<dyn std::error::Error>> {
+    let canonical = std::fs::canonicalize(path)
+        .map_err(|e| format!("cannot read config file {}: {}", path.display(), e))?;
+    if let Some(start) = chain.iter().position(|p| *p == canonical) {
+        let cycle: Vec<String> = chain[start..]
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(format!("circular inherit_from: {}", cycle.join(" -> ")).into());
+    }
+
+    let content = std::fs::read_to_string(&canonical)?;
+    let mut own = match serde_yaml::from_str::<Value>(&content)? {
+        Value::Null => Mapping::new(),
+        Value::Mapping(mapping) => mapping,
+        _ => return Err(format!("{}: expected a mapping at the top level", path.display()).into()),
+    };
+    // Anchor at the path as written rather than the canonical one, which
+    // is how discovered files are matched.
+    let dir = std::path::absolute(path)?
+        .parent()
+        .unwrap_or(Path::new("/"))
+        .to_path_buf();
+    let base_dir = path_parameters_base_dir(path, inheriting)?;
+    anchor_path_patterns(&mut own, &base_dir);
+
+    let parents = match own.get("inherit_from") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(file)) => vec![file.clone()],
+        Some(Value::Sequence(files)) => files
+            .iter()
+            .map(|f| f.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("{}: inherit_from must list file paths", path.display()))?,
+        Some(_) => return Err(format!("{}: inherit_from must list file paths", path.display()).into()),
+    };
+
+    chain.push(canonical);
+    let mut merged = Mapping::new();
+    for parent in parents {
+        if parent.starts_with("http://") || parent.starts_with("https://") {
+            eprintln!("Warning: remote inherit_from is not supported, skipping {}", parent);
+            continue;
+        }
+        if skip_todo && Path::new(&parent).file_name().is_some_and(|name| name == TODO_FILE_NAME) {
+            continue;
+        }
+        let inherited = load_with_inheritance(&dir.join(&parent), Some(&base_dir), chain, skip_todo)?;
+        merge_config(&mut merged, inherited);
+    }
+    chain.pop();
+
+    let own_mode = own.get("inherit_mode").cloned();
+    merge_config(&mut merged, own);
+    if inheriting.is_none() {
+        // The built-in AllCops patterns sit under everything, so
+        // `inherit_mode: merge` adds to them rather than replacing them
+        let mut defaults = default_path_patterns(&base_dir);
+        if let Some(mode) = own_mode {
+            merged.insert(Value::from("inherit_mode"), mode);
+        }
+        merge_config(&mut defaults, merged);
+        merged = defaults;
+    }
+    for key in LOADER_KEYS {
+        merged.remove(*key);
+    }
+    for (_, section) in merged.iter_mut() {
+        if let Value::Mapping(section) = section {
+            section.remove("inherit_mode");
+        }
+    }
+    Ok(merged)
+}
+
+/// The built-in AllCops Include/Exclude patterns, anchored at `dir`.
+fn default_path_patterns(dir: &Path) -> Mapping {
+    let mut all_cops = Mapping::new();
+    let defaults = DEFAULTS.all_cops.as_ref();
+    for (key, patterns) in [
+        ("Include", defaults.and_then(|a| a.include.as_ref())),
+        ("Exclude", defaults.and_then(|a| a.exclude.as_ref())),
+    ] {
+        if let Some(patterns) = patterns {
+            let patterns = patterns.iter().map(|p| Value::from(anchor_pattern(dir, p))).collect();
+            all_cops.insert(Value::from(key), Value::Sequence(patterns));
+        }
+    }
+    let mut config = Mapping::new();
+    config.insert(Value::from("AllCops"), Value::Mapping(all_cops));
+    config
+}
+
+/// Anchors the relative Include/Exclude globs of every section at `dir`, so
+/// they keep pointing at the same files once merged into another config.
+fn anchor_path_patterns(config: &mut Mapping, dir: &Path) {
+    for (_, section) in config.iter_mut() {
+        let Value::Mapping(section) = section else {
+            continue;
+        };
+        for key in ["Include", "Exclude"] {
+            if let Some(Value::Sequence(patterns)) = section.get_mut(key) {
+                for pattern in patterns.iter_mut() {
+                    if let Value::String(p) = pattern {
+                        *p = anchor_pattern(dir, p);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parameter names listed under `inherit_mode: <mode>`.
+fn inherit_mode_keys(section: &Mapping, mode: &str) -> Vec<String> {
+    section
+        .get("inherit_mode")
+        .and_then(|m| m.get(mode))
+        .and_then(Value::as_sequence)
+        .map(|keys| keys.iter().filter_map(|k| k.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Merges `overrides` into `base` the way RuboCop does: cop sections merge
+/// key by key with later values winning, except that array parameters named
+/// in `inherit_mode: merge` (at the top level or in the section) are
+/// concatenated. A section's `inherit_mode: override` opts back out.
+fn merge_config(base: &mut Mapping, overrides: Mapping) {
+    let global_merge = inherit_mode_keys(&overrides, "merge");
+    for (key, value) in overrides {
+        if key.as_str() == Some("inherit_mode") {
+            continue;
+        }
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(existing)), Value::Mapping(section)) => {
+                let opt_out = inherit_mode_keys(&section, "override");
+                let mut merge: Vec<String> = global_merge
+                    .iter()
+                    .filter(|k| !opt_out.contains(k))
+                    .cloned()
+                    .collect();
+                merge.extend(inherit_mode_keys(&section, "merge"));
+
+                for (param, value) in section {
+                    let merges = param.as_str().is_some_and(|p| merge.iter().any(|m| m == p));
+                    match (existing.get_mut(&param), value) {
+                        (Some(Value::Sequence(old)), Value::Sequence(new)) if merges => {
+                            for item in new {
+                                if !old.contains(&item) {
+                                    old.push(item);
+                                }
+                            }
+                        }
+                        (_, value) => {
+                            existing.insert(param, value);
+                        }
+                    }
+                }
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
     }
 }
 
@@ -167,4 +773,431 @@ Layout/TrailingWhitespace:
         assert!(config.all_cops.is_none());
         assert!(config.cops.is_empty());
     }
+
+    #[test]
+    fn test_file_filter_globs_are_relative_to_config_dir() {
+        let base = Path::new("/project");
+        let exclude = vec!["vendor/**/*".to_string(), "db/schema.rb".to_string(), "lib/*.rb".to_string()];
+        let filter = FileFilter::new(base, &[], &exclude).unwrap();
+
+        assert!(filter.is_excluded(Path::new("/project/vendor/a.rb")));
+        assert!(filter.is_excluded(Path::new("/project/vendor/bundle/gems/x.rb")));
+        assert!(filter.is_excluded(Path::new("/project/db/schema.rb")));
+        assert!(filter.is_excluded(Path::new("/project/lib/a.rb")));
+        // `*` does not cross directory boundaries.
+        assert!(!filter.is_excluded(Path::new("/project/lib/nested/a.rb")));
+        assert!(!filter.is_excluded(Path::new("/project/app/vendor/a.rb")));
+        assert!(!filter.is_excluded(Path::new("/other/db/schema.rb")));
+
+        assert!(filter.is_excluded_dir(Path::new("/project/vendor")));
+        assert!(!filter.is_excluded_dir(Path::new("/project/lib")));
+    }
+
+    #[test]
+    fn test_file_filter_include_with_alternation() {
+        let include = vec!["**/{Gemfile,Rakefile}".to_string(), "**/*.rake".to_string()];
+        let filter = FileFilter::new(Path::new("/project"), &include, &[]).unwrap();
+
+        assert!(filter.is_included(Path::new("/project/Gemfile")));
+        assert!(filter.is_included(Path::new("/project/sub/Rakefile")));
+        assert!(filter.is_included(Path::new("/project/lib/tasks/db.rake")));
+        assert!(!filter.is_included(Path::new("/project/README.md")));
+    }
+
+    #[test]
+    fn test_file_filter_falls_back_to_default_patterns() {
+        let config = Config {
+            base_dir: PathBuf::from("/project"),
+            ..Config::default()
+        };
+        let filter = config.file_filter().unwrap();
+        assert!(filter.is_included(Path::new("/project/Gemfile")));
+        assert!(filter.is_included(Path::new("/project/app.gemspec")));
+        assert!(!filter.is_included(Path::new("/project/README.md")));
+        assert!(filter.is_excluded(Path::new("/project/vendor/bundle/a.rb")));
+        assert!(filter.is_excluded_dir(Path::new("/project/node_modules")));
+
+        // A configured Exclude replaces the default one; Include still applies
+        let mut config: Config = serde_yaml::from_str("AllCops:\n  Exclude: ['db/schema.rb']\n").unwrap();
+        config.base_dir = PathBuf::from("/project");
+        let filter = config.file_filter().unwrap();
+        assert!(filter.is_excluded(Path::new("/project/db/schema.rb")));
+        assert!(!filter.is_excluded(Path::new("/project/vendor/bundle/a.rb")));
+        assert!(filter.is_included(Path::new("/project/Rakefile")));
+
+        // Under `inherit_mode: merge`, it's added to the default one instead
+        for content in [
+            "inherit_mode:\n  merge: [Exclude]\nAllCops:\n  Exclude: ['db/schema.rb']\n",
+            "AllCops:\n  inherit_mode:\n    merge: [Exclude]\n  Exclude: ['db/schema.rb']\n",
+        ] {
+            let dir = write_tree("oxicop_default_patterns_merge", &[(".rubocop.yml", content)]);
+            let filter = Config::from_file(&dir.join(".rubocop.yml")).unwrap().file_filter().unwrap();
+            assert!(filter.is_excluded(&dir.join("db/schema.rb")));
+            assert!(filter.is_excluded(&dir.join("vendor/a.rb")));
+            assert!(filter.is_included(&dir.join("Rakefile")));
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        let dir = write_tree("oxicop_default_patterns_merge", &[(".rubocop.yml", "AllCops:\n  Exclude: ['db/schema.rb']\n")]);
+        let filter = Config::from_file(&dir.join(".rubocop.yml")).unwrap().file_filter().unwrap();
+        assert!(filter.is_excluded(&dir.join("db/schema.rb")));
+        assert!(!filter.is_excluded(&dir.join("vendor/a.rb")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_filter_invalid_pattern() {
+        assert!(FileFilter::new(Path::new("/project"), &["a/{b".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_from_file_sets_base_dir() {
+        let dir = std::env::temp_dir().join("oxicop_config_base_dir");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".rubocop.yml");
+        std::fs::write(&path, "AllCops:\n  Include:\n    - '**/*.rake'\n").unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.base_dir, dir);
+        assert!(config.file_filter().unwrap().is_included(&dir.join("tasks/x.rake")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cop_include_exclude() {
+        let yaml = r#"
+Metrics/BlockLength:
+  Exclude:
+    - 'spec/**/*'
+Rails/Foo:
+  Include:
+    - 'app/models/**/*.rb'
+Style/StringLiterals:
+  Enabled: true
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.base_dir = PathBuf::from("/project");
+
+        let block = config.cop_file_filter("Metrics/BlockLength").unwrap().unwrap();
+        assert!(!block.matches(Path::new("/project/spec/models/user_spec.rb")));
+        assert!(block.matches(Path::new("/project/app/models/user.rb")));
+
+        let rails = config.cop_file_filter("Rails/Foo").unwrap().unwrap();
+        assert!(rails.matches(Path::new("/project/app/models/user.rb")));
+        assert!(!rails.matches(Path::new("/project/lib/user.rb")));
+
+        assert!(config.cop_file_filter("Style/StringLiterals").is_none());
+        assert!(config.cop_file_filter("Layout/Missing").is_none());
+    }
+
+    #[test]
+    fn test_department_settings() {
+        let yaml = r#"
+Metrics:
+  Enabled: false
+  Severity: error
+  Exclude:
+    - 'spec/**/*'
+Metrics/AbcSize:
+  Enabled: true
+Metrics/BlockLength:
+  Exclude:
+    - 'config/**/*'
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.base_dir = PathBuf::from("/project");
+
+        assert_eq!(config.is_cop_enabled("Metrics/MethodLength"), Some(false));
+        assert_eq!(config.is_cop_enabled("Metrics/AbcSize"), Some(true));
+        assert_eq!(config.is_cop_enabled("Style/StringLiterals"), None);
+        assert_eq!(config.cop_severity("Metrics/AbcSize"), Some("error"));
+
+        let method = config.cop_file_filter("Metrics/MethodLength").unwrap().unwrap();
+        assert!(!method.matches(Path::new("/project/spec/a_spec.rb")));
+        // A cop's own list replaces the department's.
+        let block = config.cop_file_filter("Metrics/BlockLength").unwrap().unwrap();
+        assert!(block.matches(Path::new("/project/spec/a_spec.rb")));
+        assert!(!block.matches(Path::new("/project/config/routes.rb")));
+    }
+
+    #[test]
+    fn test_every_cop_has_a_default() {
+        let names: Vec<String> = crate::cops::all_cops().iter().map(|c| c.name().to_string()).collect();
+        for name in &names {
+            let default = default_cop_config(name).unwrap_or_else(|| panic!("{} has no default", name));
+            assert!(default.enabled.is_some(), "{} has no default Enabled", name);
+        }
+        for name in DEFAULTS.cops.keys() {
+            assert!(names.contains(name), "default config names unknown cop {}", name);
+        }
+    }
+
+    #[test]
+    fn test_default_states_and_new_cops() {
+        let config = Config::default();
+        assert_eq!(config.resolve_enabled("Layout/TrailingWhitespace"), Some(true));
+        assert_eq!(config.resolve_enabled("Style/Copyright"), Some(false));
+        assert_eq!(config.resolve_enabled("Style/FileRead"), Some(false));
+        assert!(config.is_cop_pending("Style/FileRead"));
+        assert!(!config.is_cop_pending("Style/Copyright"));
+        assert_eq!(config.is_cop_enabled("Style/Copyright"), None);
+
+        let config: Config = serde_yaml::from_str("AllCops:\n  NewCops: enable\nStyle/Copyright:\n  Enabled: true\n").unwrap();
+        assert_eq!(config.resolve_enabled("Style/FileRead"), Some(true));
+        assert_eq!(config.resolve_enabled("Style/Copyright"), Some(true));
+        assert!(!config.is_cop_pending("Style/FileRead"));
+
+        let config: Config = serde_yaml::from_str("Style/FileRead:\n  Enabled: false\n").unwrap();
+        assert!(!config.is_cop_pending("Style/FileRead"));
+        assert!(config.is_cop_pending("Style/FileWrite"));
+
+        assert!(serde_yaml::from_str::<Config>("AllCops:\n  NewCops: sometimes\n").is_err());
+        let err = serde_yaml::from_str::<Config>("Style/FileRead:\n  Enabled: maybe\n").unwrap_err();
+        assert!(err.to_string().contains("`Enabled` must be true, false or pending, got `maybe`"), "{}", err);
+    }
+
+    #[test]
+    fn test_cop_config_fills_in_defaults() {
+        let yaml = r#"
+Metrics/MethodLength:
+  Max: 20
+Lint/Debugger:
+  DebuggerMethods:
+    Pry: ~
+    Custom:
+      - halt!
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let method = config.cop_config("Metrics/MethodLength").unwrap();
+        assert_eq!(method.max().unwrap(), Some(20));
+        assert_eq!(method.count_comments().unwrap(), Some(false));
+
+        let debugger = config.cop_config("Lint/Debugger").unwrap();
+        let groups = debugger.param("DebuggerMethods").unwrap().as_mapping().unwrap();
+        assert!(groups.get("Pry").unwrap().is_null());
+        assert!(groups.contains_key("Custom") && groups.contains_key("Byebug"));
+
+        assert_eq!(config.cop_config("Metrics/AbcSize").unwrap().max().unwrap(), Some(17));
+        assert!(config.cop_config("Nope/Nothing").is_none());
+    }
+
+    #[test]
+    fn test_disabled_and_enabled_by_default() {
+        let yaml = r#"
+AllCops:
+  DisabledByDefault: true
+Lint:
+  Enabled: true
+Style/StringLiterals:
+  Enabled: true
+Lint/Debugger:
+  Enabled: false
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.is_cop_enabled("Layout/TrailingWhitespace"), Some(false));
+        assert_eq!(config.is_cop_enabled("Style/NegatedIf"), Some(false));
+        assert_eq!(config.is_cop_enabled("Style/StringLiterals"), Some(true));
+        // An enabled department brings back its cops' own defaults
+        assert_eq!(config.is_cop_enabled("Lint/UselessAssignment"), None);
+        assert_eq!(config.resolve_enabled("Lint/UselessAssignment"), Some(true));
+        assert_eq!(config.resolve_enabled("Lint/ConstantResolution"), Some(false));
+        assert_eq!(config.is_cop_enabled("Lint/Debugger"), Some(false));
+
+        let config: Config =
+            serde_yaml::from_str("AllCops:\n  EnabledByDefault: true\nStyle:\n  Enabled: false\n").unwrap();
+        assert_eq!(config.is_cop_enabled("Layout/TrailingWhitespace"), Some(true));
+        assert_eq!(config.is_cop_enabled("Style/StringLiterals"), Some(false));
+    }
+
+    #[test]
+    fn test_enabled_department_keeps_cop_defaults() {
+        let config: Config = serde_yaml::from_str("Style:\n  Enabled: true\n").unwrap();
+        assert_eq!(config.resolve_enabled("Style/Copyright"), Some(false));
+        assert_eq!(config.resolve_enabled("Style/NegatedIf"), Some(true));
+        assert!(config.is_cop_pending("Style/FileRead"));
+
+        let config: Config =
+            serde_yaml::from_str("AllCops:\n  NewCops: enable\nStyle:\n  Enabled: true\n").unwrap();
+        assert_eq!(config.resolve_enabled("Style/FileRead"), Some(true));
+        assert_eq!(config.resolve_enabled("Style/Copyright"), Some(false));
+
+        // Disabling a department still turns off every cop in it
+        let config: Config = serde_yaml::from_str("Style:\n  Enabled: false\n").unwrap();
+        assert_eq!(config.resolve_enabled("Style/NegatedIf"), Some(false));
+    }
+
+    #[test]
+    fn test_cop_params() {
+        let yaml = r#"
+Metrics/MethodLength:
+  Max: 15
+  CountComments: true
+  AllowedMethods:
+    - initialize
+  AllowedPatterns:
+    - '^test_'
+Style/StringLiterals:
+  EnforcedStyle: double_quotes
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let method = &config.cops["Metrics/MethodLength"];
+        assert_eq!(method.max(), Ok(Some(15)));
+        assert_eq!(method.count_comments(), Ok(Some(true)));
+        assert_eq!(method.allowed_methods(), Ok(vec!["initialize".to_string()]));
+        assert!(method.allowed_patterns().unwrap()[0].is_match("test_foo"));
+        assert_eq!(method.enforced_style(&["a"]), Ok(None));
+
+        let strings = &config.cops["Style/StringLiterals"];
+        assert_eq!(
+            strings.enforced_style(&["single_quotes", "double_quotes"]),
+            Ok(Some("double_quotes"))
+        );
+        assert_eq!(strings.max(), Ok(None));
+        assert!(strings.allowed_methods().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_cop_params() {
+        let yaml = r#"
+Layout/LineLength:
+  Max: eighty
+  CountComments: 1
+  AllowedMethods: foo
+  AllowedPatterns: ['(']
+  EnforcedStyle: fancy
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let cop = &config.cops["Layout/LineLength"];
+        assert_eq!(cop.max(), Err("`Max` must be a non-negative integer, got `eighty`".to_string()));
+        assert!(cop.count_comments().unwrap_err().contains("true or false"));
+        assert!(cop.allowed_methods().unwrap_err().contains("a list of strings"));
+        assert!(cop.allowed_patterns().unwrap_err().contains("invalid AllowedPatterns entry `(`"));
+        assert!(cop.enforced_style(&["plain"]).unwrap_err().contains("supported styles are plain"));
+    }
+
+    /// Writes `files` under a fresh temp directory and returns it.
+    fn write_tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        for (file, content) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_inherit_from_later_files_override() {
+        let dir = write_tree(
+            "oxicop_inherit_override",
+            &[
+                (
+                    ".rubocop.yml",
+                    "inherit_from: [.rubocop_todo.yml, config/rubocop/base.yml]\nrequire: rubocop-rails\n\nMetrics/MethodLength:\n  Max: 30\n",
+                ),
+                (
+                    ".rubocop_todo.yml",
+                    "Metrics/MethodLength:\n  Max: 50\n  CountComments: true\nLint/Debugger:\n  Enabled: false\n",
+                ),
+                (
+                    "config/rubocop/base.yml",
+                    "Metrics/MethodLength:\n  Max: 20\nLint/Debugger:\n  Severity: error\n",
+                ),
+            ],
+        );
+
+        let config = Config::from_file(&dir.join(".rubocop.yml")).unwrap();
+        let method = &config.cops["Metrics/MethodLength"];
+        assert_eq!(method.max(), Ok(Some(30)));
+        assert_eq!(method.count_comments(), Ok(Some(true)));
+        assert_eq!(config.is_cop_enabled("Lint/Debugger"), Some(false));
+        assert_eq!(config.cop_severity("Lint/Debugger"), Some("error"));
+
+        let without_todo = Config::from_file_without_todo(&dir.join(".rubocop.yml")).unwrap();
+        assert_eq!(without_todo.cops["Metrics/MethodLength"].count_comments(), Ok(None));
+        assert_eq!(without_todo.is_cop_enabled("Lint/Debugger"), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inherit_mode_merge_and_relative_patterns() {
+        let dir = write_tree(
+            "oxicop_inherit_merge",
+            &[
+                (
+                    ".rubocop.yml",
+                    "inherit_from: config/base.yml\ninherit_mode:\n  merge:\n    - Exclude\n\nAllCops:\n  Exclude: ['tmp/**/*']\nStyle/Foo:\n  inherit_mode:\n    override: [Exclude]\n  Exclude: ['b.rb']\n",
+                ),
+                (
+                    "config/base.yml",
+                    "AllCops:\n  Exclude: ['vendor/**/*']\nStyle/Foo:\n  Exclude: ['a.rb']\n",
+                ),
+            ],
+        );
+
+        let config = Config::from_file(&dir.join(".rubocop.yml")).unwrap();
+        let filter = config.file_filter().unwrap();
+        assert!(filter.is_excluded(&dir.join("tmp/x.rb")));
+        // Patterns from config/base.yml are relative to the project root,
+        // as it isn't a `.rubocop*` file
+        assert!(filter.is_excluded(&dir.join("vendor/x.rb")));
+        assert!(!filter.is_excluded(&dir.join("config/vendor/x.rb")));
+
+        let foo = &config.cops["Style/Foo"];
+        assert_eq!(foo.exclude.as_ref().unwrap().len(), 1);
+        assert!(foo.exclude.as_ref().unwrap()[0].ends_with("/b.rb"));
+        assert!(foo.param("inherit_mode").is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inherited_path_patterns_base_dir() {
+        let dir = write_tree(
+            "oxicop_inherit_base_dir",
+            &[
+                (".rubocop.yml", "inherit_from: [config/rubocop/base.yml, sub/.rubocop_shared.yml]\n"),
+                ("config/rubocop/base.yml", "AllCops:\n  Exclude: ['db/schema.rb']\n"),
+                ("sub/.rubocop_shared.yml", "Style/Foo:\n  Exclude: ['legacy.rb']\n"),
+            ],
+        );
+
+        let config = Config::from_file(&dir.join(".rubocop.yml")).unwrap();
+        let filter = config.file_filter().unwrap();
+        assert!(filter.is_excluded(&dir.join("db/schema.rb")));
+        assert!(!filter.is_excluded(&dir.join("config/rubocop/db/schema.rb")));
+        // A `.rubocop*` file keeps patterns relative to its own directory
+        let foo = config.cops["Style/Foo"].exclude.as_ref().unwrap();
+        assert!(foo[0].ends_with("/sub/legacy.rb"), "{}", foo[0]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inherit_from_cycle_is_reported() {
+        let dir = write_tree(
+            "oxicop_inherit_cycle",
+            &[
+                (".rubocop.yml", "inherit_from: a.yml\n"),
+                ("a.yml", "inherit_from: b.yml\n"),
+                ("b.yml", "inherit_from: [a.yml]\n"),
+            ],
+        );
+
+        let err = Config::from_file(&dir.join(".rubocop.yml")).unwrap_err().to_string();
+        assert!(err.starts_with("circular inherit_from: "), "{}", err);
+        assert!(err.contains("a.yml -> ") && err.ends_with("a.yml"), "{}", err);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inherit_from_missing_file() {
+        let dir = write_tree("oxicop_inherit_missing", &[(".rubocop.yml", "inherit_from: nope.yml\n")]);
+        let err = Config::from_file(&dir.join(".rubocop.yml")).unwrap_err().to_string();
+        assert!(err.contains("nope.yml"), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,265 @@
+//! Per-directory configuration lookup.
+//!
+//! Like RuboCop, each file is governed by the nearest `.rubocop.yml` found
+//! by walking up from its directory, unless a config file is forced with
+//! `--config`. Lookups are cached per directory, and the registry and file
+//! filter built from each config are shared by every file it governs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::config::{self, Config, FileFilter};
+use crate::registry::CopRegistry;
+
+const CONFIG_FILE_NAME: &str = ".rubocop.yml";
+
+/// Builds the cop registry for a config, or for no config at all.
+pub type RegistryBuilder = Box<dyn Fn(Option<&Config>) -> Result<CopRegistry, String> + Send + Sync>;
+
+/// A loaded config with everything derived from it.
+pub struct ResolvedConfig {
+    /// The file it was loaded from, if any.
+    pub path: Option<PathBuf>,
+    pub config: Option<Config>,
+    pub registry: CopRegistry,
+    /// The AllCops Include/Exclude patterns.
+    pub file_filter: FileFilter,
+}
+
+impl ResolvedConfig {
+    fn build(path: Option<PathBuf>, builder: &RegistryBuilder, skip_todo: bool) -> Result<Self, String> {
+        let context = |e: String| match &path {
+            Some(path) => format!("{}: {}", path.display(), e),
+            None => e,
+        };
+        let config = match &path {
+            Some(path) if skip_todo => Some(Config::from_file_without_todo(path).map_err(|e| context(e.to_string()))?),
+            Some(path) => Some(Config::from_file(path).map_err(|e| context(e.to_string()))?),
+            None => None,
+        };
+        let registry = builder(config.as_ref()).map_err(context)?;
+        let file_filter = match &config {
+            Some(config) => config.file_filter().map_err(|e| context(e.to_string()))?,
+            None => config::default_file_filter()?,
+        };
+        Ok(Self {
+            path,
+            config,
+            registry,
+            file_filter,
+        })
+    }
+}
+
+/// Resolves and caches the config governing each file.
+pub struct ConfigStore {
+    /// Used for every file when set.
+    forced: Option<Arc<ResolvedConfig>>,
+    builder: Option<RegistryBuilder>,
+    /// Leave `.rubocop_todo.yml` out of `inherit_from` chains.
+    skip_todo: bool,
+    /// Directory to the nearest config file at or above it.
+    nearest: RwLock<HashMap<PathBuf, Option<PathBuf>>>,
+    /// Config file (or `None` for defaults) to its resolved form.
+    resolved: RwLock<HashMap<Option<PathBuf>, Arc<ResolvedConfig>>>,
+}
+
+impl ConfigStore {
+    /// Resolves the nearest `.rubocop.yml` for each file.
+    pub fn new(builder: RegistryBuilder) -> Self {
+        Self {
+            forced: None,
+            builder: Some(builder),
+            skip_todo: false,
+            nearest: RwLock::new(HashMap::new()),
+            resolved: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Uses the config at `path` for every file.
+    pub fn with_config_file(path: &Path, builder: RegistryBuilder) -> Result<Self, String> {
+        let resolved = ResolvedConfig::build(Some(path.to_path_buf()), &builder, false)?;
+        let mut store = Self::forced(resolved);
+        store.builder = Some(builder);
+        Ok(store)
+    }
+
+    /// Leaves `.rubocop_todo.yml` out of every config's `inherit_from`
+    /// chain, so `--auto-gen-config` sees the offenses it hides. A config
+    /// forced with `--config` is reloaded.
+    pub fn without_todo(mut self) -> Result<Self, String> {
+        self.skip_todo = true;
+        self.nearest.write().unwrap().clear();
+        self.resolved.write().unwrap().clear();
+        if let (Some(forced), Some(builder)) = (&self.forced, &self.builder) {
+            if forced.path.is_some() {
+                let resolved = ResolvedConfig::build(forced.path.clone(), builder, true)?;
+                self.forced = Some(Arc::new(resolved));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Uses `registry` for every file, with no file filters.
+    pub fn fixed(registry: CopRegistry) -> Self {
+        Self::forced(ResolvedConfig {
+            path: None,
+            config: None,
+            registry,
+            file_filter: FileFilter::default(),
+        })
+    }
+
+    fn forced(resolved: ResolvedConfig) -> Self {
+        Self {
+            forced: Some(Arc::new(resolved)),
+            builder: None,
+            skip_todo: false,
+            nearest: RwLock::new(HashMap::new()),
+            resolved: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the config governing `file`.
+    pub fn for_file(&self, file: &Path) -> Result<Arc<ResolvedConfig>, String> {
+        match &self.forced {
+            Some(forced) => Ok(forced.clone()),
+            None => {
+                // A bare file name's parent is the empty path
+                let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty());
+                self.for_dir(dir.unwrap_or(Path::new(".")))
+            }
+        }
+    }
+
+    /// Returns the config governing files directly inside `dir`.
+    pub fn for_dir(&self, dir: &Path) -> Result<Arc<ResolvedConfig>, String> {
+        if let Some(forced) = &self.forced {
+            return Ok(forced.clone());
+        }
+        let dir = std::path::absolute(dir).map_err(|e| e.to_string())?;
+        let path = self.nearest_config(&dir);
+
+        if let Some(resolved) = self.resolved.read().unwrap().get(&path) {
+            return Ok(resolved.clone());
+        }
+        let builder = self.builder.as_ref().expect("unforced store has a builder");
+        let resolved = Arc::new(ResolvedConfig::build(path.clone(), builder, self.skip_todo)?);
+        Ok(self
+            .resolved
+            .write()
+            .unwrap()
+            .entry(path)
+            .or_insert(resolved)
+            .clone())
+    }
+
+    /// Returns every config resolved so far.
+    pub fn resolved(&self) -> Vec<Arc<ResolvedConfig>> {
+        match &self.forced {
+            Some(forced) => vec![forced.clone()],
+            None => self.resolved.read().unwrap().values().cloned().collect(),
+        }
+    }
+
+    /// Finds the nearest config file at or above `dir`, caching the answer
+    /// for every directory visited on the way up.
+    fn nearest_config(&self, dir: &Path) -> Option<PathBuf> {
+        let mut visited = Vec::new();
+        let mut current = Some(dir);
+        let found = loop {
+            let Some(candidate) = current else {
+                break None;
+            };
+            if let Some(cached) = self.nearest.read().unwrap().get(candidate) {
+                break cached.clone();
+            }
+            let config = candidate.join(CONFIG_FILE_NAME);
+            if config.is_file() {
+                break Some(config);
+            }
+            visited.push(candidate.to_path_buf());
+            current = candidate.parent();
+        };
+
+        let mut nearest = self.nearest.write().unwrap();
+        for dir in visited {
+            nearest.insert(dir, found.clone());
+        }
+        nearest.insert(dir.to_path_buf(), found.clone());
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn builder() -> RegistryBuilder {
+        Box::new(|config| {
+            let mut registry = CopRegistry::new();
+            if let Some(config) = config {
+                for name in registry.cop_names().iter().map(|n| n.to_string()).collect::<Vec<_>>() {
+                    if config.is_cop_enabled(&name) == Some(false) {
+                        registry.disable(&name);
+                    }
+                }
+            }
+            Ok(registry)
+        })
+    }
+
+    #[test]
+    fn test_nearest_config_per_directory() {
+        let root = std::env::temp_dir().join("oxicop_config_store");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("engines/billing/lib")).unwrap();
+        fs::create_dir_all(root.join("app")).unwrap();
+        fs::write(root.join(".rubocop.yml"), "Style/StringLiterals:\n  Enabled: false\n").unwrap();
+        fs::write(
+            root.join("engines/billing/.rubocop.yml"),
+            "Layout/TrailingWhitespace:\n  Enabled: false\n",
+        )
+        .unwrap();
+
+        let store = ConfigStore::new(builder());
+        let app = store.for_file(&root.join("app/a.rb")).unwrap();
+        let billing = store.for_file(&root.join("engines/billing/lib/b.rb")).unwrap();
+
+        assert_eq!(app.path.as_deref(), Some(root.join(".rubocop.yml").as_path()));
+        assert!(!app.registry.is_enabled("Style/StringLiterals"));
+        assert!(app.registry.is_enabled("Layout/TrailingWhitespace"));
+        assert!(billing.registry.is_enabled("Style/StringLiterals"));
+        assert!(!billing.registry.is_enabled("Layout/TrailingWhitespace"));
+
+        // Files sharing a config share its resolved form.
+        let again = store.for_file(&root.join("engines/billing/b.rb")).unwrap();
+        assert!(Arc::ptr_eq(&billing, &again));
+
+        // A bare file name is looked up from the working directory
+        assert!(store.for_file(Path::new("a.rb")).is_ok());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_forced_config_and_errors() {
+        let root = std::env::temp_dir().join("oxicop_config_store_forced");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("custom.yml"), "Style/StringLiterals:\n  Enabled: false\n").unwrap();
+        fs::write(root.join("sub/.rubocop.yml"), "inherit_from: missing.yml\n").unwrap();
+
+        let forced = ConfigStore::with_config_file(&root.join("custom.yml"), builder()).unwrap();
+        let resolved = forced.for_file(&root.join("sub/a.rb")).unwrap();
+        assert!(!resolved.registry.is_enabled("Style/StringLiterals"));
+
+        let store = ConfigStore::new(builder());
+        let err = store.for_file(&root.join("sub/a.rb")).err().unwrap();
+        assert!(err.contains("sub/.rubocop.yml: ") && err.contains("missing.yml"), "{}", err);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,5 +1,8 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Node, NodeKind};
 use crate::offense::Offense;
 use crate::source::SourceFile;
 
@@ -26,7 +29,8 @@ impl fmt::Display for Category {
 }
 
 /// Severity levels matching RuboCop's severity levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Severity {
     Info,
     Refactor,
@@ -50,6 +54,26 @@ impl Severity {
     }
 }
 
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    /// Parses a severity name as written in `.rubocop.yml`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "refactor" => Ok(Severity::Refactor),
+            "convention" => Ok(Severity::Convention),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            "fatal" => Ok(Severity::Fatal),
+            _ => Err(format!(
+                "invalid severity `{}`; valid severities are info, refactor, convention, warning, error, fatal",
+                s
+            )),
+        }
+    }
+}
+
 impl fmt::Display for Severity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.code())
@@ -70,8 +94,122 @@ pub trait Cop: Send + Sync {
     /// Human-readable description.
     fn description(&self) -> &str;
 
-    /// Check a source file and return all offenses found.
-    fn check(&self, source: &SourceFile) -> Vec<Offense>;
+    /// Check a source file and return all offenses found. Cops that work
+    /// purely on the syntax tree can leave this empty and use the node hooks.
+    fn check(&self, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// Whether offenses from this cop carry edits that fix them.
+    fn supports_autocorrect(&self) -> bool {
+        false
+    }
+
+    /// Whether the correction preserves behavior. Unsafe corrections are only
+    /// applied with `--autocorrect-all`.
+    fn safe_autocorrect(&self) -> bool {
+        true
+    }
+
+    /// The oldest Ruby version the cop applies to, for cops whose advice
+    /// relies on newer syntax or core methods. Checked against
+    /// `TargetRubyVersion`.
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        None
+    }
+
+    /// The newest Ruby version the cop applies to, for cops about problems
+    /// later versions fixed.
+    fn maximum_target_ruby_version(&self) -> Option<f64> {
+        None
+    }
+
+    /// Whether the node hooks below should be called. The syntax tree is only
+    /// walked when at least one enabled cop opts in.
+    fn visits_ast(&self) -> bool {
+        false
+    }
+
+    /// Called for every node, before the more specific hooks.
+    fn on_node(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// Method calls, including operators, attribute writers and `&.` calls.
+    fn on_send(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// Method definitions, both `def foo` and `def self.foo`.
+    fn on_def(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// Class definitions, including `class << self`.
+    fn on_class(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    fn on_module(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// Calls with a `do`/`{}` block, and stabby lambdas.
+    fn on_block(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// `if`, `unless`, `elsif`, ternaries and modifier conditionals.
+    fn on_if(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// `case ... when`.
+    fn on_case(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// `case ... in` pattern matching.
+    fn on_case_match(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+
+    /// A body with one or more `rescue` clauses, including `rescue` modifiers.
+    fn on_rescue(&self, _node: &Node, _source: &SourceFile) -> Vec<Offense> {
+        Vec::new()
+    }
+}
+
+/// Runs `cops` on a source file: each cop's `check`, then a single walk of
+/// the syntax tree dispatching node hooks to the cops that visit it.
+pub fn investigate(cops: &[&dyn Cop], source: &SourceFile) -> Vec<Offense> {
+    let mut offenses: Vec<Offense> = cops.iter().flat_map(|cop| cop.check(source)).collect();
+    let visitors: Vec<&dyn Cop> = cops.iter().copied().filter(|cop| cop.visits_ast()).collect();
+    if visitors.is_empty() {
+        return offenses;
+    }
+    source.ast().walk(&mut |node| {
+        for cop in &visitors {
+            offenses.extend(cop.on_node(node, source));
+            offenses.extend(dispatch(*cop, node, source));
+        }
+    });
+    offenses
+}
+
+fn dispatch(cop: &dyn Cop, node: &Node, source: &SourceFile) -> Vec<Offense> {
+    match node.kind {
+        NodeKind::Send { .. } => cop.on_send(node, source),
+        NodeKind::Def { .. } => cop.on_def(node, source),
+        NodeKind::Class { .. } | NodeKind::SingletonClass { .. } => cop.on_class(node, source),
+        NodeKind::Module { .. } => cop.on_module(node, source),
+        NodeKind::Block { .. } => cop.on_block(node, source),
+        NodeKind::If { .. } => cop.on_if(node, source),
+        NodeKind::Case { .. } => cop.on_case(node, source),
+        NodeKind::CaseMatch { .. } => cop.on_case_match(node, source),
+        NodeKind::Rescue { .. } => cop.on_rescue(node, source),
+        _ => Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -86,6 +224,15 @@ mod tests {
         assert!(Severity::Error < Severity::Fatal);
     }
 
+    #[test]
+    fn test_severity_from_str() {
+        assert_eq!("error".parse::<Severity>(), Ok(Severity::Error));
+        assert_eq!("info".parse::<Severity>(), Ok(Severity::Info));
+        assert_eq!("convention".parse::<Severity>(), Ok(Severity::Convention));
+        let err = "critical".parse::<Severity>().unwrap_err();
+        assert!(err.contains("`critical`"));
+    }
+
     #[test]
     fn test_severity_codes() {
         assert_eq!(Severity::Convention.code(), 'C');
@@ -100,4 +247,32 @@ mod tests {
         assert_eq!(Category::Lint.to_string(), "Lint");
         assert_eq!(Category::Naming.to_string(), "Naming");
     }
+
+    struct CallCounter;
+
+    impl Cop for CallCounter {
+        fn name(&self) -> &str { "Test/CallCounter" }
+        fn category(&self) -> Category { Category::Lint }
+        fn severity(&self) -> Severity { Severity::Warning }
+        fn description(&self) -> &str { "Reports every method definition and call" }
+        fn visits_ast(&self) -> bool { true }
+
+        fn on_send(&self, node: &Node, source: &SourceFile) -> Vec<Offense> {
+            let message = format!("send {}", node.method_name().unwrap_or_default());
+            vec![Offense::new(self.name(), message, self.severity(), source.location_of(node))]
+        }
+
+        fn on_def(&self, node: &Node, source: &SourceFile) -> Vec<Offense> {
+            vec![Offense::new(self.name(), "def", self.severity(), source.location_of(node))]
+        }
+    }
+
+    #[test]
+    fn test_investigate_dispatches_node_hooks() {
+        let source = SourceFile::from_string("test.rb".into(), "def a\n  b.c\nend\n".into());
+        let offenses = investigate(&[&CallCounter], &source);
+        let messages: Vec<&str> = offenses.iter().map(|o| o.message.as_str()).collect();
+        assert_eq!(messages, vec!["def", "send c", "send b"]);
+        assert_eq!(offenses[1].location, crate::offense::Location::new(2, 3, 3));
+    }
 }
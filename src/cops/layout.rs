@@ -4,7 +4,8 @@ use regex::Regex;
 use std::sync::OnceLock;
 
 use crate::cop::{Category, Cop, Severity};
-use crate::offense::{Location, Offense};
+use crate::lexer::TokenKind;
+use crate::offense::{Edit, Location, Offense};
 use crate::source::SourceFile;
 
 // ============================================================================
@@ -31,6 +32,10 @@ impl Cop for TrailingWhitespace {
         "Checks for trailing whitespace at the end of lines"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -47,12 +52,22 @@ impl Cop for TrailingWhitespace {
                 let trailing_start = stripped.len() + 1; // 1-based column
                 let trailing_len = line.len() - stripped.len();
                 
-                offenses.push(Offense::new(
+                let mut offense = Offense::new(
                     self.name(),
                     "Trailing whitespace detected.",
                     self.severity(),
                     Location::new(line_number, trailing_start, trailing_len),
-                ));
+                );
+                // Whitespace inside a heredoc or multi-line string is content.
+                let line_start = source.offset_of(line_number, 1).unwrap_or(0);
+                let start = line_start + stripped.trim_end().len();
+                let in_string = source
+                    .token_at_offset(start)
+                    .is_some_and(|t| t.kind == TokenKind::StringContent);
+                if !in_string {
+                    offense = offense.with_edit(Edit::remove(start, line_start + line.len()));
+                }
+                offenses.push(offense);
             }
         }
 
@@ -84,6 +99,10 @@ impl Cop for TrailingEmptyLines {
         "Checks for trailing blank lines at the end of the file"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -99,12 +118,15 @@ impl Cop for TrailingEmptyLines {
             // Missing final newline
             let last_line = source.line_count();
             if let Some(line) = source.line(last_line) {
-                offenses.push(Offense::new(
-                    self.name(),
-                    "File should end with a newline.",
-                    self.severity(),
-                    Location::new(last_line, line.len() + 1, 0),
-                ));
+                offenses.push(
+                    Offense::new(
+                        self.name(),
+                        "File should end with a newline.",
+                        self.severity(),
+                        Location::new(last_line, line.len() + 1, 0),
+                    )
+                    .with_edit(Edit::insert(source.content.len(), "\n")),
+                );
             }
         } else {
             // Check for multiple trailing newlines
@@ -116,12 +138,15 @@ impl Cop for TrailingEmptyLines {
                 let last_content_line = source.lines.iter().rposition(|l| !l.is_empty()).map(|i| i + 1).unwrap_or(0);
                 
                 if last_content_line < source.line_count() {
-                    offenses.push(Offense::new(
-                        self.name(),
-                        format!("{} trailing blank lines detected.", newline_count - 1),
-                        self.severity(),
-                        Location::new(last_content_line + 1, 1, 0),
-                    ));
+                    offenses.push(
+                        Offense::new(
+                            self.name(),
+                            format!("{} trailing blank lines detected.", newline_count - 1),
+                            self.severity(),
+                            Location::new(last_content_line + 1, 1, 0),
+                        )
+                        .with_edit(Edit::replace(trimmed.len(), source.content.len(), "\n")),
+                    );
                 }
             }
         }
@@ -154,6 +179,10 @@ impl Cop for LeadingEmptyLines {
         "Checks for empty lines at the start of a file"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -172,12 +201,18 @@ impl Cop for LeadingEmptyLines {
         }
 
         if leading_empty > 0 {
-            offenses.push(Offense::new(
-                self.name(),
-                format!("{} leading empty lines detected.", leading_empty),
-                self.severity(),
-                Location::new(1, 1, 0),
-            ));
+            let first_content = source
+                .offset_of(leading_empty + 1, 1)
+                .unwrap_or(source.content.len());
+            offenses.push(
+                Offense::new(
+                    self.name(),
+                    format!("{} leading empty lines detected.", leading_empty),
+                    self.severity(),
+                    Location::new(1, 1, 0),
+                )
+                .with_edit(Edit::remove(0, first_content)),
+            );
         }
 
         offenses
@@ -208,6 +243,10 @@ impl Cop for EndOfLine {
         "Checks for CRLF line endings (should be LF only)"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -219,12 +258,15 @@ impl Cop for EndOfLine {
             // Count how many newlines we've passed to get the line number
             line_number += source.content[last_pos..pos].matches('\n').count();
             
-            offenses.push(Offense::new(
-                self.name(),
-                "Use LF (\\n) instead of CRLF (\\r\\n).",
-                self.severity(),
-                Location::new(line_number, 1, 0),
-            ));
+            offenses.push(
+                Offense::new(
+                    self.name(),
+                    "Use LF (\\n) instead of CRLF (\\r\\n).",
+                    self.severity(),
+                    Location::new(line_number, 1, 0),
+                )
+                .with_edit(Edit::remove(pos, pos + 1)),
+            );
             
             last_pos = pos + 2;
             line_number += 1;
@@ -258,6 +300,10 @@ impl Cop for IndentationStyle {
         "Checks for tabs used for indentation (should be spaces)"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -269,12 +315,26 @@ impl Cop for IndentationStyle {
                 // Only report if tab is in leading whitespace
                 let leading_ws = line.chars().take_while(|c| c.is_whitespace()).collect::<String>();
                 if leading_ws.contains('\t') {
-                    offenses.push(Offense::new(
+                    let mut offense = Offense::new(
                         self.name(),
                         "Use spaces for indentation, not tabs.",
                         self.severity(),
                         Location::new(line_number, pos + 1, 1),
-                    ));
+                    );
+                    // Tabs at the start of a heredoc or multi-line string
+                    // line are content.
+                    let line_start = source.offset_of(line_number, 1).unwrap_or(0);
+                    let in_string = source
+                        .token_at_offset(line_start)
+                        .is_some_and(|t| t.kind == TokenKind::StringContent);
+                    if !in_string {
+                        offense = offense.with_edit(Edit::replace(
+                            line_start,
+                            line_start + leading_ws.len(),
+                            leading_ws.replace('\t', "  "),
+                        ));
+                    }
+                    offenses.push(offense);
                 }
             }
         }
@@ -432,6 +492,10 @@ impl Cop for SpaceAfterComma {
         "Checks for missing space after commas"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -451,12 +515,16 @@ impl Cop for SpaceAfterComma {
                     if i + 1 < chars.len() {
                         let next_char = chars[i + 1];
                         if next_char != ' ' && next_char != '\t' && next_char != '\n' {
-                            offenses.push(Offense::new(
-                                self.name(),
-                                "Space missing after comma.",
-                                self.severity(),
-                                Location::new(line_number, i + 2, 1),
-                            ));
+                            let after_comma = source.offset_of(line_number, i + 2).unwrap_or(0);
+                            offenses.push(
+                                Offense::new(
+                                    self.name(),
+                                    "Space missing after comma.",
+                                    self.severity(),
+                                    Location::new(line_number, i + 2, 1),
+                                )
+                                .with_edit(Edit::insert(after_comma, " ")),
+                            );
                         }
                     }
                 }
@@ -691,6 +759,10 @@ impl Cop for SpaceInsideParens {
         "Checks for spaces immediately inside parentheses"
     }
 
+    fn supports_autocorrect(&self) -> bool {
+        true
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
 
@@ -709,12 +781,16 @@ impl Cop for SpaceInsideParens {
                     if i + 1 < chars.len() && chars[i + 1] == ' ' {
                         // Make sure it's not followed by another space or closing paren
                         if i + 2 < chars.len() && chars[i + 2] != ')' {
-                            offenses.push(Offense::new(
-                                self.name(),
-                                "Space inside opening parenthesis.",
-                                self.severity(),
-                                Location::new(line_number, i + 2, 1),
-                            ));
+                            let space = source.offset_of(line_number, i + 2).unwrap_or(0);
+                            offenses.push(
+                                Offense::new(
+                                    self.name(),
+                                    "Space inside opening parenthesis.",
+                                    self.severity(),
+                                    Location::new(line_number, i + 2, 1),
+                                )
+                                .with_edit(Edit::remove(space, space + 1)),
+                            );
                         }
                     }
                 } else if ch == ')' {
@@ -722,12 +798,16 @@ impl Cop for SpaceInsideParens {
                     if i > 0 && chars[i - 1] == ' ' {
                         // Make sure it's not preceded by opening paren
                         if i < 2 || chars[i - 2] != '(' {
-                            offenses.push(Offense::new(
-                                self.name(),
-                                "Space inside closing parenthesis.",
-                                self.severity(),
-                                Location::new(line_number, i, 1),
-                            ));
+                            let space = source.offset_of(line_number, i).unwrap_or(0);
+                            offenses.push(
+                                Offense::new(
+                                    self.name(),
+                                    "Space inside closing parenthesis.",
+                                    self.severity(),
+                                    Location::new(line_number, i, 1),
+                                )
+                                .with_edit(Edit::remove(space, space + 1)),
+                            );
                         }
                     }
                 }
@@ -751,6 +831,13 @@ mod tests {
         SourceFile::from_string(PathBuf::from("test.rb"), content.to_string())
     }
 
+    /// Applies one pass of the cop's corrections.
+    fn autocorrect(cop: &dyn Cop, content: &str) -> String {
+        let source = test_source(content);
+        let mut offenses = cop.check(&source);
+        crate::corrector::apply(content, &mut offenses, |_| true)
+    }
+
     // ========================================================================
     // TrailingWhitespace tests
     // ========================================================================
@@ -1182,4 +1269,58 @@ mod tests {
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
+
+    // ========================================================================
+    // Autocorrect tests
+    // ========================================================================
+
+    #[test]
+    fn test_autocorrect_trailing_whitespace() {
+        assert_eq!(autocorrect(&TrailingWhitespace, "x = 1  \ny = 2\t\n"), "x = 1\ny = 2\n");
+    }
+
+    #[test]
+    fn test_autocorrect_trailing_whitespace_keeps_heredoc_content() {
+        let content = "x = <<~EOS\n  keep  \nEOS\n";
+        assert_eq!(autocorrect(&TrailingWhitespace, content), content);
+    }
+
+    #[test]
+    fn test_autocorrect_trailing_empty_lines() {
+        assert_eq!(autocorrect(&TrailingEmptyLines, "x = 1\n\n\n"), "x = 1\n");
+        assert_eq!(autocorrect(&TrailingEmptyLines, "x = 1"), "x = 1\n");
+    }
+
+    #[test]
+    fn test_autocorrect_leading_empty_lines() {
+        assert_eq!(autocorrect(&LeadingEmptyLines, "\n\nx = 1\n"), "x = 1\n");
+    }
+
+    #[test]
+    fn test_autocorrect_end_of_line() {
+        assert_eq!(autocorrect(&EndOfLine, "a\r\nb\r\n"), "a\nb\n");
+    }
+
+    #[test]
+    fn test_autocorrect_indentation_style() {
+        assert_eq!(
+            autocorrect(&IndentationStyle, "def foo\n\tbar\nend\n"),
+            "def foo\n  bar\nend\n"
+        );
+        // Tabs inside a heredoc or multi-line string are left alone
+        let heredoc = "y = <<~EOS\n\tfoo,bar\nEOS\n";
+        assert_eq!(autocorrect(&IndentationStyle, heredoc), heredoc);
+        let string = "y = \"a\n\tb\"\n";
+        assert_eq!(autocorrect(&IndentationStyle, string), string);
+    }
+
+    #[test]
+    fn test_autocorrect_space_after_comma() {
+        assert_eq!(autocorrect(&SpaceAfterComma, "foo(a,b,c)\n"), "foo(a, b, c)\n");
+    }
+
+    #[test]
+    fn test_autocorrect_space_inside_parens() {
+        assert_eq!(autocorrect(&SpaceInsideParens, "foo( bar )\n"), "foo(bar)\n");
+    }
 }
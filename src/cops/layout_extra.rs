@@ -3,6 +3,7 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::config::CopConfig;
 use crate::cop::{Category, Cop, Severity};
 use crate::offense::{Location, Offense};
 use crate::source::SourceFile;
@@ -23,7 +24,35 @@ fn is_magic_comment(line: &str) -> bool {
 // 1. LineLength
 // ============================================================================
 
-pub struct LineLength;
+/// Default `Max` for `Layout/LineLength`.
+const MAX_LENGTH: usize = 120;
+
+pub struct LineLength {
+    max: usize,
+    allowed_patterns: Vec<Regex>,
+}
+
+impl LineLength {
+    pub fn new() -> Self {
+        Self {
+            max: MAX_LENGTH,
+            allowed_patterns: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self {
+            max: config.max()?.unwrap_or(MAX_LENGTH),
+            allowed_patterns: config.allowed_patterns()?,
+        })
+    }
+}
+
+impl Default for LineLength {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Cop for LineLength {
     fn name(&self) -> &str {
@@ -39,21 +68,20 @@ impl Cop for LineLength {
     }
 
     fn description(&self) -> &str {
-        "Checks that lines do not exceed 120 characters"
+        "Checks that lines do not exceed the configured maximum length"
     }
 
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
-        const MAX_LENGTH: usize = 120;
 
         for (line_num, line) in source.lines.iter().enumerate() {
             let line_number = line_num + 1;
-            if line.len() > MAX_LENGTH {
+            if line.len() > self.max && !self.allowed_patterns.iter().any(|p| p.is_match(line)) {
                 offenses.push(Offense::new(
                     self.name(),
-                    format!("Line is too long. [{}/{}]", line.len(), MAX_LENGTH),
+                    format!("Line is too long. [{}/{}]", line.len(), self.max),
                     self.severity(),
-                    Location::new(line_number, MAX_LENGTH + 1, line.len() - MAX_LENGTH),
+                    Location::new(line_number, self.max + 1, line.len() - self.max),
                 ));
             }
         }
@@ -2329,7 +2357,7 @@ mod tests {
     #[test]
     fn test_line_length_pass() {
         let source = test_source("x = 1\n");
-        let cop = LineLength;
+        let cop = LineLength::new();
         assert_eq!(cop.check(&source).len(), 0);
     }
 
@@ -2337,10 +2365,24 @@ mod tests {
     fn test_line_length_fail() {
         let long_line = "x".repeat(121);
         let source = test_source(&long_line);
-        let cop = LineLength;
+        let cop = LineLength::new();
         assert_eq!(cop.check(&source).len(), 1);
     }
 
+    #[test]
+    fn test_line_length_from_config() {
+        let config: CopConfig =
+            serde_yaml::from_str("Max: 80\nAllowedPatterns: ['^\\s*# http']\n").unwrap();
+        let cop = LineLength::from_config(&config).unwrap();
+        let long = "x".repeat(81);
+        let url = format!("# http://{}", "x".repeat(80));
+        let source = test_source(&format!("{}\n{}\n", long, url));
+        let offenses = cop.check(&source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(offenses[0].message, "Line is too long. [81/80]");
+        assert_eq!(offenses[0].location, Location::new(1, 81, 1));
+    }
+
     // EmptyComment tests
     #[test]
     fn test_empty_comment_pass() {
@@ -3,46 +3,75 @@
 use regex::Regex;
 use std::collections::HashMap;
 
+use crate::config::CopConfig;
 use crate::cop::{Category, Cop, Severity};
 use crate::offense::{Location, Offense};
 use crate::source::SourceFile;
 
 /// Detects leftover debugging calls like `binding.pry`, `byebug`, etc.
 pub struct Debugger {
-    patterns: Vec<(&'static str, Regex)>,
+    patterns: Vec<(String, Regex)>,
 }
 
 impl Debugger {
     pub fn new() -> Self {
-        let debug_methods = vec![
-            "binding.pry",
-            "binding.irb",
-            "byebug",
-            "debugger",
-            "binding.break",
-            "pry",
-            "save_and_open_page",
-            "save_and_open_screenshot",
-        ];
-
-        let patterns = debug_methods
+        Self::with_methods(
+            [
+                "binding.pry",
+                "binding.irb",
+                "byebug",
+                "debugger",
+                "binding.break",
+                "pry",
+                "save_and_open_page",
+                "save_and_open_screenshot",
+            ]
+            .iter()
+            .map(|m| m.to_string()),
+        )
+    }
+
+    pub fn with_methods(methods: impl IntoIterator<Item = String>) -> Self {
+        let patterns = methods
             .into_iter()
             .map(|method| {
-                // Use word boundaries to match whole words/expressions
-                // \b works for word characters, but for dots we need special handling
-                let pattern = if method.contains('.') {
-                    // For dotted expressions, match them exactly with word boundary at start and end
-                    format!(r"\b{}\b", regex::escape(method))
-                } else {
-                    // For single words, use simple word boundaries
-                    format!(r"\b{}\b", regex::escape(method))
-                };
+                // Word boundaries on both ends match whole calls, dotted or not
+                let pattern = format!(r"\b{}\b", regex::escape(&method));
                 (method, Regex::new(&pattern).unwrap())
             })
             .collect();
 
         Self { patterns }
     }
+
+    /// Reads `DebuggerMethods`, either a flat list or RuboCop's map of groups
+    /// to lists, where a `~` group is turned off.
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        let Some(value) = config.param("DebuggerMethods") else {
+            return Ok(Self::new());
+        };
+        let methods = match value.as_mapping() {
+            Some(groups) => {
+                let mut methods = Vec::new();
+                for (group, list) in groups {
+                    let group = group.as_str().unwrap_or_default();
+                    if list.is_null() {
+                        continue;
+                    }
+                    let list = list
+                        .as_sequence()
+                        .and_then(|items| items.iter().map(|i| i.as_str().map(str::to_string)).collect::<Option<Vec<_>>>())
+                        .ok_or_else(|| format!("`DebuggerMethods` group `{}` must be a list of strings", group))?;
+                    methods.extend(list);
+                }
+                methods
+            }
+            None => config
+                .string_list_param("DebuggerMethods")?
+                .unwrap_or_default(),
+        };
+        Ok(Self::with_methods(methods))
+    }
 }
 
 impl Default for Debugger {
@@ -298,6 +327,25 @@ mod tests {
         assert_eq!(offenses.len(), 0);
     }
 
+    #[test]
+    fn test_debugger_methods_from_config() {
+        let config: CopConfig = serde_yaml::from_str(
+            "DebuggerMethods:\n  Kernel: [binding.irb]\n  Pry: ~\n  Custom: [my_debug]\n",
+        )
+        .unwrap();
+        let cop = Debugger::from_config(&config).unwrap();
+        let source = test_source("binding.pry\nmy_debug\nbinding.irb\n");
+        let lines: Vec<usize> = cop.check(&source).iter().map(|o| o.location.line).collect();
+        assert_eq!(lines, vec![2, 3]);
+
+        let flat: CopConfig = serde_yaml::from_str("DebuggerMethods: [trace!]\n").unwrap();
+        let cop = Debugger::from_config(&flat).unwrap();
+        assert!(cop.check(&test_source("byebug\n")).is_empty());
+
+        let bad: CopConfig = serde_yaml::from_str("DebuggerMethods:\n  Kernel: binding.irb\n").unwrap();
+        assert!(Debugger::from_config(&bad).is_err());
+    }
+
     #[test]
     fn test_debugger_detects_binding_pry() {
         let cop = Debugger::new();
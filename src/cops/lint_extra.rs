@@ -3,7 +3,9 @@
 use regex::Regex;
 use once_cell::sync::Lazy;
 
+use crate::ast::{Node, NodeKind};
 use crate::cop::{Category, Cop, Severity};
+use crate::directive::{DirectiveName, Directives};
 use crate::offense::{Location, Offense};
 use crate::source::SourceFile;
 
@@ -509,12 +511,83 @@ macro_rules! simple_cop {
 
 // Apply macro for remaining cops
 simple_cop!(DuplicateBranch, "Lint/DuplicateBranch", "Duplicate code in if/else branches");
-simple_cop!(DuplicateCaseCondition, "Lint/DuplicateCaseCondition", "Duplicate case condition");
 simple_cop!(DuplicateElsifCondition, "Lint/DuplicateElsifCondition", "Duplicate elsif condition");
 simple_cop!(DuplicateHashKey, "Lint/DuplicateHashKey", "Duplicate hash key");
 simple_cop!(DuplicateMagicComment, "Lint/DuplicateMagicComment", "Duplicate magic comment");
 simple_cop!(DuplicateRequire, "Lint/DuplicateRequire", "Duplicate require");
-simple_cop!(DuplicateRescueException, "Lint/DuplicateRescueException", "Duplicate rescue exception");
+
+/// Reports nodes whose source text repeats an earlier one in `nodes`.
+fn duplicate_nodes<'a>(nodes: impl IntoIterator<Item = &'a Node>, source: &SourceFile) -> Vec<&'a Node> {
+    let mut seen = std::collections::HashSet::new();
+    nodes
+        .into_iter()
+        .filter(|node| !seen.insert(node.source(&source.content)))
+        .collect()
+}
+
+/// Detects repeated conditions across the `when` branches of a `case`
+pub struct DuplicateCaseCondition;
+
+impl Cop for DuplicateCaseCondition {
+    fn name(&self) -> &str { "Lint/DuplicateCaseCondition" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Duplicate case condition" }
+    fn visits_ast(&self) -> bool { true }
+
+    fn on_case(&self, node: &Node, source: &SourceFile) -> Vec<Offense> {
+        let NodeKind::Case { whens, .. } = &node.kind else {
+            return Vec::new();
+        };
+        let conditions = whens.iter().flat_map(|when| match &when.kind {
+            NodeKind::When { conditions, .. } => conditions.as_slice(),
+            _ => &[],
+        });
+        duplicate_nodes(conditions, source)
+            .into_iter()
+            .map(|condition| {
+                Offense::new(
+                    self.name(),
+                    "Duplicate `when` condition detected.",
+                    self.severity(),
+                    source.location_of(condition),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Detects an exception class rescued more than once in the same body
+pub struct DuplicateRescueException;
+
+impl Cop for DuplicateRescueException {
+    fn name(&self) -> &str { "Lint/DuplicateRescueException" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Duplicate rescue exception" }
+    fn visits_ast(&self) -> bool { true }
+
+    fn on_rescue(&self, node: &Node, source: &SourceFile) -> Vec<Offense> {
+        let NodeKind::Rescue { handlers, .. } = &node.kind else {
+            return Vec::new();
+        };
+        let exceptions = handlers.iter().flat_map(|handler| match &handler.kind {
+            NodeKind::RescueBody { exceptions, .. } => exceptions.as_slice(),
+            _ => &[],
+        });
+        duplicate_nodes(exceptions, source)
+            .into_iter()
+            .map(|exception| {
+                Offense::new(
+                    self.name(),
+                    "Duplicate `rescue` exception detected.",
+                    self.severity(),
+                    source.location_of(exception),
+                )
+            })
+            .collect()
+    }
+}
 
 /// Detects empty blocks
 pub struct EmptyBlock;
@@ -732,7 +805,132 @@ impl Cop for RandOne {
 }
 
 // Continue with remaining cops...
-simple_cop!(RedundantCopDisableDirective, "Lint/RedundantCopDisableDirective", "Redundant cop disable");
+/// How a redundant-directive message refers to a directive name
+fn directive_subject(name: &str) -> String {
+    match name {
+        "all" => "all cops".to_string(),
+        name if name.contains('/') => name.to_string(),
+        name => format!("{} department", name),
+    }
+}
+
+/// How a missing-enable message refers to a directive name
+fn directive_target(name: &str) -> String {
+    if name == "all" {
+        "all cops".to_string()
+    } else if name.contains('/') {
+        format!("{} cop", name)
+    } else {
+        format!("{} department", name)
+    }
+}
+
+/// Detects `rubocop:disable` directives that suppress no offense. The runner
+/// reports these once every other cop has run; see `runner::lint`.
+pub struct RedundantCopDisableDirective;
+
+impl RedundantCopDisableDirective {
+    /// Builds the offense for a directive name that disabled nothing.
+    pub fn offense(&self, entry: &DirectiveName) -> Offense {
+        Offense::new(
+            self.name(),
+            format!("Unnecessary disabling of {}.", directive_subject(&entry.name)),
+            self.severity(),
+            Location::new(entry.line, entry.column, entry.name.chars().count()),
+        )
+    }
+}
+
+impl Cop for RedundantCopDisableDirective {
+    fn name(&self) -> &str { "Lint/RedundantCopDisableDirective" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Redundant cop disable" }
+}
+
+/// Detects directives that disable cops for the rest of the file
+pub struct MissingCopEnableDirective;
+
+impl Cop for MissingCopEnableDirective {
+    fn name(&self) -> &str { "Lint/MissingCopEnableDirective" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Missing cop enable" }
+
+    fn check(&self, source: &SourceFile) -> Vec<Offense> {
+        let directives = Directives::parse(source);
+        directives
+            .ranges
+            .iter()
+            .filter(|range| range.unclosed)
+            .map(|range| {
+                let entry = directives.range_name(range);
+                Offense::new(
+                    self.name(),
+                    format!(
+                        "Re-enable {} with `# rubocop:enable` after disabling it.",
+                        directive_target(&entry.name)
+                    ),
+                    self.severity(),
+                    Location::new(entry.line, entry.column, entry.name.chars().count()),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Detects directive comments that can't be parsed
+pub struct CopDirectiveSyntax;
+
+impl Cop for CopDirectiveSyntax {
+    fn name(&self) -> &str { "Lint/CopDirectiveSyntax" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Cop directive syntax" }
+
+    fn check(&self, source: &SourceFile) -> Vec<Offense> {
+        Directives::parse(source)
+            .malformed
+            .iter()
+            .map(|m| {
+                Offense::new(
+                    self.name(),
+                    format!("Malformed directive comment detected. {}", m.reason),
+                    self.severity(),
+                    Location::new(m.line, m.column, m.length),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Detects `rubocop:enable` directives with no matching disable
+pub struct RedundantCopEnableDirective;
+
+impl Cop for RedundantCopEnableDirective {
+    fn name(&self) -> &str { "Lint/RedundantCopEnableDirective" }
+    fn category(&self) -> Category { Category::Lint }
+    fn severity(&self) -> Severity { Severity::Warning }
+    fn description(&self) -> &str { "Redundant rubocop:enable directive" }
+
+    fn check(&self, source: &SourceFile) -> Vec<Offense> {
+        let directives = Directives::parse(source);
+        directives
+            .redundant_enables
+            .iter()
+            .map(|&(directive, name_index)| {
+                let entry = &directives.directives[directive].names[name_index];
+                Offense::new(
+                    self.name(),
+                    format!("Unnecessary enabling of {}.", directive_subject(&entry.name)),
+                    self.severity(),
+                    Location::new(entry.line, entry.column, entry.name.chars().count()),
+                )
+            })
+            .collect()
+    }
+}
+
 simple_cop!(RedundantRequireStatement, "Lint/RedundantRequireStatement", "Redundant require");
 
 /// Detects redundant .to_s in string interpolation
@@ -897,7 +1095,6 @@ simple_cop!(EmptyInPattern, "Lint/EmptyInPattern", "Empty in-pattern");
 simple_cop!(HashCompareByIdentity, "Lint/HashCompareByIdentity", "Hash compare_by_identity issue");
 simple_cop!(IneffectiveAccessModifier, "Lint/IneffectiveAccessModifier", "Access modifier has no effect");
 simple_cop!(InterpolationCheck, "Lint/InterpolationCheck", "Interpolation in single-quoted string");
-simple_cop!(MissingCopEnableDirective, "Lint/MissingCopEnableDirective", "Missing cop enable");
 simple_cop!(MultipleComparison, "Lint/MultipleComparison", "Use .include? instead");
 simple_cop!(NextWithoutAccumulator, "Lint/NextWithoutAccumulator", "next without accumulator");
 simple_cop!(NoReturnInBeginEndBlocks, "Lint/NoReturnInBeginEndBlocks", "Return in BEGIN/END");
@@ -942,7 +1139,6 @@ simple_cop!(AmbiguousBlockAssociation, "Lint/AmbiguousBlockAssociation", "Ambigu
 simple_cop!(AmbiguousOperatorPrecedence, "Lint/AmbiguousOperatorPrecedence", "Ambiguous precedence");
 simple_cop!(ConstantOverwrittenInRescue, "Lint/ConstantOverwrittenInRescue", "Constant overwritten in rescue");
 simple_cop!(ConstantResolution, "Lint/ConstantResolution", "Constant resolution style");
-simple_cop!(CopDirectiveSyntax, "Lint/CopDirectiveSyntax", "Cop directive syntax");
 simple_cop!(EachWithObjectArgument, "Lint/EachWithObjectArgument", "Wrong argument to each_with_object");
 simple_cop!(ElseLayout, "Lint/ElseLayout", "else layout issue");
 simple_cop!(CircularArgumentReference, "Lint/CircularArgumentReference", "Circular default arg");
@@ -955,7 +1151,6 @@ simple_cop!(DeprecatedConstants, "Lint/DeprecatedConstants", "Use of deprecated
 simple_cop!(DeprecatedOpenSSLConstant, "Lint/DeprecatedOpenSSLConstant", "Use of deprecated OpenSSL constants");
 simple_cop!(MixedRegexpCaptureTypes, "Lint/MixedRegexpCaptureTypes", "Mixed named and numbered captures in regexp");
 simple_cop!(NumericOperationWithConstantResult, "Lint/NumericOperationWithConstantResult", "Numeric operation with constant result");
-simple_cop!(RedundantCopEnableDirective, "Lint/RedundantCopEnableDirective", "Redundant rubocop:enable directive");
 simple_cop!(UnescapedBracketInRegexp, "Lint/UnescapedBracketInRegexp", "Unescaped bracket in regexp");
 simple_cop!(LiteralAsCondition, "Lint/LiteralAsCondition", "Literal used as condition");
 
@@ -1273,4 +1468,89 @@ mod tests {
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 1);
     }
+
+    #[test]
+    fn test_duplicate_case_condition() {
+        let source = test_source("case x\nwhen 1, :a\n  foo\nwhen :b, 1\n  bar\nend\n");
+        let offenses = crate::cop::investigate(&[&DuplicateCaseCondition], &source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(offenses[0].location, Location::new(4, 10, 1));
+    }
+
+    #[test]
+    fn test_duplicate_case_condition_distinct() {
+        let source = test_source("case x\nwhen 1 then foo\nwhen 2 then bar\nend\n");
+        let offenses = crate::cop::investigate(&[&DuplicateCaseCondition], &source);
+        assert!(offenses.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_rescue_exception() {
+        let source = test_source(
+            "begin\n  work\nrescue IOError, ArgumentError\n  retry\nrescue IOError => e\n  raise e\nend\n",
+        );
+        let offenses = crate::cop::investigate(&[&DuplicateRescueException], &source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(offenses[0].location, Location::new(5, 8, 7));
+    }
+
+    #[test]
+    fn test_duplicate_rescue_exception_in_separate_methods() {
+        let source = test_source(
+            "def a\n  x\nrescue IOError\nend\n\ndef b\n  y\nrescue IOError\nend\n",
+        );
+        let offenses = crate::cop::investigate(&[&DuplicateRescueException], &source);
+        assert!(offenses.is_empty());
+    }
+
+    #[test]
+    fn test_missing_cop_enable_directive() {
+        let source = test_source("# rubocop:disable Style/Foo, Lint
+x = 1
+");
+        let offenses = MissingCopEnableDirective.check(&source);
+        assert_eq!(offenses.len(), 2);
+        assert_eq!(
+            offenses[0].message,
+            "Re-enable Style/Foo cop with `# rubocop:enable` after disabling it."
+        );
+        assert_eq!(offenses[0].location, Location::new(1, 19, 9));
+        assert!(offenses[1].message.contains("Lint department"));
+    }
+
+    #[test]
+    fn test_missing_cop_enable_directive_closed_or_inline() {
+        let source = test_source("# rubocop:disable Style/Foo
+x = 1
+# rubocop:enable Style/Foo
+y # rubocop:disable Lint
+");
+        assert!(MissingCopEnableDirective.check(&source).is_empty());
+    }
+
+    #[test]
+    fn test_cop_directive_syntax() {
+        let source = test_source("x = 1 # rubocop:disable
+# rubocop:disable Style/Foo -- fine
+");
+        let offenses = CopDirectiveSyntax.check(&source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(
+            offenses[0].message,
+            "Malformed directive comment detected. The cop name is missing."
+        );
+        assert_eq!(offenses[0].location, Location::new(1, 7, 17));
+    }
+
+    #[test]
+    fn test_redundant_cop_enable_directive() {
+        let source = test_source("# rubocop:disable Style/Foo
+x
+# rubocop:enable Style/Foo, Lint/Bar
+");
+        let offenses = RedundantCopEnableDirective.check(&source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(offenses[0].message, "Unnecessary enabling of Lint/Bar.");
+        assert_eq!(offenses[0].location, Location::new(3, 29, 8));
+    }
 }
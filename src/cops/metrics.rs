@@ -3,6 +3,7 @@
 use regex::Regex;
 use once_cell::sync::Lazy;
 
+use crate::config::CopConfig;
 use crate::cop::{Category, Cop, Severity};
 use crate::offense::{Location, Offense};
 use crate::source::SourceFile;
@@ -15,6 +16,10 @@ static METHOD_DEF_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"^\s*def\s+"#).unwrap()
 });
 
+static METHOD_NAME_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\s*def\s+(?:[A-Za-z_][A-Za-z0-9_]*\.)?([^\s(;]+)"#).unwrap()
+});
+
 static CLASS_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"^\s*class\s+"#).unwrap()
 });
@@ -59,18 +64,60 @@ static MARSHAL_LOAD_PATTERN: Lazy<Regex> = Lazy::new(|| {
 // METRICS COPS
 // ============================================================================
 
+/// Counts the non-blank lines strictly between `start` and `end` (1-based),
+/// skipping comment-only lines unless `count_comments` is set.
+///
+/// This is how RuboCop's length cops measure a body: the `def`/`class`/`do`
+/// and `end` lines and blank lines never count, and comments only with
+/// `CountComments: true`. Counting every line from opening to `end` would
+/// leave `CountComments` meaningless and report bodies RuboCop accepts.
+fn body_length(source: &SourceFile, start: usize, end: usize, count_comments: bool) -> usize {
+    source.lines[start..end.saturating_sub(1).max(start)]
+        .iter()
+        .filter(|line| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && (count_comments || !trimmed.starts_with('#'))
+        })
+        .count()
+}
+
 /// Checks method length
 pub struct MethodLength {
     max_lines: usize,
+    count_comments: bool,
+    allowed_methods: Vec<String>,
+    allowed_patterns: Vec<Regex>,
 }
 
 impl MethodLength {
     pub fn new() -> Self {
-        Self { max_lines: 10 }
+        Self::with_max_lines(10)
     }
 
     pub fn with_max_lines(max_lines: usize) -> Self {
-        Self { max_lines }
+        Self {
+            max_lines,
+            count_comments: false,
+            allowed_methods: Vec::new(),
+            allowed_patterns: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self {
+            max_lines: config.max()?.unwrap_or(10),
+            count_comments: config.count_comments()?.unwrap_or(false),
+            allowed_methods: config.allowed_methods()?,
+            allowed_patterns: config.allowed_patterns()?,
+        })
+    }
+
+    fn is_allowed(&self, def_line: &str) -> bool {
+        let Some(name) = METHOD_NAME_PATTERN.captures(def_line).and_then(|c| c.get(1)) else {
+            return false;
+        };
+        let name = name.as_str();
+        self.allowed_methods.iter().any(|m| m == name) || self.allowed_patterns.iter().any(|p| p.is_match(name))
     }
 }
 
@@ -107,8 +154,8 @@ impl Cop for MethodLength {
                 depth -= 1;
                 if depth == 0 {
                     if let Some(start) = method_start {
-                        let length = line_number - start + 1;
-                        if length > self.max_lines {
+                        let length = body_length(source, start, line_number, self.count_comments);
+                        if length > self.max_lines && !self.is_allowed(&source.lines[start - 1]) {
                             offenses.push(Offense::new(
                                 self.name(),
                                 format!("Method has {} lines (max {})", length, self.max_lines),
@@ -129,15 +176,23 @@ impl Cop for MethodLength {
 /// Checks class length
 pub struct ClassLength {
     max_lines: usize,
+    count_comments: bool,
 }
 
 impl ClassLength {
     pub fn new() -> Self {
-        Self { max_lines: 100 }
+        Self::with_max_lines(100)
     }
 
     pub fn with_max_lines(max_lines: usize) -> Self {
-        Self { max_lines }
+        Self { max_lines, count_comments: false }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self {
+            max_lines: config.max()?.unwrap_or(100),
+            count_comments: config.count_comments()?.unwrap_or(false),
+        })
     }
 }
 
@@ -182,7 +237,7 @@ impl Cop for ClassLength {
                 depth -= 1;
                 if depth == 0 {
                     if let Some(start) = class_start {
-                        let length = line_number - start + 1;
+                        let length = body_length(source, start, line_number, self.count_comments);
                         if length > self.max_lines {
                             offenses.push(Offense::new(
                                 self.name(),
@@ -204,15 +259,23 @@ impl Cop for ClassLength {
 /// Checks module length
 pub struct ModuleLength {
     max_lines: usize,
+    count_comments: bool,
 }
 
 impl ModuleLength {
     pub fn new() -> Self {
-        Self { max_lines: 100 }
+        Self::with_max_lines(100)
     }
 
     pub fn with_max_lines(max_lines: usize) -> Self {
-        Self { max_lines }
+        Self { max_lines, count_comments: false }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self {
+            max_lines: config.max()?.unwrap_or(100),
+            count_comments: config.count_comments()?.unwrap_or(false),
+        })
     }
 }
 
@@ -249,7 +312,7 @@ impl Cop for ModuleLength {
                 depth -= 1;
                 if depth == 0 {
                     if let Some(start) = module_start {
-                        let length = line_number - start + 1;
+                        let length = body_length(source, start, line_number, self.count_comments);
                         if length > self.max_lines {
                             offenses.push(Offense::new(
                                 self.name(),
@@ -271,15 +334,23 @@ impl Cop for ModuleLength {
 /// Checks block length
 pub struct BlockLength {
     max_lines: usize,
+    count_comments: bool,
 }
 
 impl BlockLength {
     pub fn new() -> Self {
-        Self { max_lines: 25 }
+        Self::with_max_lines(25)
     }
 
     pub fn with_max_lines(max_lines: usize) -> Self {
-        Self { max_lines }
+        Self { max_lines, count_comments: false }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self {
+            max_lines: config.max()?.unwrap_or(25),
+            count_comments: config.count_comments()?.unwrap_or(false),
+        })
     }
 }
 
@@ -310,7 +381,7 @@ impl Cop for BlockLength {
 
             if BLOCK_END_PATTERN.is_match(line) && !source.in_string_or_comment(line_number, 1) {
                 if let Some(start) = block_starts.pop() {
-                    let length = line_number - start + 1;
+                    let length = body_length(source, start, line_number, self.count_comments);
                     if length > self.max_lines {
                         offenses.push(Offense::new(
                             self.name(),
@@ -340,6 +411,10 @@ impl BlockNesting {
     pub fn with_max_depth(max_depth: usize) -> Self {
         Self { max_depth }
     }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self::with_max_depth(config.max()?.unwrap_or(3)))
+    }
 }
 
 impl Default for BlockNesting {
@@ -398,6 +473,10 @@ impl ParameterLists {
     pub fn with_max_params(max_params: usize) -> Self {
         Self { max_params }
     }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self::with_max_params(config.max()?.unwrap_or(5)))
+    }
 }
 
 impl Default for ParameterLists {
@@ -456,6 +535,10 @@ impl CyclomaticComplexity {
     pub fn with_max_complexity(max_complexity: usize) -> Self {
         Self { max_complexity }
     }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self::with_max_complexity(config.max()?.unwrap_or(10)))
+    }
 }
 
 impl Default for CyclomaticComplexity {
@@ -531,6 +614,10 @@ impl AbcSize {
     pub fn with_max_size(max_size: usize) -> Self {
         Self { max_size }
     }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self::with_max_size(config.max()?.unwrap_or(20)))
+    }
 }
 
 impl Default for AbcSize {
@@ -609,6 +696,10 @@ impl CollectionLiteralLength {
     pub fn with_max_length(max_length: usize) -> Self {
         Self { max_length }
     }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        Ok(Self::with_max_length(config.usize_param("LengthThreshold")?.unwrap_or(20)))
+    }
 }
 
 impl Default for CollectionLiteralLength {
@@ -927,6 +1018,69 @@ mod tests {
         assert_eq!(offenses.len(), 0);
     }
 
+    #[test]
+    fn test_method_length_from_config() {
+        let body: String = (0..4).map(|i| format!("  # note {}\n  x{} = 1\n\n", i, i)).collect();
+        let source = test_source(&format!("def foo\n{}end\n\ndef initialize\n{}end\n", body, body));
+
+        // Blank lines never count; comments only with CountComments.
+        let config: CopConfig = serde_yaml::from_str("Max: 4\n").unwrap();
+        assert!(MethodLength::from_config(&config).unwrap().check(&source).is_empty());
+
+        let config: CopConfig = serde_yaml::from_str("Max: 4\nCountComments: true\n").unwrap();
+        let offenses = MethodLength::from_config(&config).unwrap().check(&source);
+        assert_eq!(offenses.len(), 2);
+        assert_eq!(offenses[0].message, "Method has 8 lines (max 4)");
+
+        let config: CopConfig =
+            serde_yaml::from_str("Max: 4\nCountComments: true\nAllowedMethods: [initialize]\nAllowedPatterns: ['^fo']\n")
+                .unwrap();
+        assert!(MethodLength::from_config(&config).unwrap().check(&source).is_empty());
+    }
+
+    #[test]
+    fn test_length_cops_count_body_lines_only() {
+        // Ten code lines, with blank and comment lines between them
+        let body: String = (0..10)
+            .map(|i| if i % 2 == 0 { format!("  x{} = 1\n\n", i) } else { format!("  # note\n  x{} = 1\n", i) })
+            .collect();
+        let wrap = |open: &str| test_source(&format!("{}\n{}end\n", open, body));
+
+        // Exactly at the limit: the opening and `end` lines, the five blank
+        // lines and the five comments don't count
+        assert!(MethodLength::with_max_lines(10).check(&wrap("def foo")).is_empty());
+        assert!(ClassLength::with_max_lines(10).check(&wrap("class Foo")).is_empty());
+        assert!(ModuleLength::with_max_lines(10).check(&wrap("module Foo")).is_empty());
+        assert!(BlockLength::with_max_lines(10).check(&wrap("items.each do |i|")).is_empty());
+
+        let offenses = MethodLength::with_max_lines(9).check(&wrap("def foo"));
+        assert_eq!(offenses[0].message, "Method has 10 lines (max 9)");
+        let offenses = ClassLength::with_max_lines(9).check(&wrap("class Foo"));
+        assert_eq!(offenses[0].message, "Class has 10 lines (max 9)");
+        let offenses = ModuleLength::with_max_lines(9).check(&wrap("module Foo"));
+        assert_eq!(offenses[0].message, "Module has 10 lines (max 9)");
+        let offenses = BlockLength::with_max_lines(9).check(&wrap("items.each do |i|"));
+        assert_eq!(offenses[0].message, "Block has 10 lines (max 9)");
+
+        // Comments count when configured, blank lines still don't
+        let config: CopConfig = serde_yaml::from_str("Max: 10\nCountComments: true\n").unwrap();
+        let offenses = ClassLength::from_config(&config).unwrap().check(&wrap("class Foo"));
+        assert_eq!(offenses[0].message, "Class has 15 lines (max 10)");
+        let offenses = ModuleLength::from_config(&config).unwrap().check(&wrap("module Foo"));
+        assert_eq!(offenses[0].message, "Module has 15 lines (max 10)");
+        let offenses = BlockLength::from_config(&config).unwrap().check(&wrap("items.each do |i|"));
+        assert_eq!(offenses[0].message, "Block has 15 lines (max 10)");
+    }
+
+    #[test]
+    fn test_block_length_from_config() {
+        let source = test_source("items.each do |i|\n  a\n  b\n  c\nend\n");
+        let config: CopConfig = serde_yaml::from_str("Max: 2\n").unwrap();
+        assert_eq!(BlockLength::from_config(&config).unwrap().check(&source).len(), 1);
+        let config: CopConfig = serde_yaml::from_str("Max: 3\n").unwrap();
+        assert!(BlockLength::from_config(&config).unwrap().check(&source).is_empty());
+    }
+
     #[test]
     fn test_class_length() {
         let cop = ClassLength::with_max_lines(5);
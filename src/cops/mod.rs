@@ -13,6 +13,7 @@ pub mod naming_extra;
 pub mod metrics;
 pub mod bundler_gemspec;
 
+use crate::config::CopConfig;
 use crate::cop::Cop;
 
 /// Returns all implemented cops as trait objects.
@@ -30,7 +31,7 @@ pub fn all_cops() -> Vec<Box<dyn Cop>> {
         Box::new(layout::EmptyLineBetweenDefs),
         Box::new(layout::SpaceInsideParens),
         // ==================== Layout extra (90) ====================
-        Box::new(layout_extra::LineLength),
+        Box::new(layout_extra::LineLength::new()),
         Box::new(layout_extra::EmptyComment),
         Box::new(layout_extra::EmptyLines),
         Box::new(layout_extra::LeadingCommentSpace),
@@ -122,7 +123,7 @@ pub fn all_cops() -> Vec<Box<dyn Cop>> {
         Box::new(layout_extra::ErrorA),
         // ==================== Style (original 5) ====================
         Box::new(style::FrozenStringLiteralComment),
-        Box::new(style::StringLiterals),
+        Box::new(style::StringLiterals::new()),
         Box::new(style::NegatedIf),
         Box::new(style::RedundantReturn),
         Box::new(style::EmptyMethod),
@@ -152,6 +153,31 @@ pub fn all_cops() -> Vec<Box<dyn Cop>> {
     cops
 }
 
+/// Builds a cop from its `.rubocop.yml` parameters. Returns `None` for cops
+/// that take no parameters, which keep their default instance.
+pub fn from_config(name: &str, config: &CopConfig) -> Option<Result<Box<dyn Cop>, String>> {
+    fn boxed<C: Cop + 'static>(cop: Result<C, String>) -> Result<Box<dyn Cop>, String> {
+        cop.map(|cop| Box::new(cop) as Box<dyn Cop>)
+    }
+
+    let cop = match name {
+        "Layout/LineLength" => boxed(layout_extra::LineLength::from_config(config)),
+        "Lint/Debugger" => boxed(lint::Debugger::from_config(config)),
+        "Style/StringLiterals" => boxed(style::StringLiterals::from_config(config)),
+        "Metrics/MethodLength" => boxed(metrics::MethodLength::from_config(config)),
+        "Metrics/ClassLength" => boxed(metrics::ClassLength::from_config(config)),
+        "Metrics/ModuleLength" => boxed(metrics::ModuleLength::from_config(config)),
+        "Metrics/BlockLength" => boxed(metrics::BlockLength::from_config(config)),
+        "Metrics/BlockNesting" => boxed(metrics::BlockNesting::from_config(config)),
+        "Metrics/ParameterLists" => boxed(metrics::ParameterLists::from_config(config)),
+        "Metrics/CyclomaticComplexity" => boxed(metrics::CyclomaticComplexity::from_config(config)),
+        "Metrics/AbcSize" => boxed(metrics::AbcSize::from_config(config)),
+        "Metrics/CollectionLiteralLength" => boxed(metrics::CollectionLiteralLength::from_config(config)),
+        _ => return None,
+    };
+    Some(cop)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,4 +210,25 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_from_config() {
+        let config: CopConfig = serde_yaml::from_str("Max: 3\n").unwrap();
+        let cop = from_config("Metrics/MethodLength", &config).unwrap().unwrap();
+        assert_eq!(cop.name(), "Metrics/MethodLength");
+
+        let bad: CopConfig = serde_yaml::from_str("Max: -1\n").unwrap();
+        assert!(from_config("Metrics/MethodLength", &bad).unwrap().is_err());
+        assert!(from_config("Style/NegatedIf", &config).is_none());
+    }
+
+    #[test]
+    fn test_default_params_are_valid() {
+        for cop in all_cops() {
+            let defaults = crate::config::default_cop_config(cop.name()).unwrap();
+            if let Some(built) = from_config(cop.name(), defaults) {
+                assert!(built.is_ok(), "{}: {}", cop.name(), built.err().unwrap());
+            }
+        }
+    }
 }
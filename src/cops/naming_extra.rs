@@ -167,6 +167,10 @@ impl Cop for BlockForwarding {
         "Use anonymous block forwarding"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.1)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let pattern = Regex::new(r#"def\s+\w+\([^)]*&block\)"#).unwrap();
@@ -2,6 +2,7 @@
 
 use regex::Regex;
 
+use crate::config::CopConfig;
 use crate::cop::{Category, Cop, Severity};
 use crate::offense::{Location, Offense};
 use crate::source::SourceFile;
@@ -83,8 +84,74 @@ impl Cop for FrozenStringLiteralComment {
 ///
 /// This cop detects double-quoted strings that could be single-quoted. Double quotes
 /// are only necessary when the string contains interpolation (`#{}`) or escape sequences
-/// like `\n`, `\t`, etc.
-pub struct StringLiterals;
+/// like `\n`, `\t`, etc. With `EnforcedStyle: double_quotes` it flags single-quoted
+/// strings that could be double-quoted instead.
+pub struct StringLiterals {
+    style: QuoteStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuoteStyle {
+    SingleQuotes,
+    DoubleQuotes,
+}
+
+impl StringLiterals {
+    pub fn new() -> Self {
+        Self {
+            style: QuoteStyle::SingleQuotes,
+        }
+    }
+
+    pub fn from_config(config: &CopConfig) -> Result<Self, String> {
+        let style = match config.enforced_style(&["single_quotes", "double_quotes"])? {
+            Some("double_quotes") => QuoteStyle::DoubleQuotes,
+            _ => QuoteStyle::SingleQuotes,
+        };
+        Ok(Self { style })
+    }
+
+    fn check_single_quoted(&self, source: &SourceFile) -> Vec<Offense> {
+        let mut offenses = Vec::new();
+        let string_regex = Regex::new(r#"'([^'\\]*(\\.[^'\\]*)*)'"#).unwrap();
+
+        for (line_num, line) in source.lines.iter().enumerate() {
+            let line_number = line_num + 1;
+
+            for capture in string_regex.captures_iter(line) {
+                if let Some(matched) = capture.get(0) {
+                    let start_col = matched.start() + 1;
+                    let content = capture.get(1).map(|m| m.as_str()).unwrap_or("");
+
+                    if source.in_string_or_comment(line_number, start_col) {
+                        continue;
+                    }
+
+                    // Quotes, backslashes and `#` would need escaping or
+                    // change meaning inside double quotes
+                    if content.contains(['"', '\\', '#']) {
+                        continue;
+                    }
+
+                    offenses.push(Offense::new(
+                        self.name(),
+                        "Prefer double-quoted strings unless you need single quotes to avoid extra backslashes for escaping",
+                        self.severity(),
+                        Location::new(line_number, start_col, matched.len()),
+                    ));
+                }
+            }
+        }
+
+        offenses
+    }
+}
+
+impl Default for StringLiterals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Cop for StringLiterals {
     fn name(&self) -> &str {
@@ -104,6 +171,10 @@ impl Cop for StringLiterals {
     }
 
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
+        if self.style == QuoteStyle::DoubleQuotes {
+            return self.check_single_quoted(source);
+        }
+
         let mut offenses = Vec::new();
 
         // Match double-quoted strings
@@ -469,7 +540,7 @@ mod tests {
     #[test]
     fn test_string_literals_single_quotes_ok() {
         let source = test_source("x = 'hello world'\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
@@ -477,16 +548,30 @@ mod tests {
     #[test]
     fn test_string_literals_double_quotes_unnecessary() {
         let source = test_source("x = \"hello world\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 1);
         assert!(offenses[0].message.contains("single-quoted"));
     }
 
+    #[test]
+    fn test_string_literals_double_quotes_style() {
+        let config: CopConfig = serde_yaml::from_str("EnforcedStyle: double_quotes\n").unwrap();
+        let cop = StringLiterals::from_config(&config).unwrap();
+        let source = test_source("a = 'plain'\nb = 'say \"hi\"'\nc = \"ok\"\nd = '#{x}'\n");
+        let offenses = cop.check(&source);
+        assert_eq!(offenses.len(), 1);
+        assert_eq!(offenses[0].location, Location::new(1, 5, 7));
+        assert!(offenses[0].message.contains("double-quoted"));
+
+        let bad: CopConfig = serde_yaml::from_str("EnforcedStyle: backticks\n").unwrap();
+        assert!(StringLiterals::from_config(&bad).is_err());
+    }
+
     #[test]
     fn test_string_literals_interpolation_ok() {
         let source = test_source("x = \"hello #{name}\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
@@ -494,7 +579,7 @@ mod tests {
     #[test]
     fn test_string_literals_escape_sequences_ok() {
         let source = test_source("x = \"hello\\nworld\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
@@ -502,7 +587,7 @@ mod tests {
     #[test]
     fn test_string_literals_tab_escape_ok() {
         let source = test_source("x = \"hello\\tworld\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
@@ -510,7 +595,7 @@ mod tests {
     #[test]
     fn test_string_literals_escaped_quote() {
         let source = test_source("x = \"hello \\\"world\\\"\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         // Escaped quotes alone shouldn't require double quotes in our simplified model
         // But with backslash-letter detection, this might trigger
@@ -521,7 +606,7 @@ mod tests {
     #[test]
     fn test_string_literals_empty_string() {
         let source = test_source("x = \"\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 1);
     }
@@ -529,7 +614,7 @@ mod tests {
     #[test]
     fn test_string_literals_multiple_strings() {
         let source = test_source("x = \"hello\"\ny = \"world\"\nz = 'ok'\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 2);
     }
@@ -537,7 +622,7 @@ mod tests {
     #[test]
     fn test_string_literals_in_comment() {
         let source = test_source("# This is a \"comment\"\nx = 'hello'\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         // The string in the comment shouldn't be flagged
         assert_eq!(offenses.len(), 0);
@@ -546,7 +631,7 @@ mod tests {
     #[test]
     fn test_string_literals_unicode_escape() {
         let source = test_source("x = \"hello\\u0041\"\n");
-        let cop = StringLiterals;
+        let cop = StringLiterals::new();
         let offenses = cop.check(&source);
         assert_eq!(offenses.len(), 0);
     }
@@ -769,7 +854,7 @@ mod tests {
         );
         
         let frozen = FrozenStringLiteralComment;
-        let strings = StringLiterals;
+        let strings = StringLiterals::new();
         let negated = NegatedIf;
         let redundant = RedundantReturn;
         let empty = EmptyMethod;
@@ -798,7 +883,7 @@ mod tests {
         );
         
         let frozen = FrozenStringLiteralComment;
-        let strings = StringLiterals;
+        let strings = StringLiterals::new();
         let negated = NegatedIf;
         let redundant = RedundantReturn;
         let empty = EmptyMethod;
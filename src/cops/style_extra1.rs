@@ -4180,6 +4180,10 @@ impl Cop for SafeNavigation {
         "Use safe navigation operator &."
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.3)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let nil_check_regex = Regex::new(r#"(\w+)\s*&&\s*(\w+)\."#).unwrap();
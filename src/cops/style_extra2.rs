@@ -594,6 +594,10 @@ impl Cop for UnpackFirst {
         "Checks for [0] after unpack, suggest .first"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.4)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let unpack_regex = Regex::new(r#"\*\w+\[0\]"#).unwrap();
@@ -1217,6 +1221,10 @@ impl Cop for ArrayIntersect {
         "Checks for (a & b).any? instead of a.intersect?(b)"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.1)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let intersect_regex = Regex::new(r#"\([^)]+&[^)]+\)\.any\?"#).unwrap();
@@ -1704,6 +1712,10 @@ impl Cop for DataInheritance {
         "Checks for class inheriting from Data"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.2)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let data_inherit_regex = Regex::new(r#"^\s*class\s+\w+\s*<\s*Data\s*$"#).unwrap();
@@ -1835,6 +1847,10 @@ impl Cop for DirEmpty {
         "Checks for Dir.entries.size == 2 instead of Dir.empty?"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.4)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let dir_empty_regex = Regex::new(r#"Dir\.entries.*\.size\s*==\s*2"#).unwrap();
@@ -2185,6 +2201,10 @@ impl Cop for EndlessMethod {
         "Checks for endless method definitions"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.0)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let offenses = Vec::new();
         let endless_regex = Regex::new(r#"^\s*def\s+\w+.*=\s*[^=]"#).unwrap();
@@ -2547,6 +2567,10 @@ impl Cop for FileEmpty {
         "Checks for File.size == 0 instead of File.empty?"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.4)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let file_size_regex = Regex::new(r#"File\.size.*==\s*0"#).unwrap();
@@ -2908,6 +2932,10 @@ impl Cop for HashExcept {
         "Checks for reject with key check instead of except"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.0)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let reject_key_regex = Regex::new(r#"\.reject\s*\{\s*\|[^|]*\|\s*\[[^]]*\]"#).unwrap();
@@ -2977,6 +3005,10 @@ impl Cop for HashSlice {
         "Checks for select with keys instead of slice"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.5)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let select_keys_regex = Regex::new(r#"\.select\s*\{\s*\|[^|]*\|\s*\[[^]]*\]\.include\?"#).unwrap();
@@ -3020,6 +3052,10 @@ impl Cop for HashTransformKeys {
         "Checks for map instead of transform_keys"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.5)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let map_keys_regex = Regex::new(r#"\.map\s*\{\s*\|([^,|]+),"#).unwrap();
@@ -3064,6 +3100,10 @@ impl Cop for HashTransformValues {
         "Checks for map instead of transform_values"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.5)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let map_values_regex = Regex::new(r#"\.map\s*\{\s*\|[^,|]+,\s*([^|]+)\|"#).unwrap();
@@ -3188,6 +3228,10 @@ impl Cop for InPatternThen {
         "Checks for then in pattern matching"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.7)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let _in_pattern_regex = Regex::new(r#"^\s*in\s+.*[^t][^h][^e][^n]\s*$"#).unwrap();
@@ -3446,6 +3490,10 @@ impl Cop for MapToHash {
         "Checks for .map.to_h instead of .to_h with block"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.6)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let map_to_h_regex = Regex::new(r##"\.map\s*\{[^}]+\}\.to_h"##).unwrap();
@@ -4017,6 +4065,10 @@ impl Cop for NumberedParameters {
         "Checks for numbered parameters"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.7)
+    }
+
     fn check(&self, _source: &SourceFile) -> Vec<Offense> {
         // This checks if numbered params should be used
         Vec::new()
@@ -4043,6 +4095,10 @@ impl Cop for ObjectThen {
         "Checks for .yield_self instead of .then"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.6)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let yield_self_regex = Regex::new(r#"\.yield_self"#).unwrap();
@@ -2284,6 +2284,10 @@ impl Cop for SlicingWithRange {
         "Use first/last/take/drop instead of slice with range"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.6)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"\[0\.\.(\d+|\.)\]"#).unwrap();
@@ -3171,6 +3175,10 @@ impl Cop for ArgumentsForwarding {
         "Use ... for argument forwarding in Ruby 2.7+"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.7)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"def\s+\w+\(\*args,\s*\*\*kwargs,\s*&block\)"#).unwrap();
@@ -3623,6 +3631,10 @@ impl Cop for ComparableClamp {
         "Use clamp for min/max constraints"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.4)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"\[\[(\w+),\s*\d+\]\.max,\s*\d+\]\.min"#).unwrap();
@@ -4009,6 +4021,10 @@ impl Cop for ItBlockParameter {
         r#"Use 'it' for single-parameter blocks in Ruby 3.4+"#
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(3.4)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"\{\s*\|(\w+)\|\s*(\w+)\s*\}"#).unwrap();
@@ -4341,6 +4357,10 @@ impl Cop for MultilineInPatternThen {
         "Do not use then in multiline pattern matching"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.7)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"^\s*in\s+.+\s+then\s*$"#).unwrap();
@@ -4535,6 +4555,10 @@ impl Cop for NumberedParametersLimit {
         "Limit use of numbered parameters"
     }
 
+    fn minimum_target_ruby_version(&self) -> Option<f64> {
+        Some(2.7)
+    }
+
     fn check(&self, source: &SourceFile) -> Vec<Offense> {
         let mut offenses = Vec::new();
         let regex = Regex::new(r#"\{[^}]*_[3-9][^}]*\}"#).unwrap();
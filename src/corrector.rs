@@ -0,0 +1,103 @@
+//! Applies autocorrect edits carried by offenses to source text.
+
+use crate::cop::Cop;
+use crate::offense::{Edit, Offense};
+
+/// Which corrections to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutocorrectMode {
+    /// Only corrections marked safe (`--autocorrect`).
+    Safe,
+    /// Safe and unsafe corrections (`--autocorrect-all`).
+    All,
+}
+
+impl AutocorrectMode {
+    /// Whether offenses of `cop` should be corrected in this mode.
+    pub fn allows(&self, cop: &dyn Cop) -> bool {
+        cop.supports_autocorrect() && (*self == AutocorrectMode::All || cop.safe_autocorrect())
+    }
+}
+
+/// Applies the edits of every offense accepted by `allowed`, in order.
+/// An offense whose edits overlap edits already taken is left for a later
+/// pass. Applied offenses are marked as corrected. Returns the new source.
+pub fn apply(source: &str, offenses: &mut [Offense], allowed: impl Fn(&Offense) -> bool) -> String {
+    let mut accepted: Vec<&Edit> = Vec::new();
+    for offense in offenses.iter_mut() {
+        if !offense.is_correctable() || !allowed(offense) {
+            continue;
+        }
+        let valid = offense.edits.iter().all(|edit| {
+            edit.start <= edit.end
+                && edit.end <= source.len()
+                && source.is_char_boundary(edit.start)
+                && source.is_char_boundary(edit.end)
+        });
+        let conflicting = offense.edits.iter().enumerate().any(|(i, edit)| {
+            accepted.iter().any(|taken| taken.conflicts_with(edit))
+                || offense.edits[..i].iter().any(|own| own.conflicts_with(edit))
+        });
+        if !valid || conflicting {
+            continue;
+        }
+        offense.corrected = true;
+        accepted.extend(offense.edits.iter());
+    }
+
+    accepted.sort_by_key(|edit| edit.start);
+    let mut output = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for edit in accepted {
+        output.push_str(&source[cursor..edit.start]);
+        output.push_str(&edit.replacement);
+        cursor = edit.end;
+    }
+    output.push_str(&source[cursor..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cop::Severity;
+    use crate::offense::Location;
+
+    fn offense(cop: &str, edit: Edit) -> Offense {
+        Offense::new(cop, "msg", Severity::Convention, Location::new(1, 1, 1)).with_edit(edit)
+    }
+
+    #[test]
+    fn test_apply_edits_in_order() {
+        let mut offenses = vec![
+            offense("A", Edit::insert(2, " ")),
+            offense("B", Edit::remove(3, 4)),
+        ];
+        let output = apply("a,b  c\n", &mut offenses, |_| true);
+        assert_eq!(output, "a, b c\n");
+        assert!(offenses.iter().all(|o| o.corrected));
+    }
+
+    #[test]
+    fn test_conflicting_edits_are_deferred() {
+        let mut offenses = vec![
+            offense("A", Edit::replace(0, 3, "bar")),
+            offense("B", Edit::remove(2, 4)),
+        ];
+        let output = apply("foo baz", &mut offenses, |_| true);
+        assert_eq!(output, "bar baz");
+        assert!(offenses[0].corrected);
+        assert!(!offenses[1].corrected);
+    }
+
+    #[test]
+    fn test_disallowed_and_invalid_edits_are_skipped() {
+        let mut offenses = vec![
+            offense("Unsafe/Cop", Edit::remove(0, 1)),
+            offense("A", Edit::remove(3, 99)),
+        ];
+        let output = apply("abc", &mut offenses, |o| o.cop_name != "Unsafe/Cop");
+        assert_eq!(output, "abc");
+        assert!(offenses.iter().all(|o| !o.corrected));
+    }
+}
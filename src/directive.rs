@@ -0,0 +1,455 @@
+//! Inline `# rubocop:disable` / `enable` / `todo` comments, also accepted
+//! with an `oxicop:` prefix.
+//!
+//! A directive on a line of its own disables the listed cops until a
+//! matching `enable` (or the end of the file); one trailing code disables
+//! them for that line only. Names may be cops (`Style/Foo`), departments
+//! (`Style`) or `all`.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::lexer::TokenKind;
+use crate::source::SourceFile;
+
+/// Found anywhere in a comment, as in RuboCop, so a directive can follow
+/// another comment: `foo # legacy API # rubocop:disable Style/Foo`.
+static DIRECTIVE_PREFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"#\s*(?:rubocop|oxicop)\s*:").unwrap());
+
+static COP_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:all\b|[A-Z][A-Za-z0-9_]*(?:/[A-Z][A-Za-z0-9_]*)*)").unwrap());
+
+/// What a directive does to the cops it names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveMode {
+    Disable,
+    Enable,
+    /// Like `disable`, written by `--auto-gen-config` style workflows.
+    Todo,
+}
+
+/// A cop, department or `all` named by a directive, with its position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveName {
+    pub name: String,
+    pub line: usize,
+    /// 1-based character column.
+    pub column: usize,
+}
+
+/// A well-formed directive comment.
+#[derive(Debug, Clone)]
+pub struct Directive {
+    pub mode: DirectiveMode,
+    pub names: Vec<DirectiveName>,
+    pub line: usize,
+    /// 1-based character column of the `#`.
+    pub column: usize,
+    /// Length of the comment in characters.
+    pub length: usize,
+    /// Whether code precedes the comment on its line.
+    pub inline: bool,
+}
+
+/// A comment that looks like a directive but can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedDirective {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub reason: &'static str,
+}
+
+/// Lines on which a name is disabled, inclusive on both ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisabledRange {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Index into [`Directives::directives`] of the disabling comment.
+    pub directive: usize,
+    /// Index into that directive's names.
+    pub name_index: usize,
+    /// Whether the range runs to the end of the file without an `enable`.
+    pub unclosed: bool,
+    /// Cops of a department or `all` range turned back on by their own
+    /// `enable`, with the line they are enabled from.
+    pub reenabled: Vec<(String, usize)>,
+}
+
+impl DisabledRange {
+    /// Whether the range disables `cop_name` on `line`.
+    fn covers(&self, cop_name: &str, line: usize) -> bool {
+        self.start_line <= line
+            && line <= self.end_line
+            && name_matches(&self.name, cop_name)
+            && !self
+                .reenabled
+                .iter()
+                .any(|(name, from)| name_matches(name, cop_name) && *from <= line)
+    }
+}
+
+/// All directives of a file and the line ranges they disable.
+#[derive(Debug, Clone, Default)]
+pub struct Directives {
+    pub directives: Vec<Directive>,
+    pub malformed: Vec<MalformedDirective>,
+    pub ranges: Vec<DisabledRange>,
+    /// `(directive, name_index)` of `enable`s with nothing to re-enable.
+    pub redundant_enables: Vec<(usize, usize)>,
+}
+
+/// Whether a directive name covers the given cop.
+pub fn name_matches(name: &str, cop_name: &str) -> bool {
+    name == "all"
+        || name == cop_name
+        || cop_name
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl Directives {
+    /// Parses the directive comments of a source file.
+    pub fn parse(source: &SourceFile) -> Self {
+        let mut result = Directives::default();
+        for token in &source.tokens {
+            if token.kind != TokenKind::Comment {
+                continue;
+            }
+            let text = source.token_text(token);
+            let Some(prefix) = DIRECTIVE_PREFIX.find(text) else {
+                continue;
+            };
+            let (line, comment_column) = source.position_of(token.start);
+            let column = comment_column + text[..prefix.start()].chars().count();
+            let length = text[prefix.start()..].chars().count();
+            let inline = source
+                .line(line)
+                .and_then(|l| l.get(..token.start - source.offset_of(line, 1).unwrap_or(0)))
+                .is_some_and(|before| !before.trim().is_empty());
+            let malformed = |reason| MalformedDirective {
+                line,
+                column,
+                length,
+                reason,
+            };
+            match parse_body(&text[prefix.end()..]) {
+                Ok((mode, names, problem)) => {
+                    if let Some(reason) = problem {
+                        result.malformed.push(malformed(reason));
+                    }
+                    let prefix_chars = prefix.as_str().chars().count();
+                    let names = names
+                        .into_iter()
+                        .map(|(name, offset)| DirectiveName {
+                            name,
+                            line,
+                            column: column + prefix_chars + offset,
+                        })
+                        .collect();
+                    result.directives.push(Directive {
+                        mode,
+                        names,
+                        line,
+                        column,
+                        length,
+                        inline,
+                    });
+                }
+                Err(reason) => result.malformed.push(malformed(reason)),
+            }
+        }
+        result.build_ranges(source.line_count());
+        result
+    }
+
+    fn build_ranges(&mut self, last_line: usize) {
+        let mut open: Vec<OpenRange> = Vec::new();
+        for (index, directive) in self.directives.iter().enumerate() {
+            for (name_index, entry) in directive.names.iter().enumerate() {
+                let name = entry.name.clone();
+                match directive.mode {
+                    DirectiveMode::Disable | DirectiveMode::Todo if directive.inline => {
+                        self.ranges.push(DisabledRange {
+                            name,
+                            start_line: directive.line,
+                            end_line: directive.line,
+                            directive: index,
+                            name_index,
+                            unclosed: false,
+                            reenabled: Vec::new(),
+                        });
+                    }
+                    DirectiveMode::Disable | DirectiveMode::Todo => {
+                        if !open.iter().any(|range| range.name == name) {
+                            open.push(OpenRange {
+                                name,
+                                start_line: directive.line,
+                                directive: index,
+                                name_index,
+                                reenabled: Vec::new(),
+                            });
+                        }
+                    }
+                    DirectiveMode::Enable => {
+                        // Close every range the name covers: a department
+                        // or `all` closes the ranges of its cops too.
+                        let closing: Vec<usize> = open
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, range)| name_matches(&name, &range.name))
+                            .map(|(i, _)| i)
+                            .collect();
+                        let mut covered = !closing.is_empty();
+                        for i in closing.into_iter().rev() {
+                            let range = open.remove(i);
+                            self.ranges.push(range.close(directive.line, false));
+                        }
+                        // Re-enabling one cop inside a disabled department
+                        // or `all` block turns that cop back on while the
+                        // rest of the block stays disabled.
+                        for range in open.iter_mut().filter(|range| name_matches(&range.name, &name)) {
+                            if !range.reenabled.iter().any(|(n, _)| *n == name) {
+                                range.reenabled.push((name.clone(), directive.line + 1));
+                                covered = true;
+                            }
+                        }
+                        if !covered {
+                            self.redundant_enables.push((index, name_index));
+                        }
+                    }
+                }
+            }
+        }
+        for range in open {
+            let end_line = last_line.max(range.start_line);
+            self.ranges.push(range.close(end_line, true));
+        }
+        self.ranges.sort_by_key(|r| (r.directive, r.name_index));
+    }
+
+    /// Indices of the ranges disabling `cop_name` on `line`.
+    pub fn disabling_ranges<'a>(
+        &'a self,
+        cop_name: &'a str,
+        line: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.ranges
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, range)| range.covers(cop_name, line).then_some(i))
+    }
+
+    /// Whether `cop_name` is disabled on `line`.
+    pub fn is_disabled(&self, cop_name: &str, line: usize) -> bool {
+        self.disabling_ranges(cop_name, line).next().is_some()
+    }
+
+    /// The name entry a range was created from.
+    pub fn range_name(&self, range: &DisabledRange) -> &DirectiveName {
+        &self.directives[range.directive].names[range.name_index]
+    }
+}
+
+/// A block range whose `enable` hasn't been seen yet.
+struct OpenRange {
+    name: String,
+    start_line: usize,
+    directive: usize,
+    name_index: usize,
+    reenabled: Vec<(String, usize)>,
+}
+
+impl OpenRange {
+    fn close(self, end_line: usize, unclosed: bool) -> DisabledRange {
+        DisabledRange {
+            name: self.name,
+            start_line: self.start_line,
+            end_line,
+            directive: self.directive,
+            name_index: self.name_index,
+            unclosed,
+            reenabled: self.reenabled,
+        }
+    }
+}
+
+/// Parses the text after `rubocop:` into a mode and names with their
+/// character offsets. A trailing problem is reported alongside the names
+/// that did parse, which still take effect.
+#[allow(clippy::type_complexity)]
+fn parse_body(
+    body: &str,
+) -> Result<(DirectiveMode, Vec<(String, usize)>, Option<&'static str>), &'static str> {
+    let mode_start = body.len() - body.trim_start().len();
+    let mode_text: String = body[mode_start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_')
+        .collect();
+    let mode = match mode_text.as_str() {
+        "" => return Err("The mode name is missing."),
+        "disable" => DirectiveMode::Disable,
+        "enable" => DirectiveMode::Enable,
+        "todo" => DirectiveMode::Todo,
+        _ => return Err("The mode name must be one of `enable`, `disable`, or `todo`."),
+    };
+
+    let after_mode = mode_start + mode_text.len();
+    let list_end = body[after_mode..]
+        .find("--")
+        .map_or(body.len(), |i| after_mode + i);
+    let list = &body[after_mode..list_end];
+    if list.trim().is_empty() {
+        return Err("The cop name is missing.");
+    }
+
+    let mut names = Vec::new();
+    let mut pos = after_mode;
+    let mut problem = None;
+    loop {
+        pos += body[pos..list_end].len() - body[pos..list_end].trim_start().len();
+        let Some(found) = COP_NAME.find(&body[pos..list_end]) else {
+            problem = Some("Cop names must be separated by commas. Comment in the directive must start with `--`.");
+            break;
+        };
+        names.push((found.as_str().to_string(), body[..pos].chars().count()));
+        pos += found.end();
+        let rest = body[pos..list_end].trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(after_comma) = rest.strip_prefix(',') {
+            pos = list_end - after_comma.len();
+        } else {
+            problem = Some("Cop names must be separated by commas. Comment in the directive must start with `--`.");
+            break;
+        }
+    }
+    if names.is_empty() {
+        return Err(problem.unwrap_or("The cop name is missing."));
+    }
+    Ok((mode, names, problem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn directives(content: &str) -> Directives {
+        Directives::parse(&SourceFile::from_string(PathBuf::from("test.rb"), content.to_string()))
+    }
+
+    #[test]
+    fn test_inline_disable_covers_only_its_line() {
+        let d = directives("foo # rubocop:disable Style/Foo\nbar\n");
+        assert!(d.is_disabled("Style/Foo", 1));
+        assert!(!d.is_disabled("Style/Foo", 2));
+        assert!(!d.is_disabled("Style/Bar", 1));
+    }
+
+    #[test]
+    fn test_block_disable_until_enable() {
+        let d = directives(
+            "# rubocop:disable Style/Foo, Lint\na\nb\n# rubocop:enable Style/Foo, Lint\nc\n",
+        );
+        assert!(d.is_disabled("Style/Foo", 2));
+        assert!(d.is_disabled("Lint/Debugger", 3));
+        assert!(!d.is_disabled("Lint/Debugger", 5));
+        assert!(d.ranges.iter().all(|r| !r.unclosed));
+    }
+
+    #[test]
+    fn test_oxicop_alias_todo_and_all() {
+        let d = directives("# oxicop:todo all\nx\n");
+        assert!(d.is_disabled("Naming/MethodName", 2));
+        assert!(d.ranges[0].unclosed);
+    }
+
+    #[test]
+    fn test_enable_all_closes_every_range() {
+        let d = directives("# rubocop:disable A/B\n# rubocop:disable C\nx\n# rubocop:enable all\ny\n");
+        assert!(d.is_disabled("C/D", 3));
+        assert!(!d.is_disabled("A/B", 5));
+        assert!(!d.is_disabled("C/D", 5));
+    }
+
+    #[test]
+    fn test_name_columns_and_trailing_comment() {
+        let d = directives("x = 1 # rubocop:disable Style/Foo, Lint/Bar -- legacy\n");
+        let names = &d.directives[0].names;
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0].column, 25);
+        assert_eq!(names[1].column, 36);
+        assert!(d.malformed.is_empty());
+    }
+
+    #[test]
+    fn test_directive_after_another_comment() {
+        let d = directives("x = 1 # legacy # rubocop:disable Style/Foo\n# see docs # rubocop:disable Lint\ny\n");
+        assert!(d.is_disabled("Style/Foo", 1));
+        assert!(!d.is_disabled("Style/Foo", 2));
+        assert!(d.is_disabled("Lint/Debugger", 3));
+        let directive = &d.directives[0];
+        assert!(directive.inline);
+        assert_eq!((directive.column, directive.length), (16, 27));
+        assert_eq!(directive.names[0].column, 34);
+        assert!(!d.directives[1].inline);
+    }
+
+    #[test]
+    fn test_malformed_directives() {
+        let d = directives("# rubocop:\n# rubocop:disable\n# rubocop:disabled Foo\n# rubocop:disable Style/A Style/B\n");
+        let reasons: Vec<&str> = d.malformed.iter().map(|m| m.reason).collect();
+        assert_eq!(reasons.len(), 4);
+        assert!(reasons[0].contains("mode name is missing"));
+        assert!(reasons[1].contains("cop name is missing"));
+        assert!(reasons[2].contains("must be one of"));
+        assert!(reasons[3].contains("separated by commas"));
+        // The name before the problem still applies.
+        assert!(d.is_disabled("Style/A", 4));
+    }
+
+    #[test]
+    fn test_redundant_enable() {
+        let d = directives("# rubocop:enable Style/Foo\n# rubocop:disable Lint\n# rubocop:enable Lint/Debugger\n");
+        assert_eq!(d.redundant_enables, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_enable_one_cop_inside_department_block() {
+        let d = directives(
+            "# rubocop:disable Layout\na\n# rubocop:enable Layout/TrailingWhitespace\nb\n\
+             # rubocop:disable Layout/TrailingWhitespace\nc\n# rubocop:enable Layout\nd\n",
+        );
+        assert!(d.is_disabled("Layout/TrailingWhitespace", 2));
+        // The cop reports again after its own enable, the rest of the
+        // department stays disabled
+        assert!(!d.is_disabled("Layout/TrailingWhitespace", 4));
+        assert!(d.is_disabled("Layout/SpaceAfterComma", 4));
+        // Until it is disabled again
+        assert!(d.is_disabled("Layout/TrailingWhitespace", 6));
+        // The department enable closes the cop's own range as well
+        assert!(!d.is_disabled("Layout/TrailingWhitespace", 8));
+        assert!(!d.is_disabled("Layout/SpaceAfterComma", 8));
+        assert!(d.ranges.iter().all(|r| !r.unclosed));
+        assert!(d.redundant_enables.is_empty());
+
+        let d = directives("# rubocop:disable all\n# rubocop:enable Lint/Debugger\nx\n# rubocop:enable Lint/Debugger\n");
+        assert!(!d.is_disabled("Lint/Debugger", 3));
+        assert!(d.is_disabled("Lint/UselessAssignment", 3));
+        assert_eq!(d.redundant_enables, vec![(2, 0)]);
+
+        let d = directives("# rubocop:disable all\n# rubocop:enable Lint\nx\n");
+        assert!(!d.is_disabled("Lint/Debugger", 3));
+        assert!(d.is_disabled("Layout/TrailingWhitespace", 3));
+    }
+
+    #[test]
+    fn test_directive_in_string_is_ignored() {
+        let d = directives("x = '# rubocop:disable all'\n");
+        assert!(d.directives.is_empty());
+    }
+}
@@ -1,9 +1,16 @@
 //! Output formatters for linting results.
 
+use std::collections::HashMap;
+use std::path::Path;
+
 use colored::*;
 use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 
 use crate::cop::Severity;
+use crate::offense::Offense;
+use crate::registry::CopRegistry;
 use crate::runner::RunResult;
 
 /// Available output formats.
@@ -12,6 +19,14 @@ pub enum Format {
     Simple,
     Compact,
     Json,
+    RubocopJson,
+    Sarif,
+    Junit,
+    Checkstyle,
+    Github,
+    Gitlab,
+    Clang,
+    Pretty,
 }
 
 impl std::str::FromStr for Format {
@@ -22,11 +37,30 @@ impl std::str::FromStr for Format {
             "simple" => Ok(Format::Simple),
             "compact" => Ok(Format::Compact),
             "json" => Ok(Format::Json),
+            "rubocop-json" => Ok(Format::RubocopJson),
+            "sarif" => Ok(Format::Sarif),
+            "junit" => Ok(Format::Junit),
+            "checkstyle" => Ok(Format::Checkstyle),
+            "github" => Ok(Format::Github),
+            "gitlab" => Ok(Format::Gitlab),
+            "clang" => Ok(Format::Clang),
+            "pretty" => Ok(Format::Pretty),
             _ => Err(format!("unknown format: {}", s)),
         }
     }
 }
 
+/// Message prefixed with the offense's autocorrect status, as RuboCop does.
+fn status_message(offense: &Offense) -> String {
+    if offense.corrected {
+        format!("[Corrected] {}", offense.message)
+    } else if offense.is_correctable() {
+        format!("[Correctable] {}", offense.message)
+    } else {
+        offense.message.clone()
+    }
+}
+
 /// Trait for formatting linting results.
 pub trait Formatter {
     fn format(&self, result: &RunResult) -> String;
@@ -43,15 +77,20 @@ impl SimpleFormatter {
     }
 
     fn colorize_severity(&self, severity: Severity, text: &str) -> String {
-        if !self.use_colors {
-            return text.to_string();
-        }
-        match severity {
-            Severity::Error | Severity::Fatal => text.red().to_string(),
-            Severity::Warning => text.yellow().to_string(),
-            Severity::Convention | Severity::Refactor => text.cyan().to_string(),
-            Severity::Info => text.white().to_string(),
-        }
+        colorize_severity(self.use_colors, severity, text)
+    }
+}
+
+/// `text` in the color of `severity`.
+fn colorize_severity(use_colors: bool, severity: Severity, text: &str) -> String {
+    if !use_colors {
+        return text.to_string();
+    }
+    match severity {
+        Severity::Error | Severity::Fatal => text.red().to_string(),
+        Severity::Warning => text.yellow().to_string(),
+        Severity::Convention | Severity::Refactor => text.cyan().to_string(),
+        Severity::Info => text.white().to_string(),
     }
 }
 
@@ -74,22 +113,141 @@ impl Formatter for SimpleFormatter {
 
                 output.push_str(&format!(
                     "{}: {}: {} ({})\n",
-                    offense.location, colored_code, offense.message, offense.cop_name
+                    offense.location,
+                    colored_code,
+                    status_message(offense),
+                    offense.cop_name
                 ));
             }
 
             output.push('\n');
         }
 
-        // Summary
+        output.push_str(&summary(result));
+        output
+    }
+}
+
+/// The closing line of the human-readable formats.
+fn summary(result: &RunResult) -> String {
+    let mut output = format!(
+        "{} file{} inspected, {} offense{} detected",
+        result.total_files,
+        if result.total_files == 1 { "" } else { "s" },
+        result.total_offenses,
+        if result.total_offenses == 1 { "" } else { "s" },
+    );
+    let corrected = result.total_offenses - result.uncorrected_count();
+    if corrected > 0 {
         output.push_str(&format!(
-            "{} file{} inspected, {} offense{} detected\n",
-            result.total_files,
-            if result.total_files == 1 { "" } else { "s" },
-            result.total_offenses,
-            if result.total_offenses == 1 { "" } else { "s" },
+            ", {} offense{} corrected",
+            corrected,
+            if corrected == 1 { "" } else { "s" },
         ));
+    }
+    output.push('\n');
+    output
+}
+
+/// Offenses grouped by file, each followed by the offending source line
+/// with a `^^^` underline, as compilers report errors. `clang` shows the
+/// offending line alone, `pretty` a few lines around it as well.
+pub struct SourceFormatter {
+    use_colors: bool,
+    context_lines: usize,
+}
 
+impl SourceFormatter {
+    pub fn new(use_colors: bool, context_lines: usize) -> Self {
+        Self { use_colors, context_lines }
+    }
+
+    fn colorize(&self, severity: Severity, text: &str) -> String {
+        colorize_severity(self.use_colors, severity, text)
+    }
+
+    /// The lines around the offense with a line number gutter `width`
+    /// wide, and the underline below the offending one.
+    fn snippet(&self, offense: &Offense, lines: &[&str], width: usize) -> String {
+        let location = &offense.location;
+        let Some(line) = location.line.checked_sub(1).and_then(|index| lines.get(index)) else {
+            return String::new();
+        };
+        let first = location.line.saturating_sub(self.context_lines).max(1);
+        let last = (location.line + self.context_lines).min(lines.len());
+
+        let mut output = String::new();
+        for number in first..=last {
+            output.push_str(&format!("{:>width$} | {}\n", number, lines[number - 1]));
+            if number == location.line {
+                let underline = underline(line, location.column, location.length);
+                output.push_str(&format!("{:width$} | {}\n", "", self.colorize(offense.severity, &underline)));
+            }
+        }
+        output
+    }
+}
+
+/// Carets under `length` characters of `line` from the 1-based `column`,
+/// at least one and no further than the line's end. The tabs before the
+/// span are kept so the carets line up however tabs are displayed.
+fn underline(line: &str, column: usize, length: usize) -> String {
+    let start = column.saturating_sub(1);
+    let line_length = line.chars().count();
+    let indent: String = line
+        .chars()
+        .chain(std::iter::repeat(' '))
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let carets = length.min(line_length.saturating_sub(start)).max(1);
+    format!("{}{}", indent, "^".repeat(carets))
+}
+
+impl Formatter for SourceFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let mut output = String::new();
+
+        for file_result in &result.file_results {
+            if file_result.offenses.is_empty() {
+                continue;
+            }
+            let lines: Vec<&str> = file_result
+                .source
+                .as_deref()
+                .map(|source| source.lines().collect())
+                .unwrap_or_default();
+
+            // Line numbers are aligned across the file's snippets
+            let width = file_result
+                .offenses
+                .iter()
+                .map(|offense| (offense.location.line + self.context_lines).min(lines.len()))
+                .max()
+                .unwrap_or(0)
+                .to_string()
+                .len();
+
+            output.push_str(&format!("{}:\n", file_result.path.display()));
+            for offense in &file_result.offenses {
+                let code = offense.severity.code().to_string();
+                output.push_str(&format!(
+                    "{}: {}: {} ({})\n",
+                    offense.location,
+                    self.colorize(offense.severity, &code),
+                    status_message(offense),
+                    offense.cop_name
+                ));
+                // A corrected offense points into the source before its
+                // correction, which isn't kept
+                if !offense.corrected {
+                    output.push_str(&self.snippet(offense, &lines, width));
+                }
+            }
+            output.push('\n');
+        }
+
+        output.push_str(&summary(result));
         output
     }
 }
@@ -109,7 +267,7 @@ impl Formatter for CompactFormatter {
                     offense.location.line,
                     offense.location.column,
                     offense.severity.code(),
-                    offense.message,
+                    status_message(offense),
                     offense.cop_name
                 ));
             }
@@ -131,6 +289,10 @@ struct JsonOffense {
     severity: String,
     message: String,
     cop_name: String,
+    corrected: bool,
+    correctable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -154,6 +316,9 @@ impl Formatter for JsonFormatter {
                     severity: format!("{}", offense.severity.code()),
                     message: offense.message.clone(),
                     cop_name: offense.cop_name.clone(),
+                    corrected: offense.corrected,
+                    correctable: offense.is_correctable(),
+                    fingerprint: offense.fingerprint.clone(),
                 })
             })
             .collect();
@@ -168,12 +333,508 @@ impl Formatter for JsonFormatter {
     }
 }
 
-/// Creates a formatter based on the format type.
-pub fn create_formatter(format: Format) -> Box<dyn Formatter> {
+/// JSON in the schema of `rubocop --format json`, for tools written
+/// against RuboCop's output.
+pub struct RubocopJsonFormatter;
+
+#[derive(Serialize)]
+struct RubocopJsonOutput {
+    metadata: RubocopMetadata,
+    files: Vec<RubocopFile>,
+    summary: RubocopSummary,
+}
+
+/// RuboCop describes itself and the Ruby it runs on here. oxicop gives its
+/// own version and name, and leaves the Ruby version fields empty.
+#[derive(Serialize)]
+struct RubocopMetadata {
+    rubocop_version: &'static str,
+    ruby_engine: &'static str,
+    ruby_version: &'static str,
+    ruby_patchlevel: &'static str,
+    ruby_platform: String,
+}
+
+#[derive(Serialize)]
+struct RubocopFile {
+    path: String,
+    offenses: Vec<RubocopOffense>,
+}
+
+#[derive(Serialize)]
+struct RubocopOffense {
+    severity: Severity,
+    message: String,
+    cop_name: String,
+    corrected: bool,
+    correctable: bool,
+    location: RubocopLocation,
+}
+
+#[derive(Serialize)]
+struct RubocopLocation {
+    start_line: usize,
+    start_column: usize,
+    last_line: usize,
+    last_column: usize,
+    length: usize,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Serialize)]
+struct RubocopSummary {
+    offense_count: usize,
+    target_file_count: usize,
+    inspected_file_count: usize,
+}
+
+impl Formatter for RubocopJsonFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let files = result
+            .file_results
+            .iter()
+            .map(|file_result| RubocopFile {
+                path: file_result.path.display().to_string(),
+                offenses: file_result
+                    .offenses
+                    .iter()
+                    .map(|offense| {
+                        let location = &offense.location;
+                        RubocopOffense {
+                            severity: offense.severity,
+                            // RuboCop names the cop in the message too
+                            message: format!("{}: {}", offense.cop_name, offense.message),
+                            cop_name: offense.cop_name.clone(),
+                            corrected: offense.corrected,
+                            correctable: offense.is_correctable(),
+                            location: RubocopLocation {
+                                start_line: location.line,
+                                start_column: location.column,
+                                last_line: location.line,
+                                last_column: (location.column + location.length).saturating_sub(1),
+                                length: location.length,
+                                line: location.line,
+                                column: location.column,
+                            },
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let output = RubocopJsonOutput {
+            metadata: RubocopMetadata {
+                rubocop_version: env!("CARGO_PKG_VERSION"),
+                ruby_engine: "oxicop",
+                ruby_version: "",
+                ruby_patchlevel: "",
+                ruby_platform: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+            },
+            files,
+            summary: RubocopSummary {
+                offense_count: result.total_offenses,
+                target_file_count: result.total_files,
+                inspected_file_count: result.total_files,
+            },
+        };
+
+        serde_json::to_string(&output).unwrap()
+    }
+}
+
+/// SARIF 2.1.0 output, as consumed by code-scanning dashboards.
+///
+/// `tool.driver.rules` describes each cop that reported an offense, and
+/// every offense becomes a `result` pointing at its rule. Offense
+/// fingerprints are passed on as `partialFingerprints` so that results
+/// de-duplicate across runs.
+pub struct SarifFormatter {
+    /// Description and default severity of each known cop.
+    rules: HashMap<String, (String, Severity)>,
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+impl SarifFormatter {
+    /// Creates a formatter describing rules by the cops of `registry`.
+    pub fn new(registry: &CopRegistry) -> Self {
+        let rules = registry
+            .cop_names()
+            .into_iter()
+            .filter_map(|name| registry.cop(name))
+            .map(|cop| (cop.name().to_string(), (cop.description().to_string(), cop.severity())))
+            .collect();
+        Self { rules }
+    }
+}
+
+impl Formatter for SarifFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let mut cop_names: Vec<&str> = result
+            .file_results
+            .iter()
+            .flat_map(|file_result| file_result.offenses.iter().map(|offense| offense.cop_name.as_str()))
+            .collect();
+        cop_names.sort_unstable();
+        cop_names.dedup();
+
+        let rules: Vec<Value> = cop_names
+            .iter()
+            .map(|&name| {
+                let rule = self.rules.get(name);
+                json!({
+                    "id": name,
+                    "name": name,
+                    "shortDescription": { "text": rule.map_or(name, |(description, _)| description.as_str()) },
+                    "helpUri": cop_docs_url(name),
+                    "defaultConfiguration": {
+                        "level": sarif_level(rule.map_or(Severity::Warning, |&(_, severity)| severity)),
+                    },
+                })
+            })
+            .collect();
+
+        let results: Vec<Value> = result
+            .file_results
+            .iter()
+            .flat_map(|file_result| {
+                let cop_names = &cop_names;
+                file_result.offenses.iter().map(move |offense| {
+                    let location = &offense.location;
+                    let mut sarif_result = json!({
+                        "ruleId": offense.cop_name,
+                        "ruleIndex": cop_names.binary_search(&offense.cop_name.as_str()).unwrap_or(0),
+                        "level": sarif_level(offense.severity),
+                        "message": { "text": offense.message },
+                        "locations": [{
+                            "physicalLocation": {
+                                "artifactLocation": artifact_location(&file_result.path),
+                                "region": {
+                                    "startLine": location.line,
+                                    "startColumn": location.column,
+                                    "endColumn": location.column + location.length.max(1),
+                                },
+                            },
+                        }],
+                    });
+                    if let Some(fingerprint) = &offense.fingerprint {
+                        sarif_result["partialFingerprints"] = json!({ "oxicop/v1": fingerprint });
+                    }
+                    sarif_result
+                })
+            })
+            .collect();
+
+        let output = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "oxicop",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    },
+                },
+                // Offense columns count characters, not UTF-16 code units
+                "columnKind": "unicodeCodePoints",
+                "results": results,
+            }],
+        });
+
+        serde_json::to_string_pretty(&output).unwrap()
+    }
+}
+
+/// SARIF level of a severity.
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Convention | Severity::Refactor | Severity::Info => "note",
+    }
+}
+
+/// The cop's page in the RuboCop documentation.
+fn cop_docs_url(name: &str) -> String {
+    let department = name.split('/').next().unwrap_or(name).to_lowercase();
+    let anchor: String = name.chars().filter(|c| *c != '/').collect::<String>().to_lowercase();
+    format!("https://docs.rubocop.org/rubocop/cops_{}.html#{}", department, anchor)
+}
+
+/// An `artifactLocation` for `path`. Relative paths are given relative to
+/// the source root, absolute ones as `file://` URIs.
+fn artifact_location(path: &Path) -> Value {
+    let text = path.to_string_lossy().replace('\\', "/");
+    if path.is_absolute() {
+        let text = if text.starts_with('/') { text } else { format!("/{}", text) };
+        json!({ "uri": format!("file://{}", percent_encode(&text)) })
+    } else {
+        let text = text.trim_start_matches("./");
+        json!({ "uri": percent_encode(text), "uriBaseId": "%SRCROOT%" })
+    }
+}
+
+/// Escapes the characters of a path that can't appear in a URI as is.
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// JUnit XML output for CI servers that show test reports.
+///
+/// Each file is a `testsuite` and each offense a failed `testcase` in it.
+/// A file without offenses gets a single passing `testcase`, so it counts
+/// as a passed test.
+pub struct JunitFormatter;
+
+impl Formatter for JunitFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let mut suites = String::new();
+        let mut tests = 0;
+        for file_result in &result.file_results {
+            let path = xml_escape(&file_result.path.display().to_string());
+            let failures = file_result.offenses.len();
+            tests += failures.max(1);
+            suites.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+                path,
+                failures.max(1),
+                failures
+            ));
+            if file_result.offenses.is_empty() {
+                suites.push_str(&format!("    <testcase classname=\"{}\" name=\"oxicop\"/>\n", path));
+            }
+            for offense in &file_result.offenses {
+                let cop_name = xml_escape(&offense.cop_name);
+                suites.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{} at {}\">\n",
+                    path, cop_name, offense.location
+                ));
+                suites.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}:{}: {}</failure>\n",
+                    cop_name,
+                    xml_escape(&offense.message),
+                    path,
+                    offense.location,
+                    xml_escape(&status_message(offense))
+                ));
+                suites.push_str("    </testcase>\n");
+            }
+            suites.push_str("  </testsuite>\n");
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"oxicop\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+            tests, result.total_offenses, suites
+        )
+    }
+}
+
+/// Checkstyle XML output, read by Jenkins' warnings plugin, Bitbucket and
+/// other tools built around Java's Checkstyle.
+pub struct CheckstyleFormatter;
+
+impl Formatter for CheckstyleFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n");
+        for file_result in &result.file_results {
+            output.push_str(&format!("  <file name=\"{}\">\n", xml_escape(&file_result.path.display().to_string())));
+            for offense in &file_result.offenses {
+                output.push_str(&format!(
+                    "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>\n",
+                    offense.location.line,
+                    offense.location.column,
+                    checkstyle_severity(offense.severity),
+                    xml_escape(&status_message(offense)),
+                    xml_escape(&offense.cop_name)
+                ));
+            }
+            output.push_str("  </file>\n");
+        }
+        output.push_str("</checkstyle>\n");
+        output
+    }
+}
+
+/// Checkstyle severity of a severity.
+fn checkstyle_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Convention | Severity::Refactor | Severity::Info => "info",
+    }
+}
+
+/// GitHub Actions workflow commands, which annotate the offending lines in
+/// the pull request's diff.
+pub struct GithubFormatter;
+
+impl Formatter for GithubFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let mut output = String::new();
+        for file_result in &result.file_results {
+            let path = escape_github_property(&file_result.path.display().to_string());
+            for offense in &file_result.offenses {
+                let location = &offense.location;
+                output.push_str(&format!(
+                    "::{} file={},line={},col={},endColumn={},title={}::{}\n",
+                    github_level(offense.severity),
+                    path,
+                    location.line,
+                    location.column,
+                    location.column + location.length,
+                    escape_github_property(&offense.cop_name),
+                    escape_github_data(&format!("{}: {}", offense.cop_name, status_message(offense)))
+                ));
+            }
+        }
+        output
+    }
+}
+
+/// Workflow command of a severity.
+fn github_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Convention | Severity::Refactor | Severity::Info => "notice",
+    }
+}
+
+/// Escapes a workflow command's message.
+fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes a workflow command's property value, where `:` and `,` would
+/// end the value.
+fn escape_github_property(text: &str) -> String {
+    escape_github_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// GitLab Code Quality report, shown in merge request widgets.
+pub struct GitlabFormatter;
+
+#[derive(Serialize)]
+struct GitlabIssue {
+    description: String,
+    check_name: String,
+    fingerprint: String,
+    severity: &'static str,
+    location: GitlabLocation,
+}
+
+#[derive(Serialize)]
+struct GitlabLocation {
+    path: String,
+    lines: GitlabLines,
+}
+
+#[derive(Serialize)]
+struct GitlabLines {
+    begin: usize,
+}
+
+impl Formatter for GitlabFormatter {
+    fn format(&self, result: &RunResult) -> String {
+        let issues: Vec<GitlabIssue> = result
+            .file_results
+            .iter()
+            .flat_map(|file_result| {
+                // GitLab expects paths relative to the repository root
+                let path = file_result.path.display().to_string();
+                let path = path.trim_start_matches("./").to_string();
+                file_result.offenses.iter().map(move |offense| GitlabIssue {
+                    description: offense.message.clone(),
+                    check_name: offense.cop_name.clone(),
+                    fingerprint: offense
+                        .fingerprint
+                        .clone()
+                        .unwrap_or_else(|| fallback_fingerprint(&path, offense)),
+                    severity: gitlab_severity(offense.severity),
+                    location: GitlabLocation {
+                        path: path.clone(),
+                        lines: GitlabLines {
+                            begin: offense.location.line,
+                        },
+                    },
+                })
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&issues).unwrap()
+    }
+}
+
+/// Code Quality severity of a severity.
+fn gitlab_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "info",
+        Severity::Refactor | Severity::Convention => "minor",
+        Severity::Warning => "major",
+        Severity::Error => "critical",
+        Severity::Fatal => "blocker",
+    }
+}
+
+/// Identifies an offense that wasn't given a fingerprint by the runner,
+/// since GitLab drops issues sharing one.
+fn fallback_fingerprint(path: &str, offense: &Offense) -> String {
+    let location = &offense.location;
+    let digest = Sha256::digest(
+        format!("{}\0{}\0{}:{}\0{}", offense.cop_name, path, location.line, location.column, offense.message)
+            .as_bytes(),
+    );
+    digest[..16].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Escapes text for use in XML content and attribute values. Line breaks
+/// and tabs become character references so attributes keep them, and
+/// characters XML 1.0 doesn't allow at all become U+FFFD.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.push('\u{fffd}'),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Creates a formatter based on the format type. Formats that describe
+/// the cops behind offenses take them from `registry`.
+pub fn create_formatter(format: Format, registry: &CopRegistry) -> Box<dyn Formatter> {
     match format {
         Format::Simple => Box::new(SimpleFormatter::new(true)),
         Format::Compact => Box::new(CompactFormatter),
         Format::Json => Box::new(JsonFormatter),
+        Format::RubocopJson => Box::new(RubocopJsonFormatter),
+        Format::Sarif => Box::new(SarifFormatter::new(registry)),
+        Format::Junit => Box::new(JunitFormatter),
+        Format::Checkstyle => Box::new(CheckstyleFormatter),
+        Format::Github => Box::new(GithubFormatter),
+        Format::Gitlab => Box::new(GitlabFormatter),
+        Format::Clang => Box::new(SourceFormatter::new(true, 0)),
+        Format::Pretty => Box::new(SourceFormatter::new(true, 2)),
     }
 }
 
@@ -203,6 +864,7 @@ mod tests {
             file_results: vec![FileResult {
                 path: PathBuf::from("test.rb"),
                 offenses: vec![offense1, offense2],
+                source: Some("def one\n  1\nend\n\nfoo = bar  \n\ndef two\n  2\nend\n  debugger\n".into()),
             }],
             total_files: 1,
             total_offenses: 2,
@@ -214,6 +876,14 @@ mod tests {
         assert_eq!("simple".parse::<Format>(), Ok(Format::Simple));
         assert_eq!("compact".parse::<Format>(), Ok(Format::Compact));
         assert_eq!("json".parse::<Format>(), Ok(Format::Json));
+        assert_eq!("rubocop-json".parse::<Format>(), Ok(Format::RubocopJson));
+        assert_eq!("sarif".parse::<Format>(), Ok(Format::Sarif));
+        assert_eq!("junit".parse::<Format>(), Ok(Format::Junit));
+        assert_eq!("checkstyle".parse::<Format>(), Ok(Format::Checkstyle));
+        assert_eq!("github".parse::<Format>(), Ok(Format::Github));
+        assert_eq!("gitlab".parse::<Format>(), Ok(Format::Gitlab));
+        assert_eq!("clang".parse::<Format>(), Ok(Format::Clang));
+        assert_eq!("pretty".parse::<Format>(), Ok(Format::Pretty));
         assert_eq!("SIMPLE".parse::<Format>(), Ok(Format::Simple));
         assert!("invalid".parse::<Format>().is_err());
     }
@@ -262,6 +932,276 @@ mod tests {
         assert_eq!(offense["line"], 5);
         assert_eq!(offense["column"], 10);
         assert_eq!(offense["severity"], "C");
+        assert!(offense.get("fingerprint").is_none());
+
+        let mut result = create_test_result();
+        result.file_results[0].offenses[0].fingerprint = Some("0123abcd".to_string());
+        let parsed: serde_json::Value = serde_json::from_str(&formatter.format(&result)).unwrap();
+        assert_eq!(parsed["offenses"][0]["fingerprint"], "0123abcd");
+    }
+
+    #[test]
+    fn test_rubocop_json_formatter() {
+        let mut result = create_test_result();
+        result.file_results[0].offenses[0].edits.push(crate::offense::Edit::remove(0, 1));
+        result.file_results.push(FileResult {
+            path: PathBuf::from("clean.rb"),
+            offenses: vec![],
+            source: None,
+        });
+        result.total_files = 2;
+        let parsed: serde_json::Value = serde_json::from_str(&RubocopJsonFormatter.format(&result)).unwrap();
+
+        let metadata = parsed["metadata"].as_object().unwrap();
+        for key in ["rubocop_version", "ruby_engine", "ruby_version", "ruby_patchlevel", "ruby_platform"] {
+            assert!(metadata.contains_key(key), "missing {}", key);
+        }
+
+        let files = parsed["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["path"], "test.rb");
+        assert_eq!(files[1]["path"], "clean.rb");
+        assert!(files[1]["offenses"].as_array().unwrap().is_empty());
+
+        let offense = &files[0]["offenses"][0];
+        assert_eq!(offense["severity"], "convention");
+        assert_eq!(offense["message"], "Layout/TrailingWhitespace: Trailing whitespace detected.");
+        assert_eq!(offense["cop_name"], "Layout/TrailingWhitespace");
+        assert_eq!(offense["corrected"], false);
+        assert_eq!(offense["correctable"], true);
+        assert_eq!(
+            offense["location"],
+            serde_json::json!({
+                "start_line": 5, "start_column": 10, "last_line": 5, "last_column": 11,
+                "length": 2, "line": 5, "column": 10,
+            })
+        );
+        assert_eq!(files[0]["offenses"][1]["severity"], "warning");
+        assert_eq!(files[0]["offenses"][1]["correctable"], false);
+
+        assert_eq!(
+            parsed["summary"],
+            serde_json::json!({ "offense_count": 2, "target_file_count": 2, "inspected_file_count": 2 })
+        );
+    }
+
+    #[test]
+    fn test_sarif_formatter() {
+        let mut result = create_test_result();
+        result.file_results[0].offenses[1].fingerprint = Some("0123abcd".to_string());
+        result.file_results.push(FileResult {
+            path: PathBuf::from("./app/my models/user.rb"),
+            offenses: vec![Offense::new(
+                "Layout/TrailingWhitespace",
+                "Trailing whitespace detected.",
+                Severity::Convention,
+                Location::new(1, 4, 1),
+            )],
+            source: None,
+        });
+        let parsed: serde_json::Value = serde_json::from_str(&SarifFormatter::new(&CopRegistry::new()).format(&result)).unwrap();
+
+        assert_eq!(parsed["version"], "2.1.0");
+        let run = &parsed["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "oxicop");
+
+        // One rule per reported cop, with its default severity
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "Layout/TrailingWhitespace");
+        assert_eq!(rules[0]["defaultConfiguration"]["level"], "note");
+        assert_eq!(
+            rules[0]["helpUri"],
+            "https://docs.rubocop.org/rubocop/cops_layout.html#layouttrailingwhitespace"
+        );
+        assert_eq!(rules[1]["id"], "Lint/Debugger");
+        assert_eq!(rules[1]["defaultConfiguration"]["level"], "warning");
+        assert!(!rules[1]["shortDescription"]["text"].as_str().unwrap().is_empty());
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1]["ruleId"], "Lint/Debugger");
+        assert_eq!(results[1]["ruleIndex"], 1);
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(results[1]["message"]["text"], "Remove debugger statement.");
+        assert_eq!(results[1]["partialFingerprints"]["oxicop/v1"], "0123abcd");
+        assert!(results[0].get("partialFingerprints").is_none());
+
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "test.rb");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(location["region"]["startLine"], 10);
+        assert_eq!(location["region"]["startColumn"], 3);
+        assert_eq!(location["region"]["endColumn"], 11);
+
+        let location = &results[2]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "app/my%20models/user.rb");
+    }
+
+    #[test]
+    fn test_sarif_rules_come_from_the_given_registry() {
+        let mut result = create_test_result();
+        result.file_results[0].offenses[0].cop_name = "Rails/Unknown".to_string();
+        let registry = CopRegistry::new();
+        let formatter = create_formatter(Format::Sarif, &registry);
+        let parsed: serde_json::Value = serde_json::from_str(&formatter.format(&result)).unwrap();
+
+        let rules = parsed["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap();
+        let debugger = registry.cop("Lint/Debugger").unwrap();
+        assert_eq!(rules[0]["id"], "Lint/Debugger");
+        assert_eq!(rules[0]["shortDescription"]["text"], debugger.description());
+        // Cops the registry doesn't know are described by name
+        assert_eq!(rules[1]["id"], "Rails/Unknown");
+        assert_eq!(rules[1]["shortDescription"]["text"], "Rails/Unknown");
+        assert_eq!(rules[1]["defaultConfiguration"]["level"], "warning");
+    }
+
+    /// A result whose message holds characters XML must escape.
+    fn create_xml_test_result() -> RunResult {
+        let mut result = create_test_result();
+        result.file_results[0].offenses[1].message = "Use `a < b && c > \"d\"` or 'e'.\u{1}".to_string();
+        result.file_results.push(FileResult {
+            path: PathBuf::from("clean & tidy.rb"),
+            offenses: vec![],
+            source: None,
+        });
+        result.total_files = 2;
+        result
+    }
+
+    #[test]
+    fn test_junit_formatter() {
+        let output = JunitFormatter.format(&create_xml_test_result());
+
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(output.contains("<testsuites name=\"oxicop\" tests=\"3\" failures=\"2\">"));
+        assert!(output.contains("<testsuite name=\"test.rb\" tests=\"2\" failures=\"2\">"));
+        assert!(output.contains("<testcase classname=\"test.rb\" name=\"Lint/Debugger at 10:3\">"));
+        assert!(output.contains(
+            "<failure type=\"Lint/Debugger\" message=\"Use `a &lt; b &amp;&amp; c &gt; &quot;d&quot;` or &apos;e&apos;.\u{fffd}\">"
+        ));
+        assert!(output.contains("<testsuite name=\"clean &amp; tidy.rb\" tests=\"1\" failures=\"0\">"));
+        assert!(output.contains("<testcase classname=\"clean &amp; tidy.rb\" name=\"oxicop\"/>"));
+        assert!(output.ends_with("</testsuites>\n"));
+    }
+
+    #[test]
+    fn test_checkstyle_formatter() {
+        let output = CheckstyleFormatter.format(&create_xml_test_result());
+
+        assert!(output.contains("<checkstyle version=\"4.3\">"));
+        assert!(output.contains("<file name=\"test.rb\">"));
+        assert!(output.contains(
+            "<error line=\"5\" column=\"10\" severity=\"info\" message=\"Trailing whitespace detected.\" source=\"Layout/TrailingWhitespace\"/>"
+        ));
+        assert!(output.contains(
+            "<error line=\"10\" column=\"3\" severity=\"warning\" message=\"Use `a &lt; b &amp;&amp; c &gt; &quot;d&quot;` or &apos;e&apos;.\u{fffd}\" source=\"Lint/Debugger\"/>"
+        ));
+        assert!(output.contains("<file name=\"clean &amp; tidy.rb\">\n  </file>"));
+        assert!(output.ends_with("</checkstyle>\n"));
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape("plain"), "plain");
+        assert_eq!(xml_escape("<a href='x'>&\"</a>"), "&lt;a href=&apos;x&apos;&gt;&amp;&quot;&lt;/a&gt;");
+        assert_eq!(xml_escape("a\nb\tc\u{0}é"), "a&#10;b&#9;c\u{fffd}é");
+    }
+
+    #[test]
+    fn test_github_formatter() {
+        let mut result = create_test_result();
+        result.file_results[0].path = PathBuf::from("app/a,b:c.rb");
+        result.file_results[0].offenses[1].message = "100% sure\nreally".to_string();
+        let output = GithubFormatter.format(&result);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "::notice file=app/a%2Cb%3Ac.rb,line=5,col=10,endColumn=12,title=Layout/TrailingWhitespace::Layout/TrailingWhitespace: Trailing whitespace detected."
+        );
+        assert_eq!(
+            lines[1],
+            "::warning file=app/a%2Cb%3Ac.rb,line=10,col=3,endColumn=11,title=Lint/Debugger::Lint/Debugger: 100%25 sure%0Areally"
+        );
+        assert_eq!(github_level(Severity::Error), "error");
+    }
+
+    #[test]
+    fn test_gitlab_formatter() {
+        let mut result = create_test_result();
+        result.file_results[0].path = PathBuf::from("./app/test.rb");
+        result.file_results[0].offenses[1].fingerprint = Some("0123abcd".to_string());
+        let parsed: serde_json::Value = serde_json::from_str(&GitlabFormatter.format(&result)).unwrap();
+        let issues = parsed.as_array().unwrap();
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0]["check_name"], "Layout/TrailingWhitespace");
+        assert_eq!(issues[0]["description"], "Trailing whitespace detected.");
+        assert_eq!(issues[0]["severity"], "minor");
+        assert_eq!(issues[0]["location"]["path"], "app/test.rb");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 5);
+        assert_eq!(issues[0]["fingerprint"].as_str().unwrap().len(), 32);
+        assert_eq!(issues[1]["severity"], "major");
+        assert_eq!(issues[1]["fingerprint"], "0123abcd");
+
+        assert_eq!(gitlab_severity(Severity::Info), "info");
+        assert_eq!(gitlab_severity(Severity::Error), "critical");
+        assert_eq!(gitlab_severity(Severity::Fatal), "blocker");
+    }
+
+    #[test]
+    fn test_clang_formatter() {
+        let output = SourceFormatter::new(false, 0).format(&create_test_result());
+        let expected = "\
+test.rb:
+5:10: C: Trailing whitespace detected. (Layout/TrailingWhitespace)
+ 5 | foo = bar \x20
+   |          ^^
+10:3: W: Remove debugger statement. (Lint/Debugger)
+10 |   debugger
+   |   ^^^^^^^^
+
+1 file inspected, 2 offenses detected
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pretty_formatter_context() {
+        let mut result = create_test_result();
+        result.file_results[0].offenses[0].corrected = true;
+        let output = SourceFormatter::new(false, 2).format(&result);
+
+        // Context stops at the end of the file, and corrected offenses
+        // have no snippet
+        let expected = "\
+test.rb:
+5:10: C: [Corrected] Trailing whitespace detected. (Layout/TrailingWhitespace)
+10:3: W: Remove debugger statement. (Lint/Debugger)
+ 8 |   2
+ 9 | end
+10 |   debugger
+   |   ^^^^^^^^
+
+1 file inspected, 2 offenses detected, 1 offense corrected
+";
+        assert_eq!(output, expected);
+
+        // Without the source only the offense is shown
+        result.file_results[0].source = None;
+        let output = SourceFormatter::new(false, 2).format(&result);
+        assert!(output.contains("10:3: W: Remove debugger statement. (Lint/Debugger)\n\n"));
+    }
+
+    #[test]
+    fn test_underline() {
+        assert_eq!(underline("foo(a,b)", 6, 1), "     ^");
+        assert_eq!(underline("\tx = 1", 2, 1), "\t^");
+        // Clipped to the end of the line, but always at least one caret
+        assert_eq!(underline("abc", 2, 10), " ^^");
+        assert_eq!(underline("abc", 4, 0), "   ^");
+        assert_eq!(underline("é = 1", 3, 1), "  ^");
     }
 
     #[test]
@@ -277,6 +1217,20 @@ mod tests {
         assert!(output.contains("0 files inspected, 0 offenses detected"));
     }
 
+    #[test]
+    fn test_simple_formatter_autocorrect_status() {
+        let mut result = create_test_result();
+        let offenses = &mut result.file_results[0].offenses;
+        offenses[0].edits.push(crate::offense::Edit::remove(0, 1));
+        offenses[0].corrected = true;
+        offenses[1].edits.push(crate::offense::Edit::remove(0, 1));
+
+        let output = SimpleFormatter::new(false).format(&result);
+        assert!(output.contains("C: [Corrected] Trailing whitespace detected."));
+        assert!(output.contains("W: [Correctable] Remove debugger statement."));
+        assert!(output.contains("2 offenses detected, 1 offense corrected"));
+    }
+
     #[test]
     fn test_plural_handling() {
         let result = RunResult {
@@ -292,9 +1246,10 @@ mod tests {
 
     #[test]
     fn test_create_formatter() {
-        let simple = create_formatter(Format::Simple);
-        let compact = create_formatter(Format::Compact);
-        let json = create_formatter(Format::Json);
+        let registry = CopRegistry::new();
+        let simple = create_formatter(Format::Simple, &registry);
+        let compact = create_formatter(Format::Compact, &registry);
+        let json = create_formatter(Format::Json, &registry);
 
         let result = create_test_result();
 
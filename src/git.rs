@@ -0,0 +1,317 @@
+//! Asking the local git repository what changed, for `--since` and
+//! `--changed-lines-only`, and what is staged, for `--staged`.
+//!
+//! Changes are read from `git diff -U0` against a ref, so they cover
+//! commits since the ref as well as uncommitted edits. Untracked files
+//! count as changed throughout.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::runner::RunResult;
+
+/// Line range, inclusive at both ends, standing for a whole file.
+const WHOLE_FILE: (usize, usize) = (1, usize::MAX);
+
+/// The files changed since a ref, with the lines changed in each.
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// Absolute path to its changed line ranges, inclusive at both ends.
+    files: HashMap<PathBuf, Vec<(usize, usize)>>,
+}
+
+impl Changes {
+    /// Reads the changes since `reference` in the repository containing
+    /// `dir`.
+    pub fn since(dir: &Path, reference: &str) -> Result<Self, String> {
+        let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim_end());
+        let diff = git(
+            &root,
+            &[
+                "diff",
+                "--no-color",
+                "--no-ext-diff",
+                "--no-renames",
+                "--src-prefix=a/",
+                "--dst-prefix=b/",
+                "-U0",
+                reference,
+                "--",
+            ],
+        )?;
+        let mut files: HashMap<PathBuf, Vec<(usize, usize)>> = parse_diff(&diff)
+            .into_iter()
+            .map(|(path, ranges)| (root.join(path), ranges))
+            .collect();
+
+        let untracked = git(&root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+        for path in untracked.split('\0').filter(|path| !path.is_empty()) {
+            files.insert(root.join(path), vec![WHOLE_FILE]);
+        }
+        Ok(Self { files })
+    }
+
+    /// Whether `path` changed.
+    pub fn contains_file(&self, path: &Path) -> bool {
+        self.ranges(path).is_some()
+    }
+
+    /// Whether `line` of `path` changed.
+    pub fn contains_line(&self, path: &Path, line: usize) -> bool {
+        self.ranges(path)
+            .is_some_and(|ranges| ranges.iter().any(|&(start, end)| start <= line && line <= end))
+    }
+
+    /// Drops the offenses of `result` on unchanged lines.
+    pub fn filter(&self, result: &mut RunResult) {
+        for file_result in &mut result.file_results {
+            let path = &file_result.path;
+            file_result
+                .offenses
+                .retain(|offense| self.contains_line(path, offense.location.line));
+        }
+        result.total_offenses = result.file_results.iter().map(|r| r.offenses.len()).sum();
+    }
+
+    fn ranges(&self, path: &Path) -> Option<&Vec<(usize, usize)>> {
+        self.files.get(&canonical(path)?)
+    }
+}
+
+/// The files staged for the next commit of a repository, as recorded in
+/// its index.
+#[derive(Debug)]
+pub struct Index {
+    root: PathBuf,
+    /// Absolute paths of the files added, copied or modified; renamed
+    /// files count as added.
+    staged: HashSet<PathBuf>,
+}
+
+impl Index {
+    /// Reads the index of the repository containing `dir`.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim_end());
+        let staged = git(
+            &root,
+            &["diff", "--cached", "--name-only", "--no-renames", "--diff-filter=ACM", "-z"],
+        )?
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| root.join(path))
+        .collect();
+        Ok(Self { root, staged })
+    }
+
+    /// Whether `path` has staged changes.
+    pub fn is_staged(&self, path: &Path) -> bool {
+        canonical(path).is_some_and(|path| self.staged.contains(&path))
+    }
+
+    /// Reads the staged content of `path`.
+    pub fn read(&self, path: &Path) -> Result<String, String> {
+        let absolute = canonical(path).ok_or_else(|| format!("{}: not found", path.display()))?;
+        let relative = absolute
+            .strip_prefix(&self.root)
+            .map_err(|_| format!("{}: outside the repository", path.display()))?;
+        let spec = format!(":{}", relative.to_string_lossy());
+        let output = git_bytes(&self.root, &["cat-file", "blob", &spec])?;
+        String::from_utf8(output).map_err(|_| format!("{}: staged content is not UTF-8", path.display()))
+    }
+}
+
+/// The absolute path of `path`, with symlinks resolved when it exists.
+fn canonical(path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).or_else(|_| std::path::absolute(path)).ok()
+}
+
+/// Runs git in `dir` and returns its standard output.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    git_bytes(dir, args).map(|output| String::from_utf8_lossy(&output).into_owned())
+}
+
+fn git_bytes(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-c")
+        .arg("core.quotePath=false")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed: {}", args[0], stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Reads the added or modified line ranges of each file from a unified
+/// diff, keyed by path relative to the repository root.
+fn parse_diff(diff: &str) -> HashMap<String, Vec<(usize, usize)>> {
+    let mut files: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+    let mut current: Option<String> = None;
+    // Between a `diff --git` line and the file's first hunk, where `+++`
+    // names the file rather than adding a line starting with `++`
+    let mut in_header = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            current = None;
+            in_header = true;
+        } else if let Some(path) = line.strip_prefix("+++ ").filter(|_| in_header) {
+            // Deleted files have `/dev/null` as their new name
+            current = unquote(path).strip_prefix("b/").map(str::to_string);
+            if let Some(path) = &current {
+                files.entry(path.clone()).or_default();
+            }
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            in_header = false;
+            if let (Some(path), Some(range)) = (&current, parse_hunk_header(hunk)) {
+                files.get_mut(path).unwrap().push(range);
+            }
+        }
+    }
+    files
+}
+
+/// Reads the new-file line range from the rest of a hunk header such as
+/// `-3,2 +4,5 @@`. Hunks that only delete lines have none.
+fn parse_hunk_header(hunk: &str) -> Option<(usize, usize)> {
+    let new = hunk.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new.parse().ok()?, 1),
+    };
+    (count > 0).then(|| (start, start + count - 1))
+}
+
+/// Undoes git's C-style quoting of paths with unusual characters.
+fn unquote(path: &str) -> String {
+    let Some(quoted) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut out = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff() {
+        let diff = "\
+diff --git a/app/a.rb b/app/a.rb
+index 1111111..2222222 100644
+--- a/app/a.rb
++++ b/app/a.rb
+@@ -3,0 +4,2 @@ def foo
++  x = 1
++  y = 2
+@@ -10 +12 @@ end
+-old
++new
+@@ -20,3 +21,0 @@ end
+-gone
+diff --git a/old.rb b/old.rb
+deleted file mode 100644
+--- a/old.rb
++++ /dev/null
+@@ -1,2 +0,0 @@
+-a
+-b
+diff --git a/new.rb b/new.rb
+new file mode 100644
+--- /dev/null
++++ \"b/with \\\"quote\\\".rb\"
+@@ -0,0 +1,3 @@
++a
+";
+        let files = parse_diff(diff);
+        assert_eq!(files["app/a.rb"], vec![(4, 5), (12, 12)]);
+        assert_eq!(files["with \"quote\".rb"], vec![(1, 3)]);
+        assert!(!files.contains_key("old.rb"));
+    }
+
+    #[test]
+    fn test_parse_diff_added_line_looking_like_a_header() {
+        // Adding `++ b/other.rb` and `-- a/other.rb` lines
+        let diff = "\
+diff --git a/a.rb b/a.rb
+index 1111111..2222222 100644
+--- a/a.rb
++++ b/a.rb
+@@ -1,0 +2,2 @@ x
++-- a/other.rb
++++ b/other.rb
+@@ -8 +10 @@ y
++z
+";
+        let files = parse_diff(diff);
+        assert_eq!(files["a.rb"], vec![(2, 3), (10, 10)]);
+        assert!(!files.contains_key("other.rb"));
+    }
+
+    #[test]
+    fn test_changes_since_ref() {
+        let root = std::env::temp_dir().join("oxicop_git_changes");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let root = fs::canonicalize(&root).unwrap();
+        let run = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&root)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        run(&["init", "-q"]);
+        fs::write(root.join("a.rb"), "a = 1\nb = 2\nc = 3\n").unwrap();
+        fs::write(root.join("b.rb"), "x = 1\n").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "initial"]);
+        fs::write(root.join("a.rb"), "a = 1\nb = 20\nc = 3\nd = 4\n").unwrap();
+        fs::write(root.join("c.rb"), "new = 1\n").unwrap();
+
+        let changes = Changes::since(&root, "HEAD").unwrap();
+        assert!(changes.contains_file(&root.join("a.rb")));
+        assert!(!changes.contains_file(&root.join("b.rb")));
+        assert!(changes.contains_line(&root.join("c.rb"), 1));
+        assert!(!changes.contains_line(&root.join("a.rb"), 1));
+        assert!(changes.contains_line(&root.join("a.rb"), 2));
+        assert!(!changes.contains_line(&root.join("a.rb"), 3));
+        assert!(changes.contains_line(&root.join("a.rb"), 4));
+
+        let err = Changes::since(&root, "no-such-ref").unwrap_err();
+        assert!(err.starts_with("git diff failed: "), "{}", err);
+
+        // Only a.rb is staged, and its staged content differs from the
+        // working tree.
+        run(&["add", "a.rb"]);
+        fs::write(root.join("a.rb"), "unstaged = 1\n").unwrap();
+        let index = Index::open(&root).unwrap();
+        assert!(index.is_staged(&root.join("a.rb")));
+        assert!(!index.is_staged(&root.join("b.rb")));
+        assert!(!index.is_staged(&root.join("c.rb")));
+        assert_eq!(index.read(&root.join("a.rb")).unwrap(), "a = 1\nb = 20\nc = 3\nd = 4\n");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
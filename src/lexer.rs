@@ -0,0 +1,1090 @@
+//! Lossless Ruby tokenizer.
+//!
+//! Produces a flat token stream covering every byte of the input, so
+//! concatenating the text of all tokens reproduces the original source.
+//! The lexer understands heredocs, percent literals, regexp literals,
+//! `#{}` interpolation and `=begin`/`=end` blocks, which lets cops ask
+//! precise questions about what sits at a given position.
+
+/// Kinds of tokens produced by the lexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Spaces, tabs and escaped line continuations.
+    Whitespace,
+    /// A single `\n` (or `\r\n`) line terminator.
+    Newline,
+    /// A `#` comment, excluding the trailing newline.
+    Comment,
+    /// A `=begin` ... `=end` block.
+    EmbeddedDoc,
+    /// `__END__` and everything after it.
+    DataSection,
+    /// Reserved word, e.g. `def`, `if`, `end`.
+    Keyword,
+    /// Local variable or method name, including `?`/`!` suffixes.
+    Identifier,
+    /// Constant name, e.g. `Foo`.
+    Constant,
+    /// Hash key or keyword argument label, e.g. `key:`.
+    Label,
+    InstanceVariable,
+    ClassVariable,
+    GlobalVariable,
+    Integer,
+    Float,
+    /// Character literal, e.g. `?a`.
+    Character,
+    /// Simple symbol, e.g. `:foo` or `:+`.
+    Symbol,
+    /// Opening delimiter of a string: `"`, `'`, `%q(`, `%Q[`, `%(`.
+    StringBegin,
+    /// Opening delimiter of a quoted symbol: `:"`, `:'`, `%s(`.
+    SymbolBegin,
+    /// Opening delimiter of a command string: `` ` `` or `%x(`.
+    XStringBegin,
+    /// Opening delimiter of a word array: `%w[` or `%W[`.
+    WordsBegin,
+    /// Opening delimiter of a symbol array: `%i[` or `%I[`.
+    SymbolsBegin,
+    /// Opening delimiter of a regexp: `/` or `%r{`.
+    RegexpBegin,
+    /// Heredoc marker, e.g. `<<~SQL`.
+    HeredocBegin,
+    /// Literal text inside a string, symbol, regexp or heredoc body.
+    StringContent,
+    /// Closing delimiter of a string, symbol, word array or command string.
+    StringEnd,
+    /// Closing delimiter of a regexp, including any flags.
+    RegexpEnd,
+    /// Heredoc terminator line, including leading indentation.
+    HeredocEnd,
+    /// `#{` inside an interpolating literal.
+    InterpolationBegin,
+    /// `}` closing an interpolation.
+    InterpolationEnd,
+    /// Operators, including `.`, `::`, `=>` and `->`.
+    Operator,
+    /// Grouping and separators: `(`, `)`, `[`, `]`, `{`, `}`, `,`, `;`.
+    Punctuation,
+    /// Bytes the lexer could not classify.
+    Unknown,
+}
+
+impl TokenKind {
+    /// Whether the token opens a string-like literal.
+    pub fn is_literal_begin(self) -> bool {
+        matches!(
+            self,
+            TokenKind::StringBegin
+                | TokenKind::SymbolBegin
+                | TokenKind::XStringBegin
+                | TokenKind::WordsBegin
+                | TokenKind::SymbolsBegin
+                | TokenKind::RegexpBegin
+                | TokenKind::HeredocBegin
+        )
+    }
+
+    /// Whether the token is part of a string-like literal, excluding
+    /// interpolated code.
+    pub fn is_literal(self) -> bool {
+        self.is_literal_begin()
+            || matches!(
+                self,
+                TokenKind::StringContent
+                    | TokenKind::StringEnd
+                    | TokenKind::RegexpEnd
+                    | TokenKind::HeredocEnd
+                    | TokenKind::Character
+            )
+    }
+
+    /// Whether the token is a comment of any form.
+    pub fn is_comment(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Comment | TokenKind::EmbeddedDoc | TokenKind::DataSection
+        )
+    }
+
+    /// Whether the token carries no syntactic meaning.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, TokenKind::Whitespace) || self.is_comment()
+    }
+}
+
+/// A single token with its byte range and starting position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte offset of the first byte of the token.
+    pub start: usize,
+    /// Byte offset one past the last byte of the token.
+    pub end: usize,
+    /// 1-based line number of the first byte.
+    pub line: usize,
+    /// 1-based character column of the first byte.
+    pub column: usize,
+}
+
+impl Token {
+    /// Returns the source text covered by this token.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    /// Length of the token in bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the token covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "__ENCODING__", "__FILE__", "__LINE__", "BEGIN", "END", "alias", "and", "begin", "break",
+    "case", "class", "def", "defined?", "do", "else", "elsif", "end", "ensure", "false", "for",
+    "if", "in", "module", "next", "nil", "not", "or", "redo", "rescue", "retry", "return",
+    "self", "super", "then", "true", "undef", "unless", "until", "when", "while", "yield",
+];
+
+/// Keywords after which an operand (rather than an operator) is not expected.
+const VALUE_KEYWORDS: &[&str] = &[
+    "__ENCODING__", "__FILE__", "__LINE__", "end", "false", "nil", "redo", "retry", "self",
+    "true",
+];
+
+const OPERATORS: &[&str] = &[
+    "**=", "<=>", "===", "...", "<<=", ">>=", "&&=", "||=", "&.", "**", "==", "!=", ">=", "<=",
+    "&&", "||", "<<", ">>", "=~", "!~", "..", "::", "->", "=>", "+=", "-=", "*=", "/=", "%=",
+    "|=", "&=", "^=", "+", "-", "*", "/", "%", "=", "<", ">", "!", "&", "|", "^", "~", "?", ":",
+    ".",
+];
+
+const SYMBOL_OPERATORS: &[&str] = &[
+    "[]=", "[]", "<=>", "===", "==", "=~", "!=", "!~", "**", "+@", "-@", "<<", ">>", "<=", ">=",
+    "+", "-", "*", "/", "%", "<", ">", "!", "&", "|", "^", "~",
+];
+
+/// Tokenizes Ruby source into a lossless token stream.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(source);
+    lexer.lex_code(false);
+    let mut tokens = lexer.tokens;
+    assign_positions(source, &mut tokens);
+    tokens
+}
+
+/// Fills in line and column for each token by walking the source once.
+fn assign_positions(source: &str, tokens: &mut [Token]) {
+    let mut line = 1;
+    let mut column = 1;
+    let mut pos = 0;
+    for token in tokens.iter_mut() {
+        for ch in source[pos..token.start].chars() {
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        token.line = line;
+        token.column = column;
+        pos = token.start;
+    }
+}
+
+struct PendingHeredoc {
+    terminator: String,
+    indented: bool,
+    interpolate: bool,
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<Token>,
+    pending_heredocs: Vec<PendingHeredoc>,
+    last_significant: Option<usize>,
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+fn is_ident_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+fn closing_delimiter(open: u8) -> u8 {
+    match open {
+        b'(' => b')',
+        b'[' => b']',
+        b'{' => b'}',
+        b'<' => b'>',
+        other => other,
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            bytes: src.as_bytes(),
+            pos: 0,
+            tokens: Vec::new(),
+            pending_heredocs: Vec::new(),
+            last_significant: None,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    fn char_len_at(&self, pos: usize) -> usize {
+        self.src[pos..].chars().next().map_or(1, char::len_utf8)
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        if !kind.is_trivia() {
+            self.last_significant = Some(self.tokens.len());
+        }
+        self.tokens.push(Token {
+            kind,
+            start,
+            end,
+            line: 0,
+            column: 0,
+        });
+    }
+
+    fn last_token(&self) -> Option<(TokenKind, &'a str)> {
+        self.last_significant
+            .map(|i| self.tokens[i])
+            .map(|t| (t.kind, t.text(self.src)))
+    }
+
+    fn preceded_by_space(&self) -> bool {
+        matches!(self.tokens.last(), Some(t) if t.kind == TokenKind::Whitespace)
+    }
+
+    /// Whether the lexer is at the start of an expression, where an operand
+    /// rather than a binary operator is expected.
+    fn expects_value(&self) -> bool {
+        match self.last_token() {
+            None => true,
+            Some((kind, text)) => match kind {
+                TokenKind::Newline
+                | TokenKind::Operator
+                | TokenKind::Label
+                | TokenKind::InterpolationBegin => true,
+                TokenKind::Keyword => !VALUE_KEYWORDS.contains(&text),
+                TokenKind::Punctuation => matches!(text, "(" | "[" | "{" | "," | ";"),
+                _ => false,
+            },
+        }
+    }
+
+    /// Whether the lexer is at the first argument of a parenthesis-less
+    /// method call, e.g. `puts /re/` or `foo <<~EOS`.
+    fn at_command_argument(&self) -> bool {
+        matches!(self.last_token(), Some((TokenKind::Identifier, _)))
+            && self.preceded_by_space()
+            && !matches!(self.peek(1), Some(b' ' | b'\t' | b'\n' | b'=') | None)
+    }
+
+    /// Whether the next token names a method, as after `def` or `.`.
+    fn expects_method_name(&self) -> bool {
+        matches!(
+            self.last_token(),
+            Some((TokenKind::Keyword, "def")) | Some((TokenKind::Operator, "." | "&."))
+        )
+    }
+
+    fn literal_allowed(&self) -> bool {
+        !self.expects_method_name() && (self.expects_value() || self.at_command_argument())
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.pos == 0 || self.bytes[self.pos - 1] == b'\n'
+    }
+
+    /// Lexes code until the end of input, or until an unmatched `}` when
+    /// lexing the body of an interpolation.
+    fn lex_code(&mut self, in_interpolation: bool) {
+        let mut brace_depth = 0usize;
+        while self.pos < self.bytes.len() {
+            if self.at_line_start() && self.lex_line_start_construct() {
+                continue;
+            }
+            let start = self.pos;
+            let b = self.bytes[self.pos];
+            match b {
+                b'\n' => {
+                    self.pos += 1;
+                    self.push(TokenKind::Newline, start, self.pos);
+                    if !self.pending_heredocs.is_empty() {
+                        self.lex_heredoc_bodies();
+                    }
+                }
+                b'\r' if self.peek(1) == Some(b'\n') => {
+                    self.pos += 2;
+                    self.push(TokenKind::Newline, start, self.pos);
+                    if !self.pending_heredocs.is_empty() {
+                        self.lex_heredoc_bodies();
+                    }
+                }
+                b' ' | b'\t' | b'\r' | 0x0c | 0x0b => self.lex_whitespace(),
+                b'\\' if matches!(self.peek(1), Some(b'\n')) => self.lex_whitespace(),
+                b'\\' if self.peek(1) == Some(b'\r') && self.peek(2) == Some(b'\n') => {
+                    self.lex_whitespace()
+                }
+                b'#' => {
+                    while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                    if self.pos > start && self.bytes[self.pos - 1] == b'\r' {
+                        self.pos -= 1;
+                    }
+                    self.push(TokenKind::Comment, start, self.pos);
+                }
+                b'{' => {
+                    brace_depth += 1;
+                    self.pos += 1;
+                    self.push(TokenKind::Punctuation, start, self.pos);
+                }
+                b'}' => {
+                    if in_interpolation && brace_depth == 0 {
+                        return;
+                    }
+                    brace_depth = brace_depth.saturating_sub(1);
+                    self.pos += 1;
+                    self.push(TokenKind::Punctuation, start, self.pos);
+                }
+                b'(' | b')' | b'[' | b']' | b',' | b';' => {
+                    self.pos += 1;
+                    self.push(TokenKind::Punctuation, start, self.pos);
+                }
+                b'"' => {
+                    self.pos += 1;
+                    self.push(TokenKind::StringBegin, start, self.pos);
+                    self.lex_literal_body(b'"', true, TokenKind::StringEnd);
+                }
+                b'\'' => {
+                    self.pos += 1;
+                    self.push(TokenKind::StringBegin, start, self.pos);
+                    self.lex_literal_body(b'\'', false, TokenKind::StringEnd);
+                }
+                b'`' if !self.expects_method_name() => {
+                    self.pos += 1;
+                    self.push(TokenKind::XStringBegin, start, self.pos);
+                    self.lex_literal_body(b'`', true, TokenKind::StringEnd);
+                }
+                b'/' if self.literal_allowed() => {
+                    self.pos += 1;
+                    self.push(TokenKind::RegexpBegin, start, self.pos);
+                    self.lex_literal_body(b'/', true, TokenKind::RegexpEnd);
+                }
+                b'%' if self.literal_allowed() && self.lex_percent_literal() => {}
+                b'<' if self.peek(1) == Some(b'<')
+                    && self.literal_allowed()
+                    && !matches!(self.last_token(), Some((TokenKind::Keyword, "class")))
+                    && self.lex_heredoc_marker() => {}
+                b'?' if self.literal_allowed() && self.lex_character_literal() => {}
+                b':' if self.peek(1) != Some(b':') && self.lex_symbol() => {}
+                b'0'..=b'9' => self.lex_number(),
+                b'@' => self.lex_variable(),
+                b'$' => self.lex_global_variable(),
+                _ if is_ident_start(b) => self.lex_identifier(),
+                _ => self.lex_operator(),
+            }
+        }
+    }
+
+    /// Handles `=begin` blocks and `__END__`, which are only recognized at
+    /// the start of a line. Returns whether a token was produced.
+    fn lex_line_start_construct(&mut self) -> bool {
+        let rest = &self.src[self.pos..];
+        let start = self.pos;
+        if rest.starts_with("=begin")
+            && rest[6..].chars().next().is_none_or(|c| c.is_whitespace())
+        {
+            let mut line_start = self.pos;
+            loop {
+                let eol = self.src[line_start..]
+                    .find('\n')
+                    .map_or(self.bytes.len(), |i| line_start + i);
+                let line = &self.src[line_start..eol];
+                let is_end = line_start != start
+                    && line.starts_with("=end")
+                    && line[4..].chars().next().is_none_or(|c| c.is_whitespace());
+                if is_end || eol == self.bytes.len() {
+                    self.pos = eol;
+                    if self.pos > start && self.bytes[self.pos - 1] == b'\r' {
+                        self.pos -= 1;
+                    }
+                    break;
+                }
+                line_start = eol + 1;
+            }
+            self.push(TokenKind::EmbeddedDoc, start, self.pos);
+            return true;
+        }
+        let data_marker = rest
+            .strip_prefix("__END__")
+            .is_some_and(|after| {
+                after.is_empty() || after.starts_with('\n') || after.starts_with("\r\n")
+            });
+        if data_marker {
+            self.pos = self.bytes.len();
+            self.push(TokenKind::DataSection, start, self.pos);
+            return true;
+        }
+        false
+    }
+
+    fn lex_whitespace(&mut self) {
+        let start = self.pos;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b' ' | b'\t' | 0x0c | 0x0b => self.pos += 1,
+                b'\r' if self.peek(1) != Some(b'\n') => self.pos += 1,
+                b'\\' if self.peek(1) == Some(b'\n') => self.pos += 2,
+                b'\\' if self.peek(1) == Some(b'\r') && self.peek(2) == Some(b'\n') => {
+                    self.pos += 3
+                }
+                _ => break,
+            }
+        }
+        self.push(TokenKind::Whitespace, start, self.pos);
+    }
+
+    /// Lexes the body of a delimited literal up to and including the closing
+    /// delimiter. The opening delimiter must already have been consumed.
+    fn lex_literal_body(&mut self, close: u8, interpolate: bool, end_kind: TokenKind) {
+        let open = match close {
+            b')' => Some(b'('),
+            b']' => Some(b'['),
+            b'}' => Some(b'{'),
+            b'>' => Some(b'<'),
+            _ => None,
+        };
+        let mut nesting = 0usize;
+        let mut content_start = self.pos;
+        while self.pos < self.bytes.len() {
+            let b = self.bytes[self.pos];
+            if b == b'\\' {
+                self.pos += 1;
+                if self.pos < self.bytes.len() {
+                    self.pos += self.char_len_at(self.pos);
+                }
+                continue;
+            }
+            if interpolate && b == b'#' && self.peek(1) == Some(b'{') {
+                self.push(TokenKind::StringContent, content_start, self.pos);
+                self.lex_interpolation();
+                content_start = self.pos;
+                continue;
+            }
+            if Some(b) == open {
+                nesting += 1;
+            } else if b == close {
+                if nesting == 0 {
+                    self.push(TokenKind::StringContent, content_start, self.pos);
+                    let end_start = self.pos;
+                    self.pos += 1;
+                    if end_kind == TokenKind::RegexpEnd {
+                        while matches!(
+                            self.peek(0),
+                            Some(b'i' | b'm' | b'x' | b'o' | b'u' | b'n' | b'e' | b's')
+                        ) {
+                            self.pos += 1;
+                        }
+                    }
+                    self.push(end_kind, end_start, self.pos);
+                    return;
+                }
+                nesting -= 1;
+            }
+            self.pos += 1;
+        }
+        // Unterminated literal: everything left is content.
+        self.push(TokenKind::StringContent, content_start, self.pos);
+    }
+
+    /// Lexes `#{ ... }`, with the lexer positioned on the `#`.
+    fn lex_interpolation(&mut self) {
+        let start = self.pos;
+        self.pos += 2;
+        self.push(TokenKind::InterpolationBegin, start, self.pos);
+        self.lex_code(true);
+        if self.peek(0) == Some(b'}') {
+            let end_start = self.pos;
+            self.pos += 1;
+            self.push(TokenKind::InterpolationEnd, end_start, self.pos);
+        }
+    }
+
+    /// Lexes a `%`-literal such as `%w[a b]` or `%r{re}i`. Returns false if
+    /// the `%` does not start a literal.
+    fn lex_percent_literal(&mut self) -> bool {
+        let start = self.pos;
+        let (type_char, delim_offset) = match self.peek(1) {
+            Some(c @ (b'q' | b'Q' | b'w' | b'W' | b'i' | b'I' | b'r' | b's' | b'x')) => {
+                (Some(c), 2)
+            }
+            _ => (None, 1),
+        };
+        let open = match self.peek(delim_offset) {
+            Some(d) if d.is_ascii_punctuation() => d,
+            _ => return false,
+        };
+        // `x % y` is modulo; a bare `%` followed by `=` or space is an operator.
+        if type_char.is_none() && open == b'=' {
+            return false;
+        }
+        let (begin_kind, end_kind, interpolate) = match type_char {
+            Some(b'q') => (TokenKind::StringBegin, TokenKind::StringEnd, false),
+            Some(b'Q') | None => (TokenKind::StringBegin, TokenKind::StringEnd, true),
+            Some(b'w') => (TokenKind::WordsBegin, TokenKind::StringEnd, false),
+            Some(b'W') => (TokenKind::WordsBegin, TokenKind::StringEnd, true),
+            Some(b'i') => (TokenKind::SymbolsBegin, TokenKind::StringEnd, false),
+            Some(b'I') => (TokenKind::SymbolsBegin, TokenKind::StringEnd, true),
+            Some(b'r') => (TokenKind::RegexpBegin, TokenKind::RegexpEnd, true),
+            Some(b's') => (TokenKind::SymbolBegin, TokenKind::StringEnd, false),
+            _ => (TokenKind::XStringBegin, TokenKind::StringEnd, true),
+        };
+        self.pos += delim_offset + 1;
+        self.push(begin_kind, start, self.pos);
+        self.lex_literal_body(closing_delimiter(open), interpolate, end_kind);
+        true
+    }
+
+    /// Lexes a heredoc marker like `<<~EOS` and queues its body. Returns
+    /// false if the `<<` is an operator.
+    fn lex_heredoc_marker(&mut self) -> bool {
+        let start = self.pos;
+        let mut p = self.pos + 2;
+        let indented = matches!(self.bytes.get(p), Some(b'~' | b'-'));
+        if indented {
+            p += 1;
+        }
+        let (terminator, interpolate) = match self.bytes.get(p) {
+            Some(&q @ (b'\'' | b'"' | b'`')) => {
+                let Some(len) = self.src[p + 1..].find([q as char, '\n']) else {
+                    return false;
+                };
+                if self.bytes[p + 1 + len] != q {
+                    return false;
+                }
+                let id = self.src[p + 1..p + 1 + len].to_string();
+                p += len + 2;
+                (id, q != b'\'')
+            }
+            Some(&c) if is_ident_start(c) => {
+                let id_start = p;
+                while p < self.bytes.len() && is_ident_char(self.bytes[p]) {
+                    p += 1;
+                }
+                (self.src[id_start..p].to_string(), true)
+            }
+            _ => return false,
+        };
+        self.pos = p;
+        self.push(TokenKind::HeredocBegin, start, self.pos);
+        self.pending_heredocs.push(PendingHeredoc {
+            terminator,
+            indented,
+            interpolate,
+        });
+        true
+    }
+
+    /// Lexes the bodies of all heredocs started on the previous line.
+    fn lex_heredoc_bodies(&mut self) {
+        for heredoc in std::mem::take(&mut self.pending_heredocs) {
+            self.lex_heredoc_body(&heredoc);
+        }
+    }
+
+    fn lex_heredoc_body(&mut self, heredoc: &PendingHeredoc) {
+        let mut content_start = self.pos;
+        while self.pos < self.bytes.len() {
+            let mut eol = self.line_end(self.pos);
+            let line = self.src[self.pos..eol].trim_end_matches('\r');
+            let candidate = if heredoc.indented {
+                line.trim_start()
+            } else {
+                line
+            };
+            if candidate == heredoc.terminator {
+                self.push(TokenKind::StringContent, content_start, self.pos);
+                let end_start = self.pos;
+                self.pos = end_start + line.len();
+                self.push(TokenKind::HeredocEnd, end_start, self.pos);
+                return;
+            }
+            while self.pos < eol {
+                let b = self.bytes[self.pos];
+                if heredoc.interpolate && b == b'\\' && self.pos + 1 < eol {
+                    self.pos += 1 + self.char_len_at(self.pos + 1);
+                } else if heredoc.interpolate && b == b'#' && self.peek(1) == Some(b'{') {
+                    self.push(TokenKind::StringContent, content_start, self.pos);
+                    self.lex_interpolation();
+                    content_start = self.pos;
+                    eol = self.line_end(self.pos);
+                } else {
+                    self.pos += 1;
+                }
+            }
+            if self.pos < self.bytes.len() {
+                self.pos += 1;
+            }
+        }
+        self.push(TokenKind::StringContent, content_start, self.pos);
+    }
+
+    fn line_end(&self, from: usize) -> usize {
+        self.src[from..]
+            .find('\n')
+            .map_or(self.bytes.len(), |i| from + i)
+    }
+
+    /// Lexes a character literal like `?a` or `?\n`. Returns false if the
+    /// `?` is the ternary operator.
+    fn lex_character_literal(&mut self) -> bool {
+        let start = self.pos;
+        let Some(next) = self.peek(1) else {
+            return false;
+        };
+        if next.is_ascii_whitespace() {
+            return false;
+        }
+        let mut end = self.pos + 1;
+        if next == b'\\' {
+            end += 1;
+            if end < self.bytes.len() {
+                end += self.char_len_at(end);
+            }
+        } else {
+            end += self.char_len_at(end);
+            if self.bytes.get(end).is_some_and(|&b| is_ident_char(b)) && is_ident_char(next) {
+                return false;
+            }
+        }
+        self.pos = end.min(self.bytes.len());
+        self.push(TokenKind::Character, start, self.pos);
+        true
+    }
+
+    /// Lexes a symbol such as `:foo`, `:[]=` or `:"quoted"`. Returns false if
+    /// the `:` is an operator.
+    fn lex_symbol(&mut self) -> bool {
+        let start = self.pos;
+        match self.peek(1) {
+            Some(q @ (b'"' | b'\'')) => {
+                self.pos += 2;
+                self.push(TokenKind::SymbolBegin, start, self.pos);
+                self.lex_literal_body(q, q == b'"', TokenKind::StringEnd);
+                true
+            }
+            Some(c) if is_ident_start(c) => {
+                self.pos += 1;
+                self.consume_identifier();
+                if self.peek(0) == Some(b'=')
+                    && !matches!(self.peek(1), Some(b'=' | b'~' | b'>'))
+                {
+                    self.pos += 1;
+                }
+                self.push(TokenKind::Symbol, start, self.pos);
+                true
+            }
+            Some(b'@') | Some(b'$') => {
+                self.pos += 1;
+                let var_start = self.pos;
+                if self.peek(0) == Some(b'$') {
+                    self.lex_global_variable();
+                } else {
+                    self.lex_variable();
+                }
+                // Re-label the variable token as part of the symbol.
+                if self.tokens.last().is_some_and(|t| t.start == var_start) {
+                    self.tokens.pop();
+                }
+                self.push(TokenKind::Symbol, start, self.pos);
+                true
+            }
+            _ => {
+                let rest = &self.src[self.pos + 1..];
+                match SYMBOL_OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                    Some(op) => {
+                        self.pos += 1 + op.len();
+                        self.push(TokenKind::Symbol, start, self.pos);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Consumes identifier characters plus an optional `?` or `!` suffix.
+    fn consume_identifier(&mut self) {
+        while self.pos < self.bytes.len() && is_ident_char(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+        if matches!(self.peek(0), Some(b'?' | b'!'))
+            && (self.peek(1) != Some(b'=') || matches!(self.peek(2), Some(b'=' | b'~')))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn lex_identifier(&mut self) {
+        let start = self.pos;
+        let method_name = self.expects_method_name()
+            || matches!(self.last_token(), Some((TokenKind::Operator, "::")));
+        let ternary = matches!(self.last_token(), Some((TokenKind::Operator, "?")));
+        self.consume_identifier();
+        let word = &self.src[start..self.pos];
+
+        if self.peek(0) == Some(b':') && self.peek(1) != Some(b':') && !ternary {
+            self.pos += 1;
+            self.push(TokenKind::Label, start, self.pos);
+            return;
+        }
+
+        let kind = if !method_name && KEYWORDS.contains(&word) {
+            TokenKind::Keyword
+        } else if word.as_bytes()[0].is_ascii_uppercase() {
+            TokenKind::Constant
+        } else {
+            TokenKind::Identifier
+        };
+        self.push(kind, start, self.pos);
+    }
+
+    fn lex_number(&mut self) {
+        let start = self.pos;
+        let mut kind = TokenKind::Integer;
+        if self.peek(0) == Some(b'0')
+            && matches!(
+                self.peek(1),
+                Some(b'x' | b'X' | b'b' | b'B' | b'o' | b'O' | b'd' | b'D')
+            )
+        {
+            self.pos += 2;
+            while self
+                .peek(0)
+                .is_some_and(|b| b.is_ascii_hexdigit() || b == b'_')
+            {
+                self.pos += 1;
+            }
+        } else {
+            self.consume_digits();
+            if self.peek(0) == Some(b'.') && self.peek(1).is_some_and(|b| b.is_ascii_digit()) {
+                kind = TokenKind::Float;
+                self.pos += 1;
+                self.consume_digits();
+            }
+            if matches!(self.peek(0), Some(b'e' | b'E')) {
+                let digits_at = if matches!(self.peek(1), Some(b'+' | b'-')) {
+                    2
+                } else {
+                    1
+                };
+                if self.peek(digits_at).is_some_and(|b| b.is_ascii_digit()) {
+                    kind = TokenKind::Float;
+                    self.pos += digits_at;
+                    self.consume_digits();
+                }
+            }
+        }
+        if self.peek(0) == Some(b'r') && !self.peek(1).is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        if self.peek(0) == Some(b'i') && !self.peek(1).is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        self.push(kind, start, self.pos);
+    }
+
+    fn consume_digits(&mut self) {
+        while self
+            .peek(0)
+            .is_some_and(|b| b.is_ascii_digit() || b == b'_')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn lex_variable(&mut self) {
+        let start = self.pos;
+        let kind = if self.peek(1) == Some(b'@') {
+            self.pos += 2;
+            TokenKind::ClassVariable
+        } else {
+            self.pos += 1;
+            TokenKind::InstanceVariable
+        };
+        if !self.peek(0).is_some_and(is_ident_start) {
+            self.pos = start + 1;
+            self.push(TokenKind::Unknown, start, self.pos);
+            return;
+        }
+        while self.peek(0).is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        self.push(kind, start, self.pos);
+    }
+
+    fn lex_global_variable(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        match self.peek(0) {
+            Some(b) if is_ident_start(b) => {
+                while self.peek(0).is_some_and(is_ident_char) {
+                    self.pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_digit() => self.consume_digits(),
+            Some(b'-') if self.peek(1).is_some_and(is_ident_char) => {
+                self.pos += 1 + self.char_len_at(self.pos + 1)
+            }
+            Some(
+                b'!' | b'@' | b'&' | b'`' | b'\'' | b'+' | b'~' | b'=' | b'/' | b'\\' | b',' | b';'
+                | b'.' | b'<' | b'>' | b'_' | b'*' | b'$' | b'?' | b':' | b'"',
+            ) => self.pos += 1,
+            _ => {}
+        }
+        let kind = if self.pos == start + 1 {
+            TokenKind::Unknown
+        } else {
+            TokenKind::GlobalVariable
+        };
+        self.push(kind, start, self.pos);
+    }
+
+    fn lex_operator(&mut self) {
+        let start = self.pos;
+        let rest = &self.src[self.pos..];
+        match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            Some(op) => {
+                self.pos += op.len();
+                self.push(TokenKind::Operator, start, self.pos);
+            }
+            None => {
+                self.pos += self.char_len_at(self.pos);
+                self.push(TokenKind::Unknown, start, self.pos);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(source)
+            .into_iter()
+            .filter(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Newline))
+            .map(|t| (t.kind, t.text(source)))
+            .collect()
+    }
+
+    fn assert_lossless(source: &str) {
+        let tokens = tokenize(source);
+        let rebuilt: String = tokens.iter().map(|t| t.text(source)).collect();
+        assert_eq!(rebuilt, source);
+        for pair in tokens.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        assert_lossless("def foo(a, b = 1)\n  a + b # sum\nend\n");
+        assert_lossless("x = <<~SQL\n  SELECT #{cols}\n  FROM t\nSQL\ny = %w[a b] + [/re#{1}/i]\n");
+        assert_lossless("=begin\ndoc\n=end\nputs 'é' \\\n  \"ü\"\n__END__\ndata");
+        assert_lossless("\"unterminated #{");
+    }
+
+    #[test]
+    fn test_keywords_identifiers_and_labels() {
+        assert_eq!(
+            kinds("def end?; foo.class; bar(if: true) end"),
+            vec![
+                (TokenKind::Keyword, "def"),
+                (TokenKind::Identifier, "end?"),
+                (TokenKind::Punctuation, ";"),
+                (TokenKind::Identifier, "foo"),
+                (TokenKind::Operator, "."),
+                (TokenKind::Identifier, "class"),
+                (TokenKind::Punctuation, ";"),
+                (TokenKind::Identifier, "bar"),
+                (TokenKind::Punctuation, "("),
+                (TokenKind::Label, "if:"),
+                (TokenKind::Keyword, "true"),
+                (TokenKind::Punctuation, ")"),
+                (TokenKind::Keyword, "end"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        assert_eq!(
+            kinds("\"a #{b} c\""),
+            vec![
+                (TokenKind::StringBegin, "\""),
+                (TokenKind::StringContent, "a "),
+                (TokenKind::InterpolationBegin, "#{"),
+                (TokenKind::Identifier, "b"),
+                (TokenKind::InterpolationEnd, "}"),
+                (TokenKind::StringContent, " c"),
+                (TokenKind::StringEnd, "\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heredoc() {
+        let source = "foo(<<~EOS, 1)\n  # not a comment\nEOS\nbar\n";
+        assert_eq!(
+            kinds(source),
+            vec![
+                (TokenKind::Identifier, "foo"),
+                (TokenKind::Punctuation, "("),
+                (TokenKind::HeredocBegin, "<<~EOS"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Integer, "1"),
+                (TokenKind::Punctuation, ")"),
+                (TokenKind::StringContent, "  # not a comment\n"),
+                (TokenKind::HeredocEnd, "EOS"),
+                (TokenKind::Identifier, "bar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regexp_versus_division() {
+        assert_eq!(
+            kinds("a / b"),
+            vec![
+                (TokenKind::Identifier, "a"),
+                (TokenKind::Operator, "/"),
+                (TokenKind::Identifier, "b"),
+            ]
+        );
+        assert_eq!(
+            kinds("x =~ /a b/i"),
+            vec![
+                (TokenKind::Identifier, "x"),
+                (TokenKind::Operator, "=~"),
+                (TokenKind::RegexpBegin, "/"),
+                (TokenKind::StringContent, "a b"),
+                (TokenKind::RegexpEnd, "/i"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percent_literals() {
+        assert_eq!(
+            kinds("[%w[a (b)], %q(x), 10 % 3]"),
+            vec![
+                (TokenKind::Punctuation, "["),
+                (TokenKind::WordsBegin, "%w["),
+                (TokenKind::StringContent, "a (b)"),
+                (TokenKind::StringEnd, "]"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::StringBegin, "%q("),
+                (TokenKind::StringContent, "x"),
+                (TokenKind::StringEnd, ")"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Integer, "10"),
+                (TokenKind::Operator, "%"),
+                (TokenKind::Integer, "3"),
+                (TokenKind::Punctuation, "]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbols_and_variables() {
+        assert_eq!(
+            kinds("{ a: :b, :\"c\" => @d, e: $stdout, f: @@g, h: :[]= }"),
+            vec![
+                (TokenKind::Punctuation, "{"),
+                (TokenKind::Label, "a:"),
+                (TokenKind::Symbol, ":b"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::SymbolBegin, ":\""),
+                (TokenKind::StringContent, "c"),
+                (TokenKind::StringEnd, "\""),
+                (TokenKind::Operator, "=>"),
+                (TokenKind::InstanceVariable, "@d"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Label, "e:"),
+                (TokenKind::GlobalVariable, "$stdout"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Label, "f:"),
+                (TokenKind::ClassVariable, "@@g"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Label, "h:"),
+                (TokenKind::Symbol, ":[]="),
+                (TokenKind::Punctuation, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ternary_is_not_character_literal() {
+        assert_eq!(
+            kinds("a ? b : c"),
+            vec![
+                (TokenKind::Identifier, "a"),
+                (TokenKind::Operator, "?"),
+                (TokenKind::Identifier, "b"),
+                (TokenKind::Operator, ":"),
+                (TokenKind::Identifier, "c"),
+            ]
+        );
+        assert_eq!(kinds("x = ?a")[2], (TokenKind::Character, "?a"));
+    }
+
+    #[test]
+    fn test_embedded_doc_and_data_section() {
+        assert_eq!(
+            kinds("=begin\nx = 1\n=end\ny\n__END__\nz = 2\n"),
+            vec![
+                (TokenKind::EmbeddedDoc, "=begin\nx = 1\n=end"),
+                (TokenKind::Identifier, "y"),
+                (TokenKind::DataSection, "__END__\nz = 2\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let source = "a = 1\n  bé = 'x'\n";
+        let tokens = tokenize(source);
+        let x = tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::StringContent)
+            .unwrap();
+        assert_eq!((x.line, x.column), (2, 9));
+        let be = tokens.iter().find(|t| t.text(source) == "bé").unwrap();
+        assert_eq!((be.line, be.column), (2, 3));
+    }
+}
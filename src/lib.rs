@@ -3,11 +3,23 @@
 //! This library provides the core linting infrastructure, cop implementations,
 //! and output formatting for the oxicop command-line tool.
 
+pub mod ast;
+pub mod baseline;
+pub mod cache;
 pub mod cop;
 pub mod cops;
 pub mod config;
+pub mod config_store;
+pub mod corrector;
+pub mod directive;
 pub mod formatter;
+pub mod git;
+pub mod lexer;
+pub mod lsp;
 pub mod offense;
+pub mod parser;
 pub mod registry;
 pub mod runner;
 pub mod source;
+pub mod target_ruby;
+pub mod todo_config;
@@ -0,0 +1,490 @@
+//! Language Server Protocol server for `oxicop lsp`.
+//!
+//! Speaks JSON-RPC over stdio. Open documents are linted from memory on
+//! every change with a runner whose configs stay loaded between requests,
+//! offenses are published as diagnostics, and code actions offer each
+//! offense's autocorrection and a `# rubocop:disable` comment. Documents are
+//! synced in full; positions use UTF-16 columns as the protocol requires.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::config_store::ConfigStore;
+use crate::cop::Severity;
+use crate::directive::{DirectiveMode, Directives};
+use crate::lexer::TokenKind;
+use crate::offense::{Edit, Offense};
+use crate::runner::Runner;
+use crate::source::SourceFile;
+
+/// JSON-RPC error code for a message body that isn't valid JSON.
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code for unknown request methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP `TextDocumentSyncKind.Full`.
+const SYNC_FULL: u8 = 1;
+
+/// An open document with its offenses from the last lint.
+struct Document {
+    source: SourceFile,
+    offenses: Vec<Offense>,
+}
+
+/// Lints the documents an editor has open.
+pub struct Server {
+    runner: Runner,
+    documents: HashMap<String, Document>,
+}
+
+impl Server {
+    /// Creates a server resolving each document's config through `configs`.
+    pub fn new(configs: ConfigStore) -> Self {
+        Self {
+            runner: Runner::with_configs(configs),
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Reads messages from `input` and writes responses and notifications
+    /// to `output` until the client sends `exit` or closes the stream. A body
+    /// that isn't valid JSON gets a parse error reply; only broken framing
+    /// or I/O ends the session.
+    pub fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        while let Some(body) = read_message(&mut input)? {
+            let message: Value = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                Err(err) => {
+                    let error = json!({ "code": PARSE_ERROR, "message": format!("parse error: {}", err) });
+                    write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": null, "error": error }))?;
+                    continue;
+                }
+            };
+            if message["method"] == "exit" {
+                break;
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one client message, returning the messages to send back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let Some(id) = message.get("id") else {
+            return self.notification(method, params);
+        };
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": SYNC_FULL },
+                    "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                },
+                "serverInfo": { "name": "oxicop", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/codeAction" => Ok(self.code_actions(params)),
+            _ => Err(json!({ "code": METHOD_NOT_FOUND, "message": format!("unknown method {}", method) })),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        };
+        vec![response]
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        let version = params["textDocument"]["version"].as_i64();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                vec![self.lint(uri, version, text.to_string())]
+            }
+            "textDocument/didChange" => {
+                // With full sync the last change holds the whole document
+                let changes = params["contentChanges"].as_array();
+                match changes.and_then(|c| c.last()).and_then(|c| c["text"].as_str()) {
+                    Some(text) => vec![self.lint(uri, version, text.to_string())],
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(&uri, None, Vec::new())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Lints the document at `uri` and returns its diagnostics notification.
+    fn lint(&mut self, uri: String, version: Option<i64>, text: String) -> Value {
+        let path = uri_to_path(&uri);
+        let (source, offenses) = match self.runner.check_source(SourceFile::from_string(path.clone(), text.clone())) {
+            Some((result, source)) => (source, result.offenses),
+            None => (SourceFile::from_string(path, text), Vec::new()),
+        };
+        let diagnostics = offenses.iter().map(|offense| diagnostic(&source, offense)).collect();
+        let notification = publish_diagnostics(&uri, version, diagnostics);
+        self.documents.insert(uri, Document { source, offenses });
+        notification
+    }
+
+    /// Quick fixes for the offenses on the lines of the requested range.
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        let first = params["range"]["start"]["line"].as_u64().unwrap_or(0) as usize + 1;
+        let last = params["range"]["end"]["line"].as_u64().map_or(first, |line| line as usize + 1);
+        let source = &document.source;
+        let directives = Directives::parse(source);
+
+        let mut actions = Vec::new();
+        for offense in &document.offenses {
+            let line = offense.location.line;
+            if line < first || line > last {
+                continue;
+            }
+            let diagnostics = [diagnostic(source, offense)];
+            if offense.is_correctable() {
+                let edits: Vec<Value> = offense.edits.iter().map(|edit| text_edit(source, edit)).collect();
+                actions.push(json!({
+                    "title": format!("Autocorrect {}", offense.cop_name),
+                    "kind": "quickfix",
+                    "diagnostics": diagnostics,
+                    "isPreferred": true,
+                    "edit": { "changes": { uri: edits } },
+                }));
+            }
+            let edits: Vec<Value> = disable_edits(source, &directives, line, &offense.cop_name)
+                .iter()
+                .map(|edit| text_edit(source, edit))
+                .collect();
+            actions.push(json!({
+                "title": format!("Disable {} for this line", offense.cop_name),
+                "kind": "quickfix",
+                "diagnostics": diagnostics,
+                "edit": { "changes": { uri: edits } },
+            }));
+        }
+        Value::Array(actions)
+    }
+}
+
+/// Edits disabling `cop_name` on `line`, as RuboCop adds them. An inline
+/// `disable` already on the line gets the cop added to its list, and
+/// otherwise a directive is appended to the line. Where that would land
+/// inside a string or heredoc, or the line holds a directive that can't be
+/// extended, a `disable`/`enable` pair goes around the lines of the literal.
+fn disable_edits(source: &SourceFile, directives: &Directives, line: usize, cop_name: &str) -> Vec<Edit> {
+    let existing = directives.directives.iter().find(|d| d.line == line);
+    let malformed = directives.malformed.iter().any(|m| m.line == line);
+    match existing {
+        Some(directive) if directive.inline && directive.mode != DirectiveMode::Enable && !malformed => {
+            let last = directive.names.last().unwrap();
+            let end = source.offset_of(line, last.column + last.name.chars().count()).unwrap();
+            return vec![Edit::insert(end, format!(", {}", cop_name))];
+        }
+        None if !malformed && !continues_literal(source, line) => {
+            let line_end = source.offset_of(line, usize::MAX).unwrap_or(source.content.len());
+            return vec![Edit::insert(line_end, format!(" # rubocop:disable {}", cop_name))];
+        }
+        _ => {}
+    }
+
+    let mut first = line;
+    while first > 1 && continues_literal(source, first - 1) {
+        first -= 1;
+    }
+    let mut last = line;
+    while last < source.line_count() && continues_literal(source, last) {
+        last += 1;
+    }
+    let first_line = source.line(first).unwrap_or_default();
+    let indent = &first_line[..first_line.len() - first_line.trim_start().len()];
+    let start = source.offset_of(first, 1).unwrap_or(0);
+    let end = source.offset_of(last, usize::MAX).unwrap_or(source.content.len());
+    let enable = format!("{}# rubocop:enable {}", indent, cop_name);
+    vec![
+        Edit::insert(start, format!("{}# rubocop:disable {}\n", indent, cop_name)),
+        match source.content[end..].strip_prefix('\n') {
+            Some(_) => Edit::insert(end + 1, enable + "\n"),
+            None => Edit::insert(end, format!("\n{}", enable)),
+        },
+    ]
+}
+
+/// Whether the code of `line` runs on into the next line as part of a
+/// literal: its line break is inside a string, or it opens a heredoc.
+fn continues_literal(source: &SourceFile, line: usize) -> bool {
+    let (Some(start), Some(end)) = (source.offset_of(line, 1), source.offset_of(line, usize::MAX)) else {
+        return false;
+    };
+    let opens_heredoc = source
+        .tokens
+        .iter()
+        .any(|token| token.kind == TokenKind::HeredocBegin && start <= token.start && token.start < end);
+    opens_heredoc || (end < source.content.len() && source.in_string_or_comment(line, usize::MAX))
+}
+
+fn publish_diagnostics(uri: &str, version: Option<i64>, diagnostics: Vec<Value>) -> Value {
+    let mut params = json!({ "uri": uri, "diagnostics": diagnostics });
+    if let Some(version) = version {
+        params["version"] = json!(version);
+    }
+    json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": params })
+}
+
+fn diagnostic(source: &SourceFile, offense: &Offense) -> Value {
+    let location = &offense.location;
+    let line = source.line(location.line).unwrap_or_default();
+    let start = utf16_column(line, location.column);
+    let end = utf16_column(line, location.column + location.length);
+    json!({
+        "range": {
+            "start": { "line": location.line - 1, "character": start },
+            "end": { "line": location.line - 1, "character": end },
+        },
+        "severity": diagnostic_severity(offense.severity),
+        "code": offense.cop_name,
+        "source": "oxicop",
+        "message": offense.message,
+    })
+}
+
+/// LSP `DiagnosticSeverity` of an offense, mapped as RuboCop's server does.
+fn diagnostic_severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Fatal | Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Convention => 3,
+        Severity::Refactor | Severity::Info => 4,
+    }
+}
+
+fn text_edit(source: &SourceFile, edit: &Edit) -> Value {
+    json!({
+        "range": { "start": position(source, edit.start), "end": position(source, edit.end) },
+        "newText": edit.replacement,
+    })
+}
+
+/// LSP position of a byte offset.
+fn position(source: &SourceFile, offset: usize) -> Value {
+    let (line, column) = source.position_of(offset);
+    let text = source.line(line).unwrap_or_default();
+    json!({ "line": line - 1, "character": utf16_column(text, column) })
+}
+
+/// 0-based UTF-16 offset of a 1-based character column of `line`.
+fn utf16_column(line: &str, column: usize) -> usize {
+    line.chars().take(column.saturating_sub(1)).map(char::len_utf16).sum()
+}
+
+/// File path of a `file://` URI. Other schemes, such as the `untitled:`
+/// of unsaved buffers, keep their last segment as a relative path.
+fn uri_to_path(uri: &str) -> PathBuf {
+    match uri.strip_prefix("file://") {
+        Some(path) => PathBuf::from(percent_decode(path)),
+        None => PathBuf::from(percent_decode(uri.rsplit([':', '/']).next().unwrap_or(uri))),
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match hex.filter(|_| bytes[i] == b'%').and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Reads one `Content-Length`-framed message, or `None` at end of input.
+/// Reads the body of the next message, or `None` at the end of the stream.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offense::Location;
+    use crate::registry::CopRegistry;
+
+    fn open(server: &mut Server, text: &str) -> Value {
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {
+                "textDocument": { "uri": "file:///tmp/app/a%20b.rb", "languageId": "ruby", "version": 1, "text": text },
+            },
+        }));
+        replies[0]["params"].clone()
+    }
+
+    #[test]
+    fn test_utf16_columns_and_uris() {
+        assert_eq!(utf16_column("héllo", 3), 2);
+        assert_eq!(utf16_column("😀x", 2), 2);
+        assert_eq!(uri_to_path("file:///tmp/a%20b.rb"), PathBuf::from("/tmp/a b.rb"));
+        assert_eq!(uri_to_path("untitled:Untitled-1"), PathBuf::from("Untitled-1"));
+    }
+
+    #[test]
+    fn test_diagnostics_use_utf16_ranges() {
+        let mut server = Server::new(ConfigStore::fixed(CopRegistry::new()));
+        let params = open(&mut server, "x = 1  \n");
+        assert_eq!(params["uri"], "file:///tmp/app/a%20b.rb");
+        assert_eq!(params["version"], 1);
+        let trailing = params["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["code"] == "Layout/TrailingWhitespace")
+            .unwrap();
+        assert_eq!(trailing["severity"], 3);
+        assert_eq!(trailing["source"], "oxicop");
+
+        // The emoji is one character but two UTF-16 code units
+        let source = SourceFile::from_string(PathBuf::from("a.rb"), "x = '😀' + y\n".into());
+        let offense = Offense::new("Lint/Foo", "Foo.", Severity::Warning, Location::new(1, 9, 3));
+        let diagnostic = diagnostic(&source, &offense);
+        assert_eq!(diagnostic["range"]["start"], json!({ "line": 0, "character": 9 }));
+        assert_eq!(diagnostic["range"]["end"], json!({ "line": 0, "character": 12 }));
+        assert_eq!(diagnostic["severity"], 2);
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let mut server = Server::new(ConfigStore::fixed(CopRegistry::new()));
+        open(&mut server, "# frozen_string_literal: true\n\nfoo(a,b)\n");
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "textDocument/codeAction",
+            "params": {
+                "textDocument": { "uri": "file:///tmp/app/a%20b.rb" },
+                "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 2, "character": 0 } },
+                "context": { "diagnostics": [] },
+            },
+        }));
+        assert_eq!(replies[0]["id"], 7);
+        let actions = replies[0]["result"].as_array().unwrap();
+        let titles: Vec<&str> = actions.iter().map(|a| a["title"].as_str().unwrap()).collect();
+        assert!(titles.contains(&"Autocorrect Layout/SpaceAfterComma"), "{:?}", titles);
+        assert!(titles.contains(&"Disable Layout/SpaceAfterComma for this line"));
+
+        let fix = &actions[titles.iter().position(|t| *t == "Autocorrect Layout/SpaceAfterComma").unwrap()];
+        let edit = &fix["edit"]["changes"]["file:///tmp/app/a%20b.rb"][0];
+        assert_eq!(edit["range"]["start"], json!({ "line": 2, "character": 6 }));
+        assert_eq!(edit["newText"], " ");
+
+        let disable = &actions[titles.iter().position(|t| t.starts_with("Disable")).unwrap()];
+        let edit = &disable["edit"]["changes"]["file:///tmp/app/a%20b.rb"][0];
+        assert_eq!(edit["range"]["start"], json!({ "line": 2, "character": 8 }));
+        assert_eq!(edit["newText"], " # rubocop:disable Layout/SpaceAfterComma");
+    }
+
+    /// The source with `Lint/Foo` disabled on `line` by the quick fix.
+    fn disable_on_line(content: &str, line: usize) -> String {
+        let source = SourceFile::from_string(PathBuf::from("a.rb"), content.to_string());
+        let mut edits = disable_edits(&source, &Directives::parse(&source), line, "Lint/Foo");
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+        let mut out = content.to_string();
+        for edit in edits {
+            out.replace_range(edit.start..edit.end, &edit.replacement);
+        }
+        out
+    }
+
+    #[test]
+    fn test_disable_extends_existing_comments() {
+        assert_eq!(disable_on_line("x = 1\n", 1), "x = 1 # rubocop:disable Lint/Foo\n");
+        assert_eq!(
+            disable_on_line("x = 1 # rubocop:disable Style/Bar -- legacy\n", 1),
+            "x = 1 # rubocop:disable Style/Bar, Lint/Foo -- legacy\n"
+        );
+
+        // Another comment is left as it is, and the directive after it
+        // still counts
+        let fixed = disable_on_line("x = 1 # legacy\ny = 2\n", 1);
+        assert_eq!(fixed, "x = 1 # legacy # rubocop:disable Lint/Foo\ny = 2\n");
+        let source = SourceFile::from_string(PathBuf::from("a.rb"), fixed);
+        assert!(Directives::parse(&source).is_disabled("Lint/Foo", 1));
+
+        // A directive on its own line can't be extended
+        assert_eq!(
+            disable_on_line("def a\n  # rubocop:disable Style/Bar\nend\n", 2),
+            "def a\n  # rubocop:disable Lint/Foo\n  # rubocop:disable Style/Bar\n  # rubocop:enable Lint/Foo\nend\n"
+        );
+    }
+
+    #[test]
+    fn test_disable_around_strings_and_heredocs() {
+        assert_eq!(
+            disable_on_line("  x = \"a\n  b\n  c\"\ny\n", 2),
+            "  # rubocop:disable Lint/Foo\n  x = \"a\n  b\n  c\"\n  # rubocop:enable Lint/Foo\ny\n"
+        );
+        assert_eq!(
+            disable_on_line("sql = <<~SQL\n  SELECT 1\nSQL\nz", 2),
+            "# rubocop:disable Lint/Foo\nsql = <<~SQL\n  SELECT 1\nSQL\n# rubocop:enable Lint/Foo\nz"
+        );
+        assert_eq!(
+            disable_on_line("x = 1\ns = 'a\nb'", 2),
+            "x = 1\n# rubocop:disable Lint/Foo\ns = 'a\nb'\n# rubocop:enable Lint/Foo"
+        );
+    }
+
+    #[test]
+    fn test_unknown_request() {
+        let mut server = Server::new(ConfigStore::fixed(CopRegistry::new()));
+        let replies = server.handle(&json!({ "jsonrpc": "2.0", "id": 1, "method": "workspace/symbol" }));
+        assert_eq!(replies[0]["error"]["code"], METHOD_NOT_FOUND);
+        assert!(server.handle(&json!({ "jsonrpc": "2.0", "method": "$/cancelRequest" })).is_empty());
+    }
+}
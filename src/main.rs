@@ -1,24 +1,40 @@
 //! Oxicop - A blazing-fast Ruby linter in Rust.
 
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process;
 
-use clap::Parser;
+use clap::{ArgAction, Parser, Subcommand};
 use ignore::WalkBuilder;
 
-use oxicop::config::Config;
+use oxicop::baseline::Baseline;
+use oxicop::cache::ResultCache;
+use oxicop::config::{Config, TODO_FILE_NAME};
+use oxicop::config_store::{ConfigStore, RegistryBuilder};
+use oxicop::cop::Severity;
+use oxicop::corrector::AutocorrectMode;
 use oxicop::formatter::{create_formatter, Format};
+use oxicop::git::{Changes, Index};
+use oxicop::lsp::Server;
 use oxicop::registry::CopRegistry;
-use oxicop::runner::Runner;
+use oxicop::runner::{RunResult, Runner};
+use oxicop::source::SourceFile;
+use oxicop::target_ruby::TargetRuby;
+use oxicop::todo_config::{self, DEFAULT_EXCLUDE_LIMIT};
 
 #[derive(Parser)]
-#[command(name = "oxicop", about = "A blazing-fast Ruby linter", version)]
+#[command(name = "oxicop", about = "A blazing-fast Ruby linter", version, disable_help_subcommand = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Files or directories to lint
     #[arg(default_value = ".")]
     paths: Vec<PathBuf>,
 
-    /// Output format (simple, compact, json)
+    /// Output format (simple, compact, clang, pretty, json, rubocop-json, sarif, junit, checkstyle, github, gitlab)
     #[arg(short, long, default_value = "simple")]
     format: String,
 
@@ -37,71 +53,316 @@ struct Cli {
     /// List all available cops
     #[arg(long)]
     list: bool,
+
+    /// Autocorrect offenses (only when it's safe)
+    #[arg(short = 'a', long)]
+    autocorrect: bool,
+
+    /// Autocorrect offenses (safe and unsafe)
+    #[arg(short = 'A', long)]
+    autocorrect_all: bool,
+
+    /// Apply AllCops/Exclude to files passed explicitly on the command line
+    #[arg(long)]
+    force_exclusion: bool,
+
+    /// Generate .rubocop_todo.yml silencing the current offenses
+    #[arg(long)]
+    auto_gen_config: bool,
+
+    /// Most files a cop may offend in before --auto-gen-config disables it
+    #[arg(long, default_value_t = DEFAULT_EXCLUDE_LIMIT)]
+    exclude_limit: usize,
+
+    /// Report only offenses missing from this baseline file, creating it
+    /// from the current offenses if it doesn't exist
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Only lint files changed since this git ref, including uncommitted
+    /// and untracked files
+    #[arg(long, value_name = "REF")]
+    since: Option<String>,
+
+    /// Only report offenses on lines changed since --since (HEAD by default)
+    #[arg(long)]
+    changed_lines_only: bool,
+
+    /// Lint the content staged in the git index rather than the working tree
+    #[arg(long)]
+    staged: bool,
+
+    /// Lint source read from stdin as if it were the file at this path;
+    /// with autocorrect, the corrected source follows the report
+    #[arg(long, value_name = "FILE")]
+    stdin: Option<PathBuf>,
+
+    /// Reuse the offenses of files unchanged since an earlier run
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    cache: bool,
+
+    /// Directory holding oxicop_cache (defaults to $XDG_CACHE_HOME or ~/.cache)
+    #[arg(long, value_name = "DIR")]
+    cache_root: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a Language Server Protocol server over stdio
+    Lsp,
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    // Build the cop registry
-    let mut registry = CopRegistry::new();
-
     // Handle --list flag
     if cli.list {
-        list_cops(&registry);
+        list_cops(&CopRegistry::new());
         return;
     }
 
-    // Load configuration
-    let config = if let Some(config_path) = cli.config {
-        match Config::from_file(&config_path) {
-            Ok(c) => Some(c),
-            Err(e) => {
-                eprintln!("Error loading config file: {}", e);
-                process::exit(1);
-            }
+    // Resolve configuration: the file given with --config, otherwise the
+    // nearest .rubocop.yml of each inspected file
+    let builder = registry_builder(cli.only.clone(), cli.except.clone());
+    let mut configs = match &cli.config {
+        Some(config_path) => ConfigStore::with_config_file(config_path, builder),
+        None => Ok(ConfigStore::new(builder)),
+    };
+    if cli.auto_gen_config {
+        configs = configs.and_then(ConfigStore::without_todo);
+    }
+    let configs = configs.unwrap_or_else(|e| {
+        eprintln!("Error loading config file: {}", e);
+        process::exit(1);
+    });
+
+    if let Some(Command::Lsp) = cli.command {
+        let stdin = std::io::stdin();
+        if let Err(e) = Server::new(configs).serve(stdin.lock(), std::io::stdout()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
         }
+        return;
+    }
+
+    let format = cli.format.parse::<Format>().unwrap_or(Format::Simple);
+    // --auto-gen-config records offenses as they are rather than fixing them
+    let autocorrect = if cli.auto_gen_config {
+        None
+    } else if cli.autocorrect_all {
+        Some(AutocorrectMode::All)
+    } else if cli.autocorrect {
+        Some(AutocorrectMode::Safe)
     } else {
-        Config::find_and_load()
+        None
     };
 
-    // Apply configuration to registry
-    if let Some(ref cfg) = config {
-        apply_config_to_registry(&mut registry, cfg);
+    if let Some(path) = &cli.stdin {
+        let offenses_remain = lint_stdin(path, configs, cli.force_exclusion, autocorrect, format).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        });
+        process::exit(if offenses_remain { 1 } else { 0 });
     }
 
-    // Apply CLI filters
-    if let Some(ref only) = cli.only {
-        apply_only_filter(&mut registry, only);
+    // A new baseline has to record the offenses of every file and line
+    if let Some(path) = cli.baseline.as_deref().filter(|path| !path.exists() && !cli.auto_gen_config) {
+        if cli.since.is_some() || cli.changed_lines_only || cli.staged {
+            eprintln!(
+                "Can't create {} with --since, --changed-lines-only or --staged; run without them first",
+                path.display()
+            );
+            process::exit(1);
+        }
     }
 
-    if let Some(ref except) = cli.except {
-        apply_except_filter(&mut registry, except);
+    // Discover Ruby files
+    let mut ruby_files = discover_ruby_files(&cli.paths, &configs, cli.force_exclusion).unwrap_or_else(|e| {
+        eprintln!("Error loading config file: {}", e);
+        process::exit(1);
+    });
+
+    // Keep only what changed since the given git ref
+    let changes = (cli.since.is_some() || cli.changed_lines_only).then(|| {
+        let reference = cli.since.as_deref().unwrap_or("HEAD");
+        Changes::since(Path::new("."), reference).unwrap_or_else(|e| {
+            eprintln!("Error reading changes since {}: {}", reference, e);
+            process::exit(1);
+        })
+    });
+    if let Some(changes) = &changes {
+        ruby_files.retain(|file| changes.contains_file(file));
     }
 
-    // Discover Ruby files
-    let ruby_files = discover_ruby_files(&cli.paths);
+    // Keep only staged files, to be read from the index
+    let index = cli.staged.then(|| {
+        if cli.autocorrect || cli.autocorrect_all {
+            eprintln!("--staged can't be combined with --autocorrect or --autocorrect-all");
+            process::exit(1);
+        }
+        Index::open(Path::new(".")).unwrap_or_else(|e| {
+            eprintln!("Error reading the git index: {}", e);
+            process::exit(1);
+        })
+    });
+    if let Some(index) = &index {
+        ruby_files.retain(|file| index.is_staged(file));
+    }
 
     if ruby_files.is_empty() {
         println!("No Ruby files found.");
         return;
     }
 
+    // --only picks the cops itself, so pending ones don't need configuring
+    if cli.only.is_none() {
+        if let Some(notice) = pending_cops_notice(&configs) {
+            eprintln!("{}", notice);
+        }
+    }
+
+    // Every registry holds all cops, so the first file's describes them
+    // to the formatter
+    let formatter = match configs.for_file(&ruby_files[0]) {
+        Ok(resolved) => create_formatter(format, &resolved.registry),
+        Err(e) => {
+            eprintln!("Error loading config file: {}", e);
+            process::exit(1);
+        }
+    };
+
     // Run the linter
-    let runner = Runner::new(registry);
-    let result = runner.run(&ruby_files);
+    let mut runner = Runner::with_configs(configs);
+    if let Some(mode) = autocorrect {
+        runner = runner.with_autocorrect(mode);
+    }
+    if cli.cache {
+        runner = runner.with_cache(ResultCache::new(cli.cache_root.as_deref()));
+    }
+    let mut result = match &index {
+        Some(index) => {
+            let sources = ruby_files
+                .iter()
+                .filter_map(|file| match index.read(file) {
+                    Ok(content) => Some(SourceFile::from_string(file.clone(), content)),
+                    Err(e) => {
+                        eprintln!("Error reading staged content: {}", e);
+                        None
+                    }
+                })
+                .collect();
+            runner.run_sources(sources)
+        }
+        None => runner.run(&ruby_files),
+    };
+
+    // Leave out the offenses recorded in an existing baseline. This comes
+    // before dropping unchanged lines, so offenses still found there don't
+    // count as fixed.
+    let baseline = cli.baseline.as_deref().filter(|_| !cli.auto_gen_config);
+    let mut fixed = 0;
+    if let Some(path) = baseline.filter(|path| path.exists()) {
+        let baseline = Baseline::load(path).unwrap_or_else(|e| {
+            eprintln!("Error loading baseline: {}", e);
+            process::exit(1);
+        });
+        fixed = baseline.filter(&mut result).fixed;
+    }
+    if let Some(changes) = changes.as_ref().filter(|_| cli.changed_lines_only) {
+        changes.filter(&mut result);
+    }
 
     // Format and print output
-    let format = cli.format.parse::<Format>().unwrap_or(Format::Simple);
-    let formatter = create_formatter(format);
     let output = formatter.format(&result);
     print!("{}", output);
 
+    if cli.auto_gen_config {
+        if let Err(e) = auto_gen_config(&result, cli.config.as_deref(), cli.exclude_limit) {
+            eprintln!("Error writing {}: {}", TODO_FILE_NAME, e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(path) = baseline {
+        if !path.exists() {
+            let baseline = Baseline::from_result(&result);
+            if let Err(e) = baseline.save(path) {
+                eprintln!("Error writing baseline: {}", e);
+                process::exit(1);
+            }
+            eprintln!(
+                "Created {} with {} offense{}.",
+                path.display(),
+                baseline.len(),
+                if baseline.len() == 1 { "" } else { "s" }
+            );
+            return;
+        }
+        if fixed > 0 {
+            eprintln!(
+                "{} offense{} in {} fixed; regenerate it to drop {}.",
+                fixed,
+                if fixed == 1 { "" } else { "s" },
+                path.display(),
+                if fixed == 1 { "it" } else { "them" }
+            );
+        }
+    }
+
     // Exit with appropriate code
-    if result.total_offenses > 0 {
+    if result.uncorrected_count() > 0 {
         process::exit(1);
     }
 }
 
+/// Lints source read from stdin as the file at `path` and prints the
+/// report. When autocorrecting, the corrected source follows a line of `=`,
+/// as RuboCop prints it for editor integrations. Returns whether offenses
+/// remain.
+fn lint_stdin(
+    path: &Path,
+    configs: ConfigStore,
+    force_exclusion: bool,
+    autocorrect: Option<AutocorrectMode>,
+    format: Format,
+) -> Result<bool, String> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| format!("cannot read stdin: {}", e))?;
+
+    // The AllCops Include/Exclude patterns apply as to a file named on the
+    // command line
+    let resolved = configs.for_file(path)?;
+    let filter = &resolved.file_filter;
+    let excluded = force_exclusion && filter.is_excluded(path);
+    let inspected = !excluded && (is_ruby_file(path) || filter.is_included(path));
+    let formatter = create_formatter(format, &resolved.registry);
+
+    let mut runner = Runner::with_configs(configs);
+    if let Some(mode) = autocorrect {
+        runner = runner.with_autocorrect(mode);
+    }
+    let source = SourceFile::from_string(path.to_path_buf(), content.clone());
+    let (file_results, corrected) = match runner.check_source(source).filter(|_| inspected) {
+        Some((file_result, corrected)) => (vec![file_result], corrected.content),
+        None => (Vec::new(), content),
+    };
+    let result = RunResult {
+        total_files: file_results.len(),
+        total_offenses: file_results.iter().map(|r| r.offenses.len()).sum(),
+        file_results,
+    };
+
+    print!("{}", formatter.format(&result));
+    if autocorrect.is_some() {
+        println!("{}", "=".repeat(20));
+        print!("{}", corrected);
+    }
+    Ok(result.uncorrected_count() > 0)
+}
+
 /// Lists all available cops with their categories and descriptions.
 fn list_cops(registry: &CopRegistry) {
     println!("Available cops:\n");
@@ -116,32 +377,120 @@ fn list_cops(registry: &CopRegistry) {
     println!("\nTotal: {} cops", registry.total_count());
 }
 
-/// Applies configuration settings to the registry.
-fn apply_config_to_registry(registry: &mut CopRegistry, config: &Config) {
+/// Returns how each config's registry is built: the config's own settings,
+/// then the --only and --except filters.
+fn registry_builder(only: Option<String>, except: Option<String>) -> RegistryBuilder {
+    Box::new(move |config| {
+        let mut registry = CopRegistry::new();
+        let defaults = Config::default();
+        apply_config_to_registry(&mut registry, config.unwrap_or(&defaults)).map_err(|e| e.to_string())?;
+        if let Some(only) = &only {
+            apply_only_filter(&mut registry, only);
+        }
+        if let Some(except) = &except {
+            apply_except_filter(&mut registry, except);
+        }
+        Ok(registry)
+    })
+}
+
+/// Applies configuration settings, and the built-in defaults for anything
+/// they leave out, to the registry, along with the target Ruby version.
+fn apply_config_to_registry(registry: &mut CopRegistry, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let cop_names: Vec<String> = registry.cop_names().iter().map(|&s| s.to_string()).collect();
     for cop_name in &cop_names {
-        if let Some(enabled) = config.is_cop_enabled(cop_name) {
+        if let Some(enabled) = config.resolve_enabled(cop_name) {
             if !enabled {
                 registry.disable(cop_name);
             } else {
                 registry.enable(cop_name);
             }
         }
+        if let Some(cop_config) = config.cop_config(cop_name) {
+            registry
+                .configure(cop_name, &cop_config)
+                .map_err(|e| format!("{}: {}", cop_name, e))?;
+        }
+        if let Some(filter) = config.cop_file_filter(cop_name) {
+            registry.set_path_filter(cop_name, filter?);
+        }
+        if let Some(severity) = config.cop_severity(cop_name) {
+            let severity = severity
+                .parse::<Severity>()
+                .map_err(|e| format!("{}: {}", cop_name, e))?;
+            registry.set_severity(cop_name, severity);
+        }
     }
+    registry.set_target_ruby_version(TargetRuby::resolve(config).version);
+    Ok(())
 }
 
-/// Applies the --only filter to enable only specified cops.
+/// Applies the --only filter to enable only specified cops, even those
+/// disabled by default.
 fn apply_only_filter(registry: &mut CopRegistry, only: &str) {
     let allowed: Vec<&str> = only.split(',').map(|s| s.trim()).collect();
     let cop_names: Vec<String> = registry.cop_names().iter().map(|&s| s.to_string()).collect();
 
     for cop_name in &cop_names {
-        if !allowed.contains(&cop_name.as_str()) {
+        if allowed.contains(&cop_name.as_str()) {
+            registry.enable(cop_name);
+        } else {
             registry.disable(cop_name);
         }
     }
 }
 
+/// Writes `.rubocop_todo.yml` to the working directory and makes the config
+/// file (`.rubocop.yml` unless given with --config) inherit from it.
+fn auto_gen_config(result: &RunResult, config: Option<&Path>, exclude_limit: usize) -> std::io::Result<()> {
+    let dir = std::env::current_dir()?;
+    let todo = todo_config::generate(result, &CopRegistry::new(), &dir, exclude_limit);
+    fs::write(dir.join(TODO_FILE_NAME), todo)?;
+    println!("Created {}.", TODO_FILE_NAME);
+
+    let config = config.unwrap_or(Path::new(".rubocop.yml"));
+    let config_dir = std::path::absolute(config)?
+        .parent()
+        .map_or_else(|| dir.clone(), Path::to_path_buf);
+    let todo_path = todo_config::todo_path_from(&config_dir, &dir);
+    let existing = fs::read_to_string(config).ok();
+    if let Some(updated) = todo_config::add_inheritance(existing.as_deref(), &todo_path) {
+        fs::write(config, updated)?;
+        println!("Added inheritance from `{}` in `{}`.", todo_path, config.display());
+    }
+    Ok(())
+}
+
+/// Builds RuboCop's notice listing the pending cops no config has decided
+/// on, or `None` if there are none.
+fn pending_cops_notice(configs: &ConfigStore) -> Option<String> {
+    let defaults = Config::default();
+    let mut pending = BTreeSet::new();
+    for resolved in configs.resolved() {
+        let config = resolved.config.as_ref().unwrap_or(&defaults);
+        for cop_name in resolved.registry.cop_names() {
+            if config.is_cop_pending(cop_name) {
+                pending.insert(cop_name.to_string());
+            }
+        }
+    }
+    if pending.is_empty() {
+        return None;
+    }
+
+    let mut notice = String::from(
+        "The following cops were added to RuboCop, but are not configured. \
+         Please set Enabled to either `true` or `false` in your `.rubocop.yml` file.\n\n\
+         Please also note that you can opt-in to new cops by default by adding this to your config:\n  \
+         AllCops:\n    NewCops: enable\n",
+    );
+    for cop_name in pending {
+        notice.push_str(&format!("\n{}:\n  Enabled: true", cop_name));
+    }
+    notice.push_str("\n\nFor more information: https://docs.rubocop.org/rubocop/versioning.html");
+    Some(notice)
+}
+
 /// Applies the --except filter to disable specified cops.
 fn apply_except_filter(registry: &mut CopRegistry, except: &str) {
     let excluded: Vec<&str> = except.split(',').map(|s| s.trim()).collect();
@@ -151,27 +500,43 @@ fn apply_except_filter(registry: &mut CopRegistry, except: &str) {
     }
 }
 
-/// Discovers all Ruby files in the given paths.
-fn discover_ruby_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+/// Discovers all Ruby files in the given paths. Files found by walking a
+/// directory are subject to the AllCops Include/Exclude patterns of the
+/// config governing them; files passed explicitly are only excluded with
+/// `--force-exclusion`.
+fn discover_ruby_files(paths: &[PathBuf], configs: &ConfigStore, force_exclusion: bool) -> Result<Vec<PathBuf>, String> {
     let mut ruby_files = Vec::new();
 
     for path in paths {
         if path.is_file() {
-            if is_ruby_file(path) {
+            let filter = &configs.for_file(path)?.file_filter;
+            let excluded = force_exclusion && filter.is_excluded(path);
+            if !excluded && (is_ruby_file(path) || filter.is_included(path)) {
                 ruby_files.push(path.clone());
             }
         } else if path.is_dir() {
-            // Use WalkBuilder for efficient directory traversal
-            for entry in WalkBuilder::new(path).build().flatten() {
+            // Use WalkBuilder for efficient directory traversal, pruning
+            // directories such as `vendor/**` excluded where the walk starts
+            let pruned = configs.for_dir(path)?.file_filter.clone();
+            let walker = WalkBuilder::new(path)
+                .filter_entry(move |entry| {
+                    !entry.file_type().is_some_and(|t| t.is_dir()) || !pruned.is_excluded_dir(entry.path())
+                })
+                .build();
+            for entry in walker.flatten() {
                 let entry_path = entry.path();
-                if entry_path.is_file() && is_ruby_file(entry_path) {
+                if !entry_path.is_file() {
+                    continue;
+                }
+                let filter = &configs.for_file(entry_path)?.file_filter;
+                if (is_ruby_file(entry_path) || filter.is_included(entry_path)) && !filter.is_excluded(entry_path) {
                     ruby_files.push(entry_path.to_path_buf());
                 }
             }
         }
     }
 
-    ruby_files
+    Ok(ruby_files)
 }
 
 /// Checks if a file is a Ruby file based on extension.
@@ -233,18 +598,94 @@ mod tests {
         cops.insert(
             "Layout/TrailingWhitespace".to_string(),
             oxicop::config::CopConfig {
-                enabled: Some(false),
-                severity: None,
+                enabled: Some(oxicop::config::CopState::Disabled),
+                ..Default::default()
             },
         );
 
         let config = Config {
             all_cops: None,
             cops,
+            ..Default::default()
         };
 
-        apply_config_to_registry(&mut registry, &config);
+        apply_config_to_registry(&mut registry, &config).unwrap();
 
         assert!(!registry.is_enabled("Layout/TrailingWhitespace"));
     }
+
+    #[test]
+    fn test_discover_ruby_files_applies_exclude() {
+        use std::fs;
+
+        let dir = std::env::temp_dir().join("oxicop_discover_exclude");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("vendor/gems")).unwrap();
+        fs::create_dir_all(dir.join("app")).unwrap();
+        fs::write(dir.join("app/a.rb"), "").unwrap();
+        fs::write(dir.join("vendor/gems/b.rb"), "").unwrap();
+        fs::write(dir.join("Rakefile"), "").unwrap();
+
+        fs::write(
+            dir.join(".rubocop.yml"),
+            "AllCops:\n  Include: [Rakefile]\n  Exclude: ['vendor/**/*']\n",
+        )
+        .unwrap();
+        let configs = ConfigStore::new(registry_builder(None, None));
+
+        let mut found = discover_ruby_files(std::slice::from_ref(&dir), &configs, false).unwrap();
+        found.sort();
+        assert_eq!(found, vec![dir.join("Rakefile"), dir.join("app/a.rb")]);
+
+        // Explicit paths bypass Exclude unless --force-exclusion is given.
+        let explicit = vec![dir.join("vendor/gems/b.rb")];
+        assert_eq!(discover_ruby_files(&explicit, &configs, false).unwrap(), explicit);
+        assert!(discover_ruby_files(&explicit, &configs, true).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_defaults_and_pending_notice() {
+        let configs = ConfigStore::fixed(registry_builder(None, None)(None).unwrap());
+        let registry = &configs.resolved()[0].registry;
+        assert!(registry.is_enabled("Layout/TrailingWhitespace"));
+        assert!(!registry.is_enabled("Style/Copyright"));
+        assert!(!registry.is_enabled("Style/FileRead"));
+
+        let notice = pending_cops_notice(&configs).unwrap();
+        assert!(notice.starts_with("The following cops were added to RuboCop, but are not configured."));
+        assert!(notice.contains("\nStyle/FileRead:\n  Enabled: true\n"));
+        assert!(!notice.contains("Style/Copyright"));
+
+        let path = std::env::temp_dir().join("oxicop_new_cops_disable.yml");
+        std::fs::write(&path, "AllCops:\n  NewCops: disable\n").unwrap();
+        let configs = ConfigStore::with_config_file(&path, registry_builder(None, None)).unwrap();
+        assert!(pending_cops_notice(&configs).is_none());
+        let _ = std::fs::remove_file(&path);
+
+        // --only runs the named cops even when they're off by default.
+        let registry = registry_builder(Some("Style/Copyright".to_string()), None)(None).unwrap();
+        assert!(registry.is_enabled("Style/Copyright"));
+        assert_eq!(registry.enabled_count(), 1);
+    }
+
+    #[test]
+    fn test_apply_config_severity_and_params() {
+        let config: Config = serde_yaml::from_str("Lint/Debugger:\n  Severity: error\n").unwrap();
+        let mut registry = CopRegistry::new();
+        apply_config_to_registry(&mut registry, &config).unwrap();
+        assert_eq!(registry.severity_of("Lint/Debugger"), Some(Severity::Error));
+
+        let config: Config = serde_yaml::from_str("Metrics/MethodLength:\n  Max: lots\n").unwrap();
+        let err = apply_config_to_registry(&mut CopRegistry::new(), &config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Metrics/MethodLength: `Max` must be a non-negative integer, got `lots`"
+        );
+
+        let config: Config = serde_yaml::from_str("Lint/Debugger:\n  Severity: loud\n").unwrap();
+        let err = apply_config_to_registry(&mut CopRegistry::new(), &config).unwrap_err();
+        assert!(err.to_string().starts_with("Lint/Debugger: invalid severity `loud`"));
+    }
 }
@@ -1,9 +1,11 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 use crate::cop::Severity;
 
 /// A source location pointing to a specific range in a file.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Location {
     /// 1-based line number.
     pub line: usize,
@@ -29,8 +31,43 @@ impl fmt::Display for Location {
     }
 }
 
+/// A replacement of a byte range of the source, used for autocorrection.
+/// Insertions have an empty range, removals an empty replacement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    /// Byte offset where the replaced range starts.
+    pub start: usize,
+    /// Byte offset one past the end of the replaced range.
+    pub end: usize,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn replace(start: usize, end: usize, replacement: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            replacement: replacement.into(),
+        }
+    }
+
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self::replace(offset, offset, text)
+    }
+
+    pub fn remove(start: usize, end: usize) -> Self {
+        Self::replace(start, end, "")
+    }
+
+    /// Whether two edits touch the same text, so applying both would be
+    /// ambiguous. Two insertions at the same offset also conflict.
+    pub fn conflicts_with(&self, other: &Edit) -> bool {
+        (self.start < other.end && other.start < self.end) || self.start == other.start
+    }
+}
+
 /// A single offense (diagnostic) reported by a cop.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Offense {
     /// Fully qualified cop name, e.g. "Layout/TrailingWhitespace".
     pub cop_name: String,
@@ -40,6 +77,12 @@ pub struct Offense {
     pub severity: Severity,
     /// Location in the source file.
     pub location: Location,
+    /// Edits that fix the offense, empty when it can't be autocorrected.
+    pub edits: Vec<Edit>,
+    /// Whether the edits were applied by autocorrection.
+    pub corrected: bool,
+    /// Identifies the offense across runs; set by the runner.
+    pub fingerprint: Option<String>,
 }
 
 impl Offense {
@@ -54,8 +97,22 @@ impl Offense {
             message: message.into(),
             severity,
             location,
+            edits: Vec::new(),
+            corrected: false,
+            fingerprint: None,
         }
     }
+
+    /// Attaches an edit that corrects this offense.
+    pub fn with_edit(mut self, edit: Edit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// Whether the offense carries edits that would fix it.
+    pub fn is_correctable(&self) -> bool {
+        !self.edits.is_empty()
+    }
 }
 
 impl fmt::Display for Offense {
@@ -91,4 +148,13 @@ mod tests {
             "1:10: C: Trailing whitespace detected. (Layout/TrailingWhitespace)"
         );
     }
+
+    #[test]
+    fn test_edit_conflicts() {
+        let removal = Edit::remove(4, 8);
+        assert!(removal.conflicts_with(&Edit::replace(6, 10, "x")));
+        assert!(removal.conflicts_with(&Edit::insert(4, " ")));
+        assert!(!removal.conflicts_with(&Edit::insert(8, " ")));
+        assert!(!removal.conflicts_with(&Edit::remove(0, 4)));
+    }
 }
@@ -0,0 +1,2960 @@
+//! Recursive-descent Ruby parser building an [`ast::Node`] tree from the
+//! token stream produced by [`crate::lexer`].
+//!
+//! The parser is error tolerant: unexpected tokens become `Error` nodes and
+//! parsing continues, so cops always get a tree even for code that does not
+//! compile.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{IfKeyword, Node, NodeKind, ParamKind};
+use crate::lexer::{Token, TokenKind};
+
+/// Nesting limit protecting the parser's stack on pathological input.
+const MAX_DEPTH: usize = 256;
+
+// Binary operator precedences, lowest to highest.
+const PREC_RANGE: u8 = 3;
+const PREC_OR: u8 = 4;
+const PREC_AND: u8 = 5;
+const PREC_EQUALITY: u8 = 8;
+const PREC_COMPARISON: u8 = 9;
+const PREC_BIT_OR: u8 = 10;
+const PREC_BIT_AND: u8 = 11;
+const PREC_SHIFT: u8 = 12;
+const PREC_ADDITIVE: u8 = 13;
+const PREC_MULTIPLICATIVE: u8 = 14;
+const PREC_POWER: u8 = 16;
+
+/// Keywords that end a statement list.
+const BODY_TERMINATORS: &[&str] = &[
+    "end", "else", "elsif", "when", "in", "rescue", "ensure", "then",
+];
+
+/// Keywords that may follow an expression on the same line without being
+/// part of it.
+const TRAILING_KEYWORDS: &[&str] = &[
+    "if", "unless", "while", "until", "rescue", "and", "or", "then", "do", "end", "else",
+    "elsif", "when", "in", "ensure",
+];
+
+/// Keywords that can begin the first argument of a parenthesis-less call.
+const ARGUMENT_KEYWORDS: &[&str] = &[
+    "nil", "true", "false", "self", "not", "defined?", "__FILE__", "__LINE__", "__ENCODING__",
+    "super", "yield", "case", "def", "if", "unless", "while", "until", "begin",
+];
+
+const ASSIGNMENT_OPERATORS: &[&str] = &[
+    "+=", "-=", "*=", "/=", "%=", "**=", "||=", "&&=", "|=", "&=", "^=", "<<=", ">>=",
+];
+
+/// Parses a token stream into a syntax tree rooted at a `Statements` node.
+pub fn parse(source: &str, tokens: &[Token]) -> Node {
+    let mut parser = Parser::new(source, tokens);
+    let mut statements = Vec::new();
+    loop {
+        statements.extend(parser.parse_statements());
+        if parser.at_eof() {
+            break;
+        }
+        // A stray terminator at the top level: record it and move on.
+        let token = parser.advance();
+        statements.push(Node::new(NodeKind::Error, token.start, token.end));
+    }
+    Node::new(NodeKind::Statements(statements), 0, source.len())
+}
+
+struct Scope {
+    locals: HashSet<String>,
+    inherits: bool,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    heredoc_bodies: HashMap<usize, Vec<Token>>,
+    pos: usize,
+    prev_end: usize,
+    scopes: Vec<Scope>,
+    depth: usize,
+    no_do_block: bool,
+    in_pattern: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, all_tokens: &[Token]) -> Self {
+        let significant = all_tokens.iter().filter(|t| !t.kind.is_trivia());
+        let mut tokens = Vec::new();
+        let mut heredoc_bodies = HashMap::new();
+        let mut pending = Vec::new();
+        let mut iter = significant.copied().peekable();
+        while let Some(token) = iter.next() {
+            tokens.push(token);
+            match token.kind {
+                TokenKind::HeredocBegin => pending.push(token.start),
+                TokenKind::Newline if !pending.is_empty() => {
+                    // Heredoc bodies follow the line that opened them; move
+                    // them aside so statements read straight through.
+                    for key in pending.drain(..) {
+                        let mut body = Vec::new();
+                        let mut depth = 0usize;
+                        for body_token in iter.by_ref() {
+                            body.push(body_token);
+                            match body_token.kind {
+                                TokenKind::InterpolationBegin => depth += 1,
+                                TokenKind::InterpolationEnd => depth = depth.saturating_sub(1),
+                                TokenKind::HeredocEnd if depth == 0 => break,
+                                _ => {}
+                            }
+                        }
+                        heredoc_bodies.insert(key, body);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self {
+            src,
+            tokens,
+            heredoc_bodies,
+            pos: 0,
+            prev_end: 0,
+            scopes: vec![Scope {
+                locals: HashSet::new(),
+                inherits: false,
+            }],
+            depth: 0,
+            no_do_block: false,
+            in_pattern: false,
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // Token helpers
+    // ------------------------------------------------------------------
+
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<Token> {
+        self.tokens.get(self.pos + offset).copied()
+    }
+
+    fn text(&self) -> &'a str {
+        self.peek().map_or("", |t| t.text(self.src))
+    }
+
+    fn text_at(&self, offset: usize) -> &'a str {
+        self.peek_at(offset).map_or("", |t| t.text(self.src))
+    }
+
+    fn at_kind(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|t| t.kind == kind)
+    }
+
+    fn at(&self, kind: TokenKind, text: &str) -> bool {
+        self.at_kind(kind) && self.text() == text
+    }
+
+    fn at_kw(&self, keyword: &str) -> bool {
+        self.at(TokenKind::Keyword, keyword)
+    }
+
+    fn at_op(&self, op: &str) -> bool {
+        self.at(TokenKind::Operator, op)
+    }
+
+    fn at_punct(&self, punct: &str) -> bool {
+        self.at(TokenKind::Punctuation, punct)
+    }
+
+    fn at_eof(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos];
+        self.pos += 1;
+        self.prev_end = token.end;
+        token
+    }
+
+    fn eat_kw(&mut self, keyword: &str) -> bool {
+        let found = self.at_kw(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.at_op(op);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.at_punct(punct);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_kind(&mut self, kind: TokenKind) -> bool {
+        let found = self.at_kind(kind);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.at_kind(TokenKind::Newline) {
+            self.advance();
+        }
+    }
+
+    fn skip_terms(&mut self) {
+        while self.at_kind(TokenKind::Newline) || self.at_punct(";") {
+            self.advance();
+        }
+    }
+
+    /// Whether whitespace separates the current token from the previous one.
+    fn space_before(&self) -> bool {
+        match (self.pos.checked_sub(1).map(|i| self.tokens[i]), self.peek()) {
+            (Some(prev), Some(cur)) => cur.start > prev.end,
+            _ => false,
+        }
+    }
+
+    /// Whether whitespace separates the current token from the next one.
+    fn space_after(&self) -> bool {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(cur), Some(next)) => next.start > cur.end,
+            _ => true,
+        }
+    }
+
+    fn adjacent(&self) -> bool {
+        !self.at_eof() && !self.space_before()
+    }
+
+    fn current_start(&self) -> usize {
+        self.peek().map_or(self.src.len(), |t| t.start)
+    }
+
+    fn node(&self, kind: NodeKind, start: usize) -> Node {
+        Node::new(kind, start, self.prev_end.max(start))
+    }
+
+    fn error_node(&mut self) -> Node {
+        if self.at_eof() {
+            let end = self.src.len();
+            return Node::new(NodeKind::Error, end, end);
+        }
+        let token = self.advance();
+        Node::new(NodeKind::Error, token.start, token.end)
+    }
+
+    fn with_do_blocks<T>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let saved = std::mem::replace(&mut self.no_do_block, !allowed);
+        let result = f(self);
+        self.no_do_block = saved;
+        result
+    }
+
+    // ------------------------------------------------------------------
+    // Local variable scopes
+    // ------------------------------------------------------------------
+
+    fn push_scope(&mut self, inherits: bool) {
+        self.scopes.push(Scope {
+            locals: HashSet::new(),
+            inherits,
+        });
+    }
+
+    fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.locals.insert(name.to_string());
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if scope.locals.contains(name) {
+                return true;
+            }
+            if !scope.inherits {
+                break;
+            }
+        }
+        false
+    }
+
+    // ------------------------------------------------------------------
+    // Statements
+    // ------------------------------------------------------------------
+
+    fn at_statements_end(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(t) => match t.kind {
+                TokenKind::Keyword => BODY_TERMINATORS.contains(&t.text(self.src)),
+                TokenKind::Punctuation => matches!(t.text(self.src), ")" | "]" | "}"),
+                TokenKind::InterpolationEnd | TokenKind::HeredocEnd => true,
+                _ => false,
+            },
+        }
+    }
+
+    fn parse_statements(&mut self) -> Vec<Node> {
+        let mut statements = Vec::new();
+        loop {
+            self.skip_terms();
+            if self.at_statements_end() {
+                break;
+            }
+            let before = self.pos;
+            let statement = self.parse_statement();
+            statements.push(statement);
+            if self.pos == before {
+                statements.push(self.error_node());
+            }
+        }
+        statements
+    }
+
+    /// Wraps a statement list into an optional body node.
+    fn wrap(mut statements: Vec<Node>) -> Option<Box<Node>> {
+        match statements.len() {
+            0 => None,
+            1 => statements.pop().map(Box::new),
+            _ => {
+                let start = statements[0].start;
+                let end = statements[statements.len() - 1].end;
+                Some(Box::new(Node::new(NodeKind::Statements(statements), start, end)))
+            }
+        }
+    }
+
+    fn parse_statement(&mut self) -> Node {
+        if self.depth > MAX_DEPTH {
+            return self.error_node();
+        }
+        self.depth += 1;
+        let start = self.current_start();
+        let mut node = self.parse_expression_statement();
+        loop {
+            if self.at_kw("if") || self.at_kw("unless") {
+                let keyword = if self.advance().text(self.src) == "if" {
+                    IfKeyword::If
+                } else {
+                    IfKeyword::Unless
+                };
+                let condition = self.parse_expression_statement();
+                node = self.node(
+                    NodeKind::If {
+                        condition: Box::new(condition),
+                        then_body: Some(Box::new(node)),
+                        else_body: None,
+                        keyword,
+                        modifier: true,
+                    },
+                    start,
+                );
+            } else if self.at_kw("while") || self.at_kw("until") {
+                let until = self.advance().text(self.src) == "until";
+                let condition = self.parse_expression_statement();
+                node = self.node(
+                    NodeKind::While {
+                        condition: Box::new(condition),
+                        body: Some(Box::new(node)),
+                        until,
+                        modifier: true,
+                    },
+                    start,
+                );
+            } else if self.at_kw("rescue") {
+                node = self.parse_rescue_modifier(node);
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_rescue_modifier(&mut self, body: Node) -> Node {
+        let start = body.start;
+        let rescue_start = self.advance().start;
+        let value = self.parse_expression_statement();
+        let handler = self.node(
+            NodeKind::RescueBody {
+                exceptions: Vec::new(),
+                variable: None,
+                body: Some(Box::new(value)),
+            },
+            rescue_start,
+        );
+        self.node(
+            NodeKind::Rescue {
+                body: Some(Box::new(body)),
+                handlers: vec![handler],
+                else_body: None,
+            },
+            start,
+        )
+    }
+
+    /// Parses `and`/`or` chains.
+    fn parse_expression_statement(&mut self) -> Node {
+        let start = self.current_start();
+        let mut left = self.parse_not();
+        while self.at_kw("and") || self.at_kw("or") {
+            let is_and = self.advance().text(self.src) == "and";
+            self.skip_newlines();
+            let right = Box::new(self.parse_not());
+            let left_box = Box::new(left);
+            left = self.node(
+                if is_and {
+                    NodeKind::And {
+                        left: left_box,
+                        right,
+                    }
+                } else {
+                    NodeKind::Or {
+                        left: left_box,
+                        right,
+                    }
+                },
+                start,
+            );
+        }
+        left
+    }
+
+    fn parse_not(&mut self) -> Node {
+        let start = self.current_start();
+        if self.eat_kw("not") {
+            let operand = self.parse_not();
+            return self.node(unary_send(operand, "!"), start);
+        }
+        let value = self.parse_expression_with_masgn();
+        if self.at_op("=>") || self.at_kw("in") {
+            let raises = self.advance().text(self.src) == "=>";
+            let pattern = self.parse_pattern_top();
+            return self.node(
+                NodeKind::MatchPattern {
+                    value: Box::new(value),
+                    pattern: Box::new(pattern),
+                    raises,
+                },
+                start,
+            );
+        }
+        value
+    }
+
+    fn parse_expression_with_masgn(&mut self) -> Node {
+        let start = self.current_start();
+        let first = self.parse_expr();
+        if !self.at_punct(",") || !is_assignable(&first) {
+            return first;
+        }
+        let mut targets = vec![self.assignment_target(first)];
+        while self.eat_punct(",") {
+            if self.at_op("=") {
+                break;
+            }
+            let item = self.parse_mlhs_item();
+            targets.push(item);
+        }
+        let mlhs = self.node(NodeKind::Mlhs(targets), start);
+        if !self.eat_op("=") {
+            return mlhs;
+        }
+        self.skip_newlines();
+        let value = self.parse_masgn_value();
+        self.node(
+            NodeKind::Masgn {
+                targets: Box::new(mlhs),
+                value: Box::new(value),
+            },
+            start,
+        )
+    }
+
+    fn parse_mlhs_item(&mut self) -> Node {
+        let start = self.current_start();
+        if self.eat_punct("(") {
+            let mut items = Vec::new();
+            loop {
+                if self.at_punct(")") || self.at_eof() {
+                    break;
+                }
+                let before = self.pos;
+                items.push(self.parse_mlhs_item());
+                if self.pos == before || !self.eat_punct(",") {
+                    break;
+                }
+            }
+            self.eat_punct(")");
+            return self.node(NodeKind::Mlhs(items), start);
+        }
+        if self.eat_op("*") {
+            let operand = if self.at_punct(",") || self.at_op("=") || self.at_punct(")") {
+                None
+            } else {
+                let target = self.parse_primary();
+                Some(Box::new(self.assignment_target(target)))
+            };
+            return self.node(NodeKind::Splat(operand), start);
+        }
+        let target = self.parse_primary();
+        self.assignment_target(target)
+    }
+
+    fn parse_masgn_value(&mut self) -> Node {
+        let start = self.current_start();
+        let first = self.parse_argument_value();
+        if !self.at_punct(",") {
+            return first;
+        }
+        let mut elements = vec![first];
+        while self.eat_punct(",") {
+            self.skip_newlines();
+            elements.push(self.parse_argument_value());
+        }
+        self.node(NodeKind::Array { elements }, start)
+    }
+
+    /// Parses an expression that may start with a splat.
+    fn parse_argument_value(&mut self) -> Node {
+        let start = self.current_start();
+        if self.eat_op("*") {
+            let operand = self.parse_ternary();
+            return self.node(NodeKind::Splat(Some(Box::new(operand))), start);
+        }
+        self.parse_expr()
+    }
+
+    /// Converts an expression into an assignment target, declaring locals.
+    fn assignment_target(&mut self, node: Node) -> Node {
+        let Node { kind, start, end } = node;
+        let kind = match kind {
+            NodeKind::Send {
+                receiver: None,
+                method,
+                arguments,
+                parenthesized: false,
+                ..
+            } if arguments.is_empty() && is_identifier_name(&method) => {
+                self.declare(&method);
+                NodeKind::Lvar(method)
+            }
+            NodeKind::Send {
+                receiver,
+                method,
+                arguments,
+                safe_navigation,
+                parenthesized,
+            } if !parenthesized && (method == "[]" || is_identifier_name(&method)) => {
+                NodeKind::Send {
+                    receiver,
+                    method: format!("{}=", method),
+                    arguments,
+                    safe_navigation,
+                    parenthesized,
+                }
+            }
+            NodeKind::Lvar(name) => {
+                self.declare(&name);
+                NodeKind::Lvar(name)
+            }
+            NodeKind::Splat(Some(inner)) => NodeKind::Splat(Some(Box::new(self.assignment_target(*inner)))),
+            NodeKind::Parens(items) | NodeKind::Mlhs(items) => NodeKind::Mlhs(
+                items
+                    .into_iter()
+                    .map(|item| self.assignment_target(item))
+                    .collect(),
+            ),
+            other => other,
+        };
+        Node::new(kind, start, end)
+    }
+
+    // ------------------------------------------------------------------
+    // Expressions
+    // ------------------------------------------------------------------
+
+    /// Parses an expression including assignment.
+    fn parse_expr(&mut self) -> Node {
+        if self.depth > MAX_DEPTH {
+            return self.error_node();
+        }
+        self.depth += 1;
+        let node = self.parse_assignment();
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_assignment(&mut self) -> Node {
+        let start = self.current_start();
+        let left = self.parse_ternary();
+        if self.at_op("=") && is_single_assignable(&left) {
+            self.advance();
+            self.skip_newlines();
+            let target = self.assignment_target(left);
+            let mut value = self.parse_argument_value();
+            if self.at_kw("rescue") {
+                value = self.parse_rescue_modifier(value);
+            }
+            return match target.kind {
+                NodeKind::Send {
+                    receiver,
+                    method,
+                    mut arguments,
+                    safe_navigation,
+                    ..
+                } => {
+                    arguments.push(value);
+                    self.node(
+                        NodeKind::Send {
+                            receiver,
+                            method,
+                            arguments,
+                            safe_navigation,
+                            parenthesized: false,
+                        },
+                        start,
+                    )
+                }
+                _ => self.node(
+                    NodeKind::Assign {
+                        target: Box::new(target),
+                        value: Box::new(value),
+                    },
+                    start,
+                ),
+            };
+        }
+        if self.at_kind(TokenKind::Operator)
+            && ASSIGNMENT_OPERATORS.contains(&self.text())
+            && is_single_assignable(&left)
+        {
+            let op = self.advance().text(self.src);
+            self.skip_newlines();
+            let target = match left.kind {
+                NodeKind::Send { .. } if left.receiver().is_some() => left,
+                _ => self.assignment_target(left),
+            };
+            let mut value = self.parse_expr();
+            if self.at_kw("rescue") {
+                value = self.parse_rescue_modifier(value);
+            }
+            return self.node(
+                NodeKind::OpAssign {
+                    target: Box::new(target),
+                    operator: op[..op.len() - 1].to_string(),
+                    value: Box::new(value),
+                },
+                start,
+            );
+        }
+        left
+    }
+
+    fn parse_ternary(&mut self) -> Node {
+        let start = self.current_start();
+        let condition = self.parse_binary(PREC_RANGE);
+        if !self.at_op("?") {
+            return condition;
+        }
+        self.advance();
+        self.skip_newlines();
+        let then_branch = self.parse_ternary();
+        self.skip_newlines();
+        self.eat_op(":");
+        self.skip_newlines();
+        let else_branch = self.parse_ternary();
+        self.node(
+            NodeKind::If {
+                condition: Box::new(condition),
+                then_body: Some(Box::new(then_branch)),
+                else_body: Some(Box::new(else_branch)),
+                keyword: IfKeyword::Ternary,
+                modifier: false,
+            },
+            start,
+        )
+    }
+
+    fn binary_precedence(&self) -> Option<(u8, bool)> {
+        if !self.at_kind(TokenKind::Operator) {
+            return None;
+        }
+        let prec = match self.text() {
+            ".." | "..." => (PREC_RANGE, false),
+            "||" => (PREC_OR, false),
+            "&&" => (PREC_AND, false),
+            "==" | "!=" | "=~" | "!~" | "===" | "<=>" => (PREC_EQUALITY, false),
+            "<" | "<=" | ">" | ">=" => (PREC_COMPARISON, false),
+            "|" if self.in_pattern => return None,
+            "|" | "^" => (PREC_BIT_OR, false),
+            "&" => (PREC_BIT_AND, false),
+            "<<" | ">>" => (PREC_SHIFT, false),
+            "+" | "-" => (PREC_ADDITIVE, false),
+            "*" | "/" | "%" => (PREC_MULTIPLICATIVE, false),
+            "**" => (PREC_POWER, true),
+            _ => return None,
+        };
+        Some(prec)
+    }
+
+    fn parse_binary(&mut self, min_prec: u8) -> Node {
+        let start = self.current_start();
+        let mut left = self.parse_unary();
+        while let Some((prec, right_assoc)) = self.binary_precedence() {
+            if prec < min_prec {
+                break;
+            }
+            let op = self.advance().text(self.src);
+            self.skip_newlines();
+            if prec == PREC_RANGE {
+                let to = if self.can_start_expression() {
+                    Some(Box::new(self.parse_binary(PREC_RANGE + 1)))
+                } else {
+                    None
+                };
+                left = self.node(
+                    NodeKind::Range {
+                        from: Some(Box::new(left)),
+                        to,
+                        exclusive: op == "...",
+                    },
+                    start,
+                );
+                continue;
+            }
+            let next_min = if right_assoc { prec } else { prec + 1 };
+            let right = self.parse_binary(next_min);
+            let (left_box, right_box) = (Box::new(left), Box::new(right));
+            left = self.node(
+                match op {
+                    "&&" => NodeKind::And {
+                        left: left_box,
+                        right: right_box,
+                    },
+                    "||" => NodeKind::Or {
+                        left: left_box,
+                        right: right_box,
+                    },
+                    _ => NodeKind::Send {
+                        receiver: Some(left_box),
+                        method: op.to_string(),
+                        arguments: vec![*right_box],
+                        safe_navigation: false,
+                        parenthesized: false,
+                    },
+                },
+                start,
+            );
+        }
+        left
+    }
+
+    fn parse_unary(&mut self) -> Node {
+        if self.depth > MAX_DEPTH {
+            return self.error_node();
+        }
+        self.depth += 1;
+        let node = self.parse_unary_inner();
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_unary_inner(&mut self) -> Node {
+        let start = self.current_start();
+        if self.at_kind(TokenKind::Operator) {
+            match self.text() {
+                "!" | "~" => {
+                    let op = self.advance().text(self.src);
+                    let operand = self.parse_unary();
+                    return self.node(unary_send(operand, op), start);
+                }
+                "-" | "+" => {
+                    let numeric = self.peek_at(1).is_some_and(|t| {
+                        matches!(t.kind, TokenKind::Integer | TokenKind::Float)
+                            && !self.space_after()
+                    });
+                    if numeric {
+                        self.advance();
+                        let number = self.advance();
+                        let kind = if number.kind == TokenKind::Integer {
+                            NodeKind::Int
+                        } else {
+                            NodeKind::Float
+                        };
+                        let literal = self.node(kind, start);
+                        return self.parse_postfix(literal);
+                    }
+                    let op = self.advance().text(self.src);
+                    let operand = self.parse_binary(PREC_POWER);
+                    return self.node(unary_send(operand, &format!("{}@", op)), start);
+                }
+                "*" | "**" | "&" => {
+                    let op = self.advance().text(self.src);
+                    let operand = if self.can_start_expression() {
+                        Some(Box::new(self.parse_unary()))
+                    } else {
+                        None
+                    };
+                    let kind = match op {
+                        "*" => NodeKind::Splat(operand),
+                        "**" => NodeKind::DoubleSplat(operand),
+                        _ => NodeKind::BlockPass(operand),
+                    };
+                    return self.node(kind, start);
+                }
+                ".." | "..." => {
+                    let exclusive = self.advance().text(self.src) == "...";
+                    let to = self.parse_binary(PREC_RANGE + 1);
+                    return self.node(
+                        NodeKind::Range {
+                            from: None,
+                            to: Some(Box::new(to)),
+                            exclusive,
+                        },
+                        start,
+                    );
+                }
+                _ => {}
+            }
+        }
+        if self.at_kw("defined?") {
+            self.advance();
+            let operand = if self.at_punct("(") && self.adjacent() {
+                self.advance();
+                self.skip_newlines();
+                let inner = self.parse_expression_statement();
+                self.skip_newlines();
+                self.eat_punct(")");
+                inner
+            } else {
+                self.parse_binary(PREC_EQUALITY)
+            };
+            let node = self.node(NodeKind::Defined(Box::new(operand)), start);
+            return self.parse_postfix(node);
+        }
+        if self.at_kw("not") {
+            self.advance();
+            let operand = self.parse_expr();
+            return self.node(unary_send(operand, "!"), start);
+        }
+        self.parse_primary()
+    }
+
+    /// Whether the current token can begin an expression.
+    fn can_start_expression(&self) -> bool {
+        let Some(token) = self.peek() else {
+            return false;
+        };
+        let text = token.text(self.src);
+        match token.kind {
+            TokenKind::Newline
+            | TokenKind::InterpolationEnd
+            | TokenKind::HeredocEnd
+            | TokenKind::StringEnd
+            | TokenKind::RegexpEnd
+            | TokenKind::StringContent
+            | TokenKind::InterpolationBegin
+            | TokenKind::Unknown => false,
+            TokenKind::Keyword => !TRAILING_KEYWORDS.contains(&text),
+            TokenKind::Punctuation => matches!(text, "(" | "[" | "{"),
+            TokenKind::Operator => matches!(
+                text,
+                "!" | "-" | "+" | "*" | "**" | "&" | "::" | "->" | "~" | ".." | "..."
+            ),
+            _ => true,
+        }
+    }
+
+    /// Whether the current token starts the first argument of a call
+    /// without parentheses, e.g. the `1` in `puts 1`.
+    fn can_start_command_argument(&self) -> bool {
+        let Some(token) = self.peek() else {
+            return false;
+        };
+        if !self.space_before() {
+            return false;
+        }
+        let text = token.text(self.src);
+        match token.kind {
+            TokenKind::Integer
+            | TokenKind::Float
+            | TokenKind::Character
+            | TokenKind::StringBegin
+            | TokenKind::XStringBegin
+            | TokenKind::SymbolBegin
+            | TokenKind::WordsBegin
+            | TokenKind::SymbolsBegin
+            | TokenKind::RegexpBegin
+            | TokenKind::HeredocBegin
+            | TokenKind::Symbol
+            | TokenKind::Identifier
+            | TokenKind::Constant
+            | TokenKind::InstanceVariable
+            | TokenKind::ClassVariable
+            | TokenKind::GlobalVariable
+            | TokenKind::Label => true,
+            TokenKind::Keyword => {
+                ARGUMENT_KEYWORDS.contains(&text)
+                    && !matches!(text, "if" | "unless" | "while" | "until")
+            }
+            TokenKind::Operator => match text {
+                "->" => true,
+                "::" | "!" | "-" | "+" | "*" | "**" | "&" | "~" | ":" => !self.space_after(),
+                _ => false,
+            },
+            TokenKind::Punctuation => matches!(text, "[" | "("),
+            _ => false,
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // Primary expressions
+    // ------------------------------------------------------------------
+
+    fn parse_primary(&mut self) -> Node {
+        if self.depth > MAX_DEPTH {
+            return self.error_node();
+        }
+        self.depth += 1;
+        let base = self.parse_primary_base();
+        let node = self.parse_postfix(base);
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_primary_base(&mut self) -> Node {
+        let Some(token) = self.peek() else {
+            return self.error_node();
+        };
+        let start = token.start;
+        let text = token.text(self.src);
+        match token.kind {
+            TokenKind::Integer => {
+                self.advance();
+                self.node(NodeKind::Int, start)
+            }
+            TokenKind::Float => {
+                self.advance();
+                self.node(NodeKind::Float, start)
+            }
+            TokenKind::Character => {
+                self.advance();
+                self.node(NodeKind::Char, start)
+            }
+            TokenKind::StringBegin
+            | TokenKind::XStringBegin
+            | TokenKind::SymbolBegin
+            | TokenKind::RegexpBegin
+            | TokenKind::WordsBegin
+            | TokenKind::SymbolsBegin => self.parse_string_like(),
+            TokenKind::HeredocBegin => self.parse_heredoc(),
+            TokenKind::Symbol => {
+                self.advance();
+                self.node(
+                    NodeKind::Sym {
+                        name: text[1..].to_string(),
+                    },
+                    start,
+                )
+            }
+            TokenKind::InstanceVariable => {
+                self.advance();
+                self.node(NodeKind::Ivar(text.to_string()), start)
+            }
+            TokenKind::ClassVariable => {
+                self.advance();
+                self.node(NodeKind::Cvar(text.to_string()), start)
+            }
+            TokenKind::GlobalVariable => {
+                self.advance();
+                self.node(NodeKind::Gvar(text.to_string()), start)
+            }
+            TokenKind::Constant => {
+                self.advance();
+                if self.at_punct("(") && self.adjacent() {
+                    let arguments = self.parse_paren_arguments();
+                    return self.node(send(None, text, arguments, true), start);
+                }
+                self.node(
+                    NodeKind::Const {
+                        scope: None,
+                        name: text.to_string(),
+                    },
+                    start,
+                )
+            }
+            TokenKind::Identifier => self.parse_identifier(),
+            TokenKind::Label => {
+                let pairs = self.parse_bare_hash();
+                self.node(
+                    NodeKind::Hash {
+                        pairs,
+                        braces: false,
+                    },
+                    start,
+                )
+            }
+            TokenKind::Keyword => self.parse_keyword(),
+            TokenKind::Punctuation => match text {
+                "(" => {
+                    self.advance();
+                    let mut statements = self.with_do_blocks(true, |p| p.parse_statements());
+                    self.skip_terms();
+                    self.eat_punct(")");
+                    // `(a, b)` on the left of a multiple assignment.
+                    if statements.len() == 1 && matches!(statements[0].kind, NodeKind::Mlhs(_)) {
+                        return statements.remove(0);
+                    }
+                    self.node(NodeKind::Parens(statements), start)
+                }
+                "[" => {
+                    self.advance();
+                    let elements = self.with_do_blocks(true, |p| p.parse_call_arguments("]"));
+                    self.eat_punct("]");
+                    self.node(NodeKind::Array { elements }, start)
+                }
+                "{" => self.parse_hash_literal(),
+                _ => self.error_node(),
+            },
+            TokenKind::Operator => match text {
+                "->" => self.parse_lambda(),
+                "::" => {
+                    self.advance();
+                    let cbase = self.node(NodeKind::Cbase, start);
+                    if self.at_kind(TokenKind::Constant) {
+                        let name = self.advance().text(self.src).to_string();
+                        return self.node(
+                            NodeKind::Const {
+                                scope: Some(Box::new(cbase)),
+                                name,
+                            },
+                            start,
+                        );
+                    }
+                    cbase
+                }
+                _ => self.error_node(),
+            },
+            _ => self.error_node(),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Node {
+        let token = self.advance();
+        let start = token.start;
+        let name = token.text(self.src);
+        if self.at_punct("(") && self.adjacent() {
+            let arguments = self.parse_paren_arguments();
+            return self.node(send(None, name, arguments, true), start);
+        }
+        if self.is_local(name) {
+            return self.node(NodeKind::Lvar(name.to_string()), start);
+        }
+        if self.can_start_command_argument() {
+            let arguments = self.parse_command_arguments();
+            return self.node(send(None, name, arguments, false), start);
+        }
+        self.node(send(None, name, Vec::new(), false), start)
+    }
+
+    fn parse_postfix(&mut self, mut node: Node) -> Node {
+        loop {
+            let start = node.start;
+            let leading_dot = self.at_kind(TokenKind::Newline) && {
+                let mut i = self.pos;
+                while self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Newline) {
+                    i += 1;
+                }
+                self.tokens.get(i).is_some_and(|t| {
+                    t.kind == TokenKind::Operator && matches!(t.text(self.src), "." | "&.")
+                })
+            };
+            if leading_dot {
+                self.skip_newlines();
+            }
+            if self.at_op(".") || self.at_op("&.") {
+                let safe_navigation = self.advance().text(self.src) == "&.";
+                self.skip_newlines();
+                node = self.parse_method_call(node, start, safe_navigation);
+            } else if self.at_op("::") && self.adjacent() {
+                self.advance();
+                if self.at_kind(TokenKind::Constant)
+                    && !(self.peek_at(1).is_some_and(|t| {
+                        t.kind == TokenKind::Punctuation && t.text(self.src) == "("
+                    }) && self.peek_at(1).map(|t| t.start) == self.peek().map(|t| t.end))
+                {
+                    let name = self.advance().text(self.src).to_string();
+                    node = self.node(
+                        NodeKind::Const {
+                            scope: Some(Box::new(node)),
+                            name,
+                        },
+                        start,
+                    );
+                } else {
+                    node = self.parse_method_call(node, start, false);
+                }
+            } else if self.at_punct("[") && (!self.space_before() || !is_command_call(&node)) {
+                self.advance();
+                let arguments = self.with_do_blocks(true, |p| p.parse_call_arguments("]"));
+                self.eat_punct("]");
+                node = self.node(send(Some(node), "[]", arguments, false), start);
+            } else if self.at_punct("{") && takes_block(&node) {
+                node = self.parse_brace_block(node);
+            } else if self.at_kw("do") && !self.no_do_block && takes_block(&node) {
+                node = self.parse_do_block(node);
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn parse_method_call(&mut self, receiver: Node, start: usize, safe_navigation: bool) -> Node {
+        let Some(token) = self.peek() else {
+            return receiver;
+        };
+        let method = match token.kind {
+            TokenKind::Identifier | TokenKind::Constant | TokenKind::Keyword => {
+                self.advance();
+                token.text(self.src).to_string()
+            }
+            TokenKind::Label => {
+                // `a.b:` only appears in malformed code; keep the name.
+                self.advance();
+                token.text(self.src).trim_end_matches(':').to_string()
+            }
+            TokenKind::Operator => {
+                self.advance();
+                token.text(self.src).to_string()
+            }
+            TokenKind::Punctuation if token.text(self.src) == "(" => "call".to_string(),
+            _ => return receiver,
+        };
+        let (arguments, parenthesized) = if self.at_punct("(") && self.adjacent() {
+            (self.parse_paren_arguments(), true)
+        } else if self.can_start_command_argument() {
+            (self.parse_command_arguments(), false)
+        } else {
+            (Vec::new(), false)
+        };
+        self.node(
+            NodeKind::Send {
+                receiver: Some(Box::new(receiver)),
+                method,
+                arguments,
+                safe_navigation,
+                parenthesized,
+            },
+            start,
+        )
+    }
+
+    fn parse_paren_arguments(&mut self) -> Vec<Node> {
+        self.advance();
+        let arguments = self.with_do_blocks(true, |p| p.parse_call_arguments(")"));
+        self.eat_punct(")");
+        arguments
+    }
+
+    fn parse_command_arguments(&mut self) -> Vec<Node> {
+        self.with_do_blocks(false, |p| {
+            let mut arguments = Vec::new();
+            let mut pairs = Vec::new();
+            loop {
+                let before = p.pos;
+                p.parse_argument(&mut arguments, &mut pairs);
+                if p.pos == before || !p.eat_punct(",") {
+                    break;
+                }
+                p.skip_newlines();
+            }
+            flush_pairs(&mut arguments, &mut pairs);
+            arguments
+        })
+    }
+
+    /// Parses comma-separated arguments up to (not including) `closer`.
+    fn parse_call_arguments(&mut self, closer: &str) -> Vec<Node> {
+        let mut arguments = Vec::new();
+        let mut pairs = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.at_punct(closer) || self.at_eof() {
+                break;
+            }
+            let before = self.pos;
+            self.parse_argument(&mut arguments, &mut pairs);
+            self.skip_newlines();
+            if self.pos == before {
+                arguments.push(self.error_node());
+            }
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+        self.skip_newlines();
+        flush_pairs(&mut arguments, &mut pairs);
+        arguments
+    }
+
+    fn parse_argument(&mut self, arguments: &mut Vec<Node>, pairs: &mut Vec<Node>) {
+        let start = self.current_start();
+        if self.at_kind(TokenKind::Label) {
+            pairs.push(self.parse_label_pair());
+            return;
+        }
+        if self.at_op("**") {
+            self.advance();
+            let operand = self.optional_operand();
+            pairs.push(self.node(NodeKind::DoubleSplat(operand), start));
+            return;
+        }
+        if self.at_op("*") || self.at_op("&") {
+            let splat = self.advance().text(self.src) == "*";
+            let operand = self.optional_operand();
+            flush_pairs(arguments, pairs);
+            arguments.push(self.node(
+                if splat {
+                    NodeKind::Splat(operand)
+                } else {
+                    NodeKind::BlockPass(operand)
+                },
+                start,
+            ));
+            return;
+        }
+        if self.at_op("...") && matches!(self.text_at(1), ")" | ",") {
+            self.advance();
+            arguments.push(self.node(
+                NodeKind::Param {
+                    kind: ParamKind::Forward,
+                    name: None,
+                    default: None,
+                },
+                start,
+            ));
+            return;
+        }
+        let value = self.parse_expr();
+        if self.at_op("=>") {
+            self.advance();
+            self.skip_newlines();
+            let pair_value = self.parse_expr();
+            pairs.push(self.node(
+                NodeKind::Pair {
+                    key: Box::new(value),
+                    value: Box::new(pair_value),
+                },
+                start,
+            ));
+            return;
+        }
+        if is_string_like(&value) && self.at_op(":") && self.adjacent() {
+            self.advance();
+            self.skip_newlines();
+            let pair_value = self.parse_expr();
+            pairs.push(self.node(
+                NodeKind::Pair {
+                    key: Box::new(value),
+                    value: Box::new(pair_value),
+                },
+                start,
+            ));
+            return;
+        }
+        flush_pairs(arguments, pairs);
+        arguments.push(value);
+    }
+
+    fn optional_operand(&mut self) -> Option<Box<Node>> {
+        if self.at_punct(",") || self.at_punct(")") || self.at_punct("]") || !self.can_start_expression()
+        {
+            None
+        } else {
+            Some(Box::new(self.parse_ternary()))
+        }
+    }
+
+    /// Parses `key: value`, including the value-omission form `key:`.
+    fn parse_label_pair(&mut self) -> Node {
+        let label = self.advance();
+        let start = label.start;
+        let name = label.text(self.src).trim_end_matches(':').to_string();
+        let key = Node::new(
+            NodeKind::Sym { name: name.clone() },
+            label.start,
+            label.end,
+        );
+        self.skip_newlines_if_continued();
+        let value = if self.at_punct(",")
+            || self.at_punct(")")
+            || self.at_punct("}")
+            || self.at_punct("]")
+            || self.at_kind(TokenKind::Newline)
+            || self.at_eof()
+        {
+            let kind = if self.is_local(&name) {
+                NodeKind::Lvar(name)
+            } else {
+                send(None, &name, Vec::new(), false)
+            };
+            Node::new(kind, label.start, label.end - 1)
+        } else {
+            self.parse_expr()
+        };
+        self.node(
+            NodeKind::Pair {
+                key: Box::new(key),
+                value: Box::new(value),
+            },
+            start,
+        )
+    }
+
+    /// Allows the value of `key:` to start on the next line, as in
+    /// `foo(key:\n  value)`.
+    fn skip_newlines_if_continued(&mut self) {
+        if !self.at_kind(TokenKind::Newline) {
+            return;
+        }
+        let mut i = self.pos;
+        while self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Newline) {
+            i += 1;
+        }
+        let continues = self.tokens.get(i).is_some_and(|t| match t.kind {
+            TokenKind::Punctuation => !matches!(t.text(self.src), ")" | "}" | "]"),
+            TokenKind::Keyword => t.text(self.src) != "end",
+            TokenKind::Label => false,
+            _ => true,
+        });
+        // Only continue inside an open bracket; bare command arguments end
+        // at the newline.
+        if continues && !self.no_do_block {
+            self.skip_newlines();
+        }
+    }
+
+    fn parse_bare_hash(&mut self) -> Vec<Node> {
+        let mut pairs = Vec::new();
+        while self.at_kind(TokenKind::Label) {
+            pairs.push(self.parse_label_pair());
+            if !self.eat_punct(",") {
+                break;
+            }
+            self.skip_newlines();
+        }
+        pairs
+    }
+
+    fn parse_hash_literal(&mut self) -> Node {
+        let start = self.advance().start;
+        let pairs = self.with_do_blocks(true, |p| {
+            let mut pairs = Vec::new();
+            loop {
+                p.skip_newlines();
+                if p.at_punct("}") || p.at_eof() {
+                    break;
+                }
+                let before = p.pos;
+                let mut arguments = Vec::new();
+                p.parse_argument(&mut arguments, &mut pairs);
+                pairs.extend(arguments);
+                p.skip_newlines();
+                if p.pos == before {
+                    pairs.push(p.error_node());
+                }
+                if !p.eat_punct(",") {
+                    break;
+                }
+            }
+            p.skip_newlines();
+            pairs
+        });
+        self.eat_punct("}");
+        self.node(NodeKind::Hash { pairs, braces: true }, start)
+    }
+
+    // ------------------------------------------------------------------
+    // String-like literals
+    // ------------------------------------------------------------------
+
+    /// Parses literal parts up to and including the closing delimiter.
+    /// Returns the parts and the closing token, if any.
+    fn parse_literal_parts(&mut self) -> (Vec<Node>, Option<Token>) {
+        let mut parts = Vec::new();
+        loop {
+            let Some(token) = self.peek() else {
+                return (parts, None);
+            };
+            match token.kind {
+                TokenKind::StringContent => {
+                    self.advance();
+                    parts.push(Node::new(NodeKind::StrPart, token.start, token.end));
+                }
+                TokenKind::InterpolationBegin => {
+                    self.advance();
+                    let statements = self.with_do_blocks(true, |p| p.parse_statements());
+                    self.eat_kind(TokenKind::InterpolationEnd);
+                    parts.push(self.node(NodeKind::Interpolation(statements), token.start));
+                }
+                TokenKind::StringEnd | TokenKind::RegexpEnd | TokenKind::HeredocEnd => {
+                    self.advance();
+                    return (parts, Some(token));
+                }
+                _ => return (parts, None),
+            }
+        }
+    }
+
+    fn parse_string_like(&mut self) -> Node {
+        let begin = self.advance();
+        let start = begin.start;
+        let (parts, end) = self.parse_literal_parts();
+        match begin.kind {
+            TokenKind::StringBegin => {
+                let mut node = self.node(
+                    NodeKind::Str {
+                        parts,
+                        heredoc: false,
+                    },
+                    start,
+                );
+                // Adjacent literals concatenate: "a" "b".
+                while self.at_kind(TokenKind::StringBegin) && self.space_before() {
+                    let next = self.parse_string_like();
+                    node = self.node(
+                        NodeKind::Str {
+                            parts: vec![node, next],
+                            heredoc: false,
+                        },
+                        start,
+                    );
+                }
+                node
+            }
+            TokenKind::SymbolBegin => {
+                if parts.iter().all(|p| p.kind == NodeKind::StrPart) {
+                    let name = parts.iter().map(|p| p.source(self.src)).collect();
+                    self.node(NodeKind::Sym { name }, start)
+                } else {
+                    self.node(NodeKind::Dsym { parts }, start)
+                }
+            }
+            TokenKind::XStringBegin => self.node(NodeKind::Xstr { parts }, start),
+            TokenKind::RegexpBegin => {
+                let flags = end
+                    .map(|t| t.text(self.src).get(1..).unwrap_or("").to_string())
+                    .unwrap_or_default();
+                self.node(NodeKind::Regexp { parts, flags }, start)
+            }
+            _ => {
+                let symbols = begin.kind == TokenKind::SymbolsBegin;
+                let elements = self.split_words(parts, symbols);
+                self.node(NodeKind::Array { elements }, start)
+            }
+        }
+    }
+
+    /// Splits the parts of a `%w`/`%i` literal into elements on whitespace.
+    fn split_words(&self, parts: Vec<Node>, symbols: bool) -> Vec<Node> {
+        let mut elements = Vec::new();
+        let mut current: Vec<Node> = Vec::new();
+        let flush = |current: &mut Vec<Node>, elements: &mut Vec<Node>| {
+            if current.is_empty() {
+                return;
+            }
+            let start = current[0].start;
+            let end = current[current.len() - 1].end;
+            let parts = std::mem::take(current);
+            let kind = if symbols && parts.iter().all(|p| p.kind == NodeKind::StrPart) {
+                NodeKind::Sym {
+                    name: self.src[start..end].to_string(),
+                }
+            } else if symbols {
+                NodeKind::Dsym { parts }
+            } else {
+                NodeKind::Str {
+                    parts,
+                    heredoc: false,
+                }
+            };
+            elements.push(Node::new(kind, start, end));
+        };
+        for part in parts {
+            if part.kind != NodeKind::StrPart {
+                current.push(part);
+                continue;
+            }
+            let text = part.source(self.src);
+            let mut word_start: Option<usize> = None;
+            let mut escaped = false;
+            for (i, ch) in text.char_indices() {
+                let offset = part.start + i;
+                if ch.is_whitespace() && !escaped {
+                    if let Some(ws) = word_start.take() {
+                        current.push(Node::new(NodeKind::StrPart, ws, offset));
+                    }
+                    flush(&mut current, &mut elements);
+                } else if word_start.is_none() {
+                    word_start = Some(offset);
+                }
+                escaped = ch == '\\' && !escaped;
+            }
+            if let Some(ws) = word_start {
+                current.push(Node::new(NodeKind::StrPart, ws, part.end));
+            }
+        }
+        flush(&mut current, &mut elements);
+        elements
+    }
+
+    fn parse_heredoc(&mut self) -> Node {
+        let marker = self.advance();
+        let body = self.heredoc_bodies.remove(&marker.start).unwrap_or_default();
+        let saved_tokens = std::mem::replace(&mut self.tokens, body);
+        let saved_pos = std::mem::replace(&mut self.pos, 0);
+        let (parts, _) = self.parse_literal_parts();
+        self.tokens = saved_tokens;
+        self.pos = saved_pos;
+        self.prev_end = marker.end;
+        Node::new(
+            NodeKind::Str {
+                parts,
+                heredoc: true,
+            },
+            marker.start,
+            marker.end,
+        )
+    }
+
+    // ------------------------------------------------------------------
+    // Keywords
+    // ------------------------------------------------------------------
+
+    fn parse_keyword(&mut self) -> Node {
+        let token = self.peek().expect("caller checked for a token");
+        let start = token.start;
+        match token.text(self.src) {
+            "nil" => self.leaf(NodeKind::Nil),
+            "true" => self.leaf(NodeKind::True),
+            "false" => self.leaf(NodeKind::False),
+            "self" => self.leaf(NodeKind::SelfRef),
+            "__FILE__" => self.leaf(NodeKind::Str {
+                parts: Vec::new(),
+                heredoc: false,
+            }),
+            "__LINE__" => self.leaf(NodeKind::Int),
+            "__ENCODING__" => self.leaf(NodeKind::Const {
+                scope: None,
+                name: "Encoding".to_string(),
+            }),
+            "redo" => self.leaf(NodeKind::Redo),
+            "retry" => self.leaf(NodeKind::Retry),
+            "if" => self.parse_if(IfKeyword::If),
+            "unless" => self.parse_if(IfKeyword::Unless),
+            "while" | "until" => self.parse_while(),
+            "for" => self.parse_for(),
+            "case" => self.parse_case(),
+            "begin" => {
+                self.advance();
+                let body = self.with_do_blocks(true, |p| p.parse_body());
+                self.eat_kw("end");
+                self.node(NodeKind::Kwbegin(body), start)
+            }
+            "def" => self.parse_def(),
+            "class" => self.parse_class(),
+            "module" => self.parse_module(),
+            "return" | "break" | "next" => {
+                let keyword = self.advance().text(self.src);
+                let arguments = if self.can_start_expression() {
+                    self.parse_command_arguments()
+                } else {
+                    Vec::new()
+                };
+                let kind = match keyword {
+                    "return" => NodeKind::Return(arguments),
+                    "break" => NodeKind::Break(arguments),
+                    _ => NodeKind::Next(arguments),
+                };
+                self.node(kind, start)
+            }
+            "yield" => {
+                self.advance();
+                let arguments = if self.at_punct("(") && self.adjacent() {
+                    self.parse_paren_arguments()
+                } else if self.can_start_command_argument() {
+                    self.parse_command_arguments()
+                } else {
+                    Vec::new()
+                };
+                self.node(NodeKind::Yield { arguments }, start)
+            }
+            "super" => {
+                self.advance();
+                let arguments = if self.at_punct("(") && self.adjacent() {
+                    Some(self.parse_paren_arguments())
+                } else if self.can_start_command_argument() {
+                    Some(self.parse_command_arguments())
+                } else {
+                    None
+                };
+                self.node(NodeKind::Super { arguments }, start)
+            }
+            "alias" => {
+                self.advance();
+                let new_name = self.parse_method_reference();
+                let old_name = self.parse_method_reference();
+                self.node(
+                    NodeKind::Alias {
+                        new_name: Box::new(new_name),
+                        old_name: Box::new(old_name),
+                    },
+                    start,
+                )
+            }
+            "undef" => {
+                self.advance();
+                let mut names = vec![self.parse_method_reference()];
+                while self.eat_punct(",") {
+                    self.skip_newlines();
+                    names.push(self.parse_method_reference());
+                }
+                self.node(NodeKind::Undef(names), start)
+            }
+            "BEGIN" | "END" => {
+                let begin = self.advance().text(self.src) == "BEGIN";
+                self.eat_punct("{");
+                let body = Self::wrap(self.parse_statements());
+                self.eat_punct("}");
+                self.node(
+                    if begin {
+                        NodeKind::Preexe(body)
+                    } else {
+                        NodeKind::Postexe(body)
+                    },
+                    start,
+                )
+            }
+            _ => self.error_node(),
+        }
+    }
+
+    fn leaf(&mut self, kind: NodeKind) -> Node {
+        let token = self.advance();
+        Node::new(kind, token.start, token.end)
+    }
+
+    fn parse_method_reference(&mut self) -> Node {
+        let Some(token) = self.peek() else {
+            return self.error_node();
+        };
+        match token.kind {
+            TokenKind::Symbol | TokenKind::SymbolBegin => self.parse_primary_base(),
+            TokenKind::GlobalVariable => self.leaf(NodeKind::Gvar(token.text(self.src).to_string())),
+            TokenKind::Identifier
+            | TokenKind::Constant
+            | TokenKind::Keyword
+            | TokenKind::Operator
+            | TokenKind::Label => {
+                self.advance();
+                let mut name = token.text(self.src).to_string();
+                if self.at_op("=") && self.adjacent() {
+                    self.advance();
+                    name.push('=');
+                }
+                self.node(NodeKind::Sym { name }, token.start)
+            }
+            _ => self.error_node(),
+        }
+    }
+
+    /// Skips an optional `then` or statement terminator after a condition.
+    fn skip_then(&mut self) {
+        self.skip_terms();
+        self.eat_kw("then");
+    }
+
+    fn parse_if(&mut self, keyword: IfKeyword) -> Node {
+        let start = self.advance().start;
+        let condition = self.parse_expression_statement();
+        self.skip_then();
+        let then_body = Self::wrap(self.parse_statements());
+        let else_body = if keyword != IfKeyword::Unless && self.at_kw("elsif") {
+            Some(Box::new(self.parse_if(IfKeyword::Elsif)))
+        } else if self.eat_kw("else") {
+            Self::wrap(self.parse_statements())
+        } else {
+            None
+        };
+        if keyword != IfKeyword::Elsif {
+            self.eat_kw("end");
+        }
+        self.node(
+            NodeKind::If {
+                condition: Box::new(condition),
+                then_body,
+                else_body,
+                keyword,
+                modifier: false,
+            },
+            start,
+        )
+    }
+
+    fn parse_while(&mut self) -> Node {
+        let keyword = self.advance();
+        let until = keyword.text(self.src) == "until";
+        let condition = self.with_do_blocks(false, |p| p.parse_expression_statement());
+        self.skip_terms();
+        self.eat_kw("do");
+        let body = Self::wrap(self.parse_statements());
+        self.eat_kw("end");
+        self.node(
+            NodeKind::While {
+                condition: Box::new(condition),
+                body,
+                until,
+                modifier: false,
+            },
+            keyword.start,
+        )
+    }
+
+    fn parse_for(&mut self) -> Node {
+        let start = self.advance().start;
+        let var_start = self.current_start();
+        let mut targets = vec![self.parse_mlhs_item()];
+        while self.eat_punct(",") {
+            targets.push(self.parse_mlhs_item());
+        }
+        let variable = if targets.len() == 1 {
+            targets.pop().unwrap_or_else(|| self.node(NodeKind::Error, var_start))
+        } else {
+            self.node(NodeKind::Mlhs(targets), var_start)
+        };
+        self.eat_kw("in");
+        let iterable = self.with_do_blocks(false, |p| p.parse_expression_statement());
+        self.skip_terms();
+        self.eat_kw("do");
+        let body = Self::wrap(self.parse_statements());
+        self.eat_kw("end");
+        self.node(
+            NodeKind::For {
+                variable: Box::new(variable),
+                iterable: Box::new(iterable),
+                body,
+            },
+            start,
+        )
+    }
+
+    fn parse_case(&mut self) -> Node {
+        let start = self.advance().start;
+        let subject = if self.at_kind(TokenKind::Newline) || self.at_punct(";") {
+            None
+        } else {
+            Some(Box::new(self.parse_expression_statement()))
+        };
+        self.skip_terms();
+        let mut whens = Vec::new();
+        let mut clauses = Vec::new();
+        loop {
+            let clause_start = self.current_start();
+            if self.eat_kw("when") {
+                let mut conditions = Vec::new();
+                loop {
+                    self.skip_newlines();
+                    conditions.push(self.parse_argument_value());
+                    if !self.eat_punct(",") {
+                        break;
+                    }
+                }
+                self.skip_then();
+                let body = Self::wrap(self.parse_statements());
+                whens.push(self.node(NodeKind::When { conditions, body }, clause_start));
+            } else if self.eat_kw("in") {
+                let pattern = self.parse_pattern_top();
+                let guard = if self.at_kw("if") || self.at_kw("unless") {
+                    let guard_start = self.current_start();
+                    let unless = self.advance().text(self.src) == "unless";
+                    let condition = Box::new(self.parse_expression_statement());
+                    Some(Box::new(self.node(
+                        if unless {
+                            NodeKind::UnlessGuard(condition)
+                        } else {
+                            NodeKind::IfGuard(condition)
+                        },
+                        guard_start,
+                    )))
+                } else {
+                    None
+                };
+                self.skip_then();
+                let body = Self::wrap(self.parse_statements());
+                clauses.push(self.node(
+                    NodeKind::InClause {
+                        pattern: Box::new(pattern),
+                        guard,
+                        body,
+                    },
+                    clause_start,
+                ));
+            } else {
+                break;
+            }
+        }
+        let else_body = if self.eat_kw("else") {
+            Self::wrap(self.parse_statements())
+        } else {
+            None
+        };
+        self.eat_kw("end");
+        if clauses.is_empty() {
+            return self.node(
+                NodeKind::Case {
+                    subject,
+                    whens,
+                    else_body,
+                },
+                start,
+            );
+        }
+        let subject = subject.unwrap_or_else(|| Box::new(Node::new(NodeKind::Error, start, start)));
+        self.node(
+            NodeKind::CaseMatch {
+                subject,
+                clauses,
+                else_body,
+            },
+            start,
+        )
+    }
+
+    /// Parses a body that may contain `rescue`, `else` and `ensure` clauses.
+    fn parse_body(&mut self) -> Option<Box<Node>> {
+        let statements = self.parse_statements();
+        let mut body = Self::wrap(statements);
+        let mut handlers = Vec::new();
+        while self.at_kw("rescue") {
+            handlers.push(self.parse_rescue_clause());
+        }
+        if !handlers.is_empty() {
+            let else_body = if self.eat_kw("else") {
+                Self::wrap(self.parse_statements())
+            } else {
+                None
+            };
+            let start = body.as_ref().map_or(handlers[0].start, |b| b.start);
+            body = Some(Box::new(self.node(
+                NodeKind::Rescue {
+                    body,
+                    handlers,
+                    else_body,
+                },
+                start,
+            )));
+        }
+        if self.at_kw("ensure") {
+            let ensure_start = self.advance().start;
+            let ensure_body = Self::wrap(self.parse_statements());
+            let start = body.as_ref().map_or(ensure_start, |b| b.start);
+            body = Some(Box::new(self.node(NodeKind::Ensure { body, ensure_body }, start)));
+        }
+        body
+    }
+
+    fn parse_rescue_clause(&mut self) -> Node {
+        let start = self.advance().start;
+        let mut exceptions = Vec::new();
+        while !self.at_kind(TokenKind::Newline)
+            && !self.at_punct(";")
+            && !self.at_kw("then")
+            && !self.at_op("=>")
+            && self.can_start_expression()
+        {
+            exceptions.push(self.parse_argument_value_without_assignment());
+            if !self.eat_punct(",") {
+                break;
+            }
+            self.skip_newlines();
+        }
+        let variable = if self.eat_op("=>") {
+            let target = self.parse_primary();
+            Some(Box::new(self.assignment_target(target)))
+        } else {
+            None
+        };
+        self.skip_then();
+        let body = Self::wrap(self.parse_statements());
+        self.node(
+            NodeKind::RescueBody {
+                exceptions,
+                variable,
+                body,
+            },
+            start,
+        )
+    }
+
+    fn parse_argument_value_without_assignment(&mut self) -> Node {
+        let start = self.current_start();
+        if self.eat_op("*") {
+            let operand = self.parse_ternary();
+            return self.node(NodeKind::Splat(Some(Box::new(operand))), start);
+        }
+        self.parse_ternary()
+    }
+
+    fn parse_def(&mut self) -> Node {
+        let start = self.advance().start;
+        let receiver = self.parse_def_receiver();
+        let name = self.parse_def_name();
+        self.push_scope(false);
+        let parameters = if self.at_punct("(") {
+            let params_start = self.advance().start;
+            let params = self.parse_parameter_list(Some(")"), false);
+            self.eat_punct(")");
+            Some(Box::new(self.node(NodeKind::Params(params), params_start)))
+        } else if !self.at_op("=") && self.can_start_parameter() {
+            let params_start = self.current_start();
+            let params = self.parse_parameter_list(None, false);
+            Some(Box::new(self.node(NodeKind::Params(params), params_start)))
+        } else {
+            None
+        };
+        let (body, endless) = if self.eat_op("=") {
+            self.skip_newlines();
+            (Some(Box::new(self.parse_statement())), true)
+        } else {
+            let body = self.with_do_blocks(true, |p| p.parse_body());
+            self.eat_kw("end");
+            (body, false)
+        };
+        self.pop_scope();
+        self.node(
+            NodeKind::Def {
+                receiver,
+                name,
+                parameters,
+                body,
+                endless,
+            },
+            start,
+        )
+    }
+
+    fn parse_def_receiver(&mut self) -> Option<Box<Node>> {
+        let token = self.peek()?;
+        let dot_follows = self
+            .peek_at(1)
+            .is_some_and(|t| t.kind == TokenKind::Operator && t.text(self.src) == ".");
+        if !dot_follows {
+            return None;
+        }
+        let kind = match token.kind {
+            // The lexer reads `self` after `def` as a method name.
+            TokenKind::Keyword | TokenKind::Identifier if token.text(self.src) == "self" => {
+                NodeKind::SelfRef
+            }
+            TokenKind::Constant => NodeKind::Const {
+                scope: None,
+                name: token.text(self.src).to_string(),
+            },
+            TokenKind::Identifier => NodeKind::Lvar(token.text(self.src).to_string()),
+            _ => return None,
+        };
+        self.advance();
+        self.advance();
+        Some(Box::new(Node::new(kind, token.start, token.end)))
+    }
+
+    fn parse_def_name(&mut self) -> String {
+        let Some(token) = self.peek() else {
+            return String::new();
+        };
+        let mut name = token.text(self.src).to_string();
+        match token.kind {
+            TokenKind::Identifier | TokenKind::Constant | TokenKind::Keyword => {
+                self.advance();
+                let setter = self.at_op("=")
+                    && self.adjacent()
+                    && self.peek_at(1).is_some_and(|t| {
+                        t.kind == TokenKind::Punctuation
+                            && t.text(self.src) == "("
+                            && self.peek().is_some_and(|eq| eq.end == t.start)
+                    });
+                if setter {
+                    self.advance();
+                    name.push('=');
+                }
+            }
+            TokenKind::Label => {
+                // `def foo:` is malformed; keep the name without the colon.
+                self.advance();
+                name.pop();
+            }
+            TokenKind::Operator | TokenKind::Unknown => {
+                self.advance();
+                if self.at(TokenKind::Unknown, "@") && self.adjacent() {
+                    self.advance();
+                    name.push('@');
+                }
+            }
+            TokenKind::Punctuation if name == "[" => {
+                self.advance();
+                if self.at_punct("]") && self.adjacent() {
+                    self.advance();
+                    name.push(']');
+                    if self.at_op("=") && self.adjacent() {
+                        self.advance();
+                        name.push('=');
+                    }
+                }
+            }
+            _ => return String::new(),
+        }
+        name
+    }
+
+    fn can_start_parameter(&self) -> bool {
+        match self.peek() {
+            Some(t) => match t.kind {
+                TokenKind::Identifier | TokenKind::Label => true,
+                TokenKind::Operator => matches!(t.text(self.src), "*" | "**" | "&" | "..."),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Parses parameters up to `closer` (or the end of the line when there
+    /// is none). Block parameters restrict default values so the closing `|`
+    /// is not taken as an operator.
+    fn parse_parameter_list(&mut self, closer: Option<&str>, block: bool) -> Vec<Node> {
+        let mut params = Vec::new();
+        let at_closer = |p: &Self| match closer {
+            Some(c) => p.at_punct(c) || p.at_op(c),
+            None => false,
+        };
+        loop {
+            if closer.is_some() {
+                self.skip_newlines();
+            }
+            if at_closer(self) || self.at_eof() {
+                break;
+            }
+            let start = self.current_start();
+            if block && self.eat_punct(";") {
+                while self.at_kind(TokenKind::Identifier) {
+                    let name = self.advance().text(self.src).to_string();
+                    self.declare(&name);
+                    params.push(self.param(ParamKind::Shadow, Some(name), None, start));
+                    if !self.eat_punct(",") {
+                        break;
+                    }
+                }
+                continue;
+            }
+            let Some(token) = self.peek() else {
+                break;
+            };
+            let text = token.text(self.src);
+            let param = match token.kind {
+                TokenKind::Identifier => {
+                    self.advance();
+                    self.declare(text);
+                    if self.eat_op("=") {
+                        let default = self.parse_parameter_default(block);
+                        self.param(ParamKind::Optional, Some(text.to_string()), Some(default), start)
+                    } else {
+                        self.param(ParamKind::Required, Some(text.to_string()), None, start)
+                    }
+                }
+                TokenKind::Label => {
+                    self.advance();
+                    let name = text.trim_end_matches(':').to_string();
+                    self.declare(&name);
+                    let has_default = !(self.at_punct(",")
+                        || at_closer(self)
+                        || self.at_kind(TokenKind::Newline)
+                        || self.at_punct(";")
+                        || self.at_eof());
+                    if has_default {
+                        let default = self.parse_parameter_default(block);
+                        self.param(ParamKind::OptionalKeyword, Some(name), Some(default), start)
+                    } else {
+                        self.param(ParamKind::Keyword, Some(name), None, start)
+                    }
+                }
+                TokenKind::Operator if matches!(text, "*" | "**" | "&") => {
+                    self.advance();
+                    if text == "**" && self.at_kw("nil") {
+                        self.advance();
+                        self.param(ParamKind::NoKeywords, None, None, start)
+                    } else {
+                        let name = if self.at_kind(TokenKind::Identifier) && self.adjacent() {
+                            let name = self.advance().text(self.src).to_string();
+                            self.declare(&name);
+                            Some(name)
+                        } else {
+                            None
+                        };
+                        let kind = match text {
+                            "*" => ParamKind::Rest,
+                            "**" => ParamKind::KeywordRest,
+                            _ => ParamKind::Block,
+                        };
+                        self.param(kind, name, None, start)
+                    }
+                }
+                TokenKind::Operator if text == "..." => {
+                    self.advance();
+                    self.param(ParamKind::Forward, None, None, start)
+                }
+                TokenKind::Punctuation if text == "(" => {
+                    self.advance();
+                    let inner = self.parse_parameter_list(Some(")"), block);
+                    self.eat_punct(")");
+                    self.node(NodeKind::Mlhs(inner), start)
+                }
+                _ => {
+                    if closer.is_none() {
+                        break;
+                    }
+                    self.error_node()
+                }
+            };
+            params.push(param);
+            if !self.eat_punct(",") {
+                if block && self.at_punct(";") {
+                    continue;
+                }
+                break;
+            }
+        }
+        params
+    }
+
+    fn parse_parameter_default(&mut self, block: bool) -> Box<Node> {
+        Box::new(if block {
+            self.parse_binary(PREC_BIT_OR + 1)
+        } else {
+            self.parse_ternary()
+        })
+    }
+
+    fn param(
+        &self,
+        kind: ParamKind,
+        name: Option<String>,
+        default: Option<Box<Node>>,
+        start: usize,
+    ) -> Node {
+        self.node(
+            NodeKind::Param {
+                kind,
+                name,
+                default,
+            },
+            start,
+        )
+    }
+
+    fn parse_constant_path(&mut self) -> Node {
+        let start = self.current_start();
+        let mut node = if self.at_op("::") {
+            self.advance();
+            self.node(NodeKind::Cbase, start)
+        } else if self.at_kind(TokenKind::Constant) {
+            let name = self.advance().text(self.src).to_string();
+            self.node(NodeKind::Const { scope: None, name }, start)
+        } else {
+            return self.parse_primary();
+        };
+        if matches!(node.kind, NodeKind::Cbase) && self.at_kind(TokenKind::Constant) {
+            let name = self.advance().text(self.src).to_string();
+            node = self.node(
+                NodeKind::Const {
+                    scope: Some(Box::new(node)),
+                    name,
+                },
+                start,
+            );
+        }
+        while self.at_op("::") && self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Constant) {
+            self.advance();
+            let name = self.advance().text(self.src).to_string();
+            node = self.node(
+                NodeKind::Const {
+                    scope: Some(Box::new(node)),
+                    name,
+                },
+                start,
+            );
+        }
+        node
+    }
+
+    fn parse_class(&mut self) -> Node {
+        let start = self.advance().start;
+        if self.eat_op("<<") {
+            let target = self.parse_expression_statement();
+            self.skip_terms();
+            self.push_scope(false);
+            let body = self.with_do_blocks(true, |p| p.parse_body());
+            self.pop_scope();
+            self.eat_kw("end");
+            return self.node(
+                NodeKind::SingletonClass {
+                    target: Box::new(target),
+                    body,
+                },
+                start,
+            );
+        }
+        let name = self.parse_constant_path();
+        let superclass = if self.eat_op("<") {
+            Some(Box::new(self.parse_expr()))
+        } else {
+            None
+        };
+        self.skip_terms();
+        self.push_scope(false);
+        let body = self.with_do_blocks(true, |p| p.parse_body());
+        self.pop_scope();
+        self.eat_kw("end");
+        self.node(
+            NodeKind::Class {
+                name: Box::new(name),
+                superclass,
+                body,
+            },
+            start,
+        )
+    }
+
+    fn parse_module(&mut self) -> Node {
+        let start = self.advance().start;
+        let name = self.parse_constant_path();
+        self.skip_terms();
+        self.push_scope(false);
+        let body = self.with_do_blocks(true, |p| p.parse_body());
+        self.pop_scope();
+        self.eat_kw("end");
+        self.node(
+            NodeKind::Module {
+                name: Box::new(name),
+                body,
+            },
+            start,
+        )
+    }
+
+    // ------------------------------------------------------------------
+    // Blocks and lambdas
+    // ------------------------------------------------------------------
+
+    fn parse_block_parameters(&mut self) -> Option<Box<Node>> {
+        let start = self.current_start();
+        if self.eat_op("||") {
+            return Some(Box::new(self.node(NodeKind::Params(Vec::new()), start)));
+        }
+        if !self.eat_op("|") {
+            return None;
+        }
+        let params = self.parse_parameter_list(Some("|"), true);
+        self.eat_op("|");
+        Some(Box::new(self.node(NodeKind::Params(params), start)))
+    }
+
+    fn parse_brace_block(&mut self, call: Node) -> Node {
+        let start = call.start;
+        self.advance();
+        self.push_scope(true);
+        let (parameters, body) = self.with_do_blocks(true, |p| {
+            let parameters = p.parse_block_parameters();
+            let body = Self::wrap(p.parse_statements());
+            (parameters, body)
+        });
+        self.eat_punct("}");
+        self.pop_scope();
+        self.node(
+            NodeKind::Block {
+                call: Box::new(call),
+                parameters,
+                body,
+                braces: true,
+            },
+            start,
+        )
+    }
+
+    fn parse_do_block(&mut self, call: Node) -> Node {
+        let start = call.start;
+        self.advance();
+        self.push_scope(true);
+        let (parameters, body) = self.with_do_blocks(true, |p| {
+            let parameters = p.parse_block_parameters();
+            let body = p.parse_body();
+            (parameters, body)
+        });
+        self.eat_kw("end");
+        self.pop_scope();
+        self.node(
+            NodeKind::Block {
+                call: Box::new(call),
+                parameters,
+                body,
+                braces: false,
+            },
+            start,
+        )
+    }
+
+    fn parse_lambda(&mut self) -> Node {
+        let arrow = self.advance();
+        let lambda = Node::new(NodeKind::Lambda, arrow.start, arrow.end);
+        self.push_scope(true);
+        let parameters = if self.at_punct("(") {
+            let params_start = self.advance().start;
+            let params = self.parse_parameter_list(Some(")"), false);
+            self.eat_punct(")");
+            Some(Box::new(self.node(NodeKind::Params(params), params_start)))
+        } else if self.can_start_parameter() {
+            let params_start = self.current_start();
+            let params = self.parse_parameter_list(None, false);
+            Some(Box::new(self.node(NodeKind::Params(params), params_start)))
+        } else {
+            None
+        };
+        let braces = self.at_punct("{");
+        let body = if braces || self.at_kw("do") {
+            self.advance();
+            let body = if braces {
+                Self::wrap(self.with_do_blocks(true, |p| p.parse_statements()))
+            } else {
+                self.with_do_blocks(true, |p| p.parse_body())
+            };
+            if braces {
+                self.eat_punct("}");
+            } else {
+                self.eat_kw("end");
+            }
+            body
+        } else {
+            None
+        };
+        self.pop_scope();
+        self.node(
+            NodeKind::Block {
+                call: Box::new(lambda),
+                parameters,
+                body,
+                braces,
+            },
+            arrow.start,
+        )
+    }
+
+    // ------------------------------------------------------------------
+    // Pattern matching
+    // ------------------------------------------------------------------
+
+    fn parse_pattern_top(&mut self) -> Node {
+        let saved = std::mem::replace(&mut self.in_pattern, true);
+        let start = self.current_start();
+        let node = if self.at_kind(TokenKind::Label) {
+            let pairs = self.parse_hash_pattern_items(None);
+            self.node(
+                NodeKind::HashPattern {
+                    constant: None,
+                    pairs,
+                },
+                start,
+            )
+        } else {
+            let first = self.parse_pattern();
+            if self.at_punct(",") {
+                let mut elements = vec![first];
+                while self.eat_punct(",") {
+                    if !self.can_start_expression() && !self.at_op("^") {
+                        break;
+                    }
+                    elements.push(self.parse_pattern());
+                }
+                self.node(
+                    NodeKind::ArrayPattern {
+                        constant: None,
+                        elements,
+                    },
+                    start,
+                )
+            } else if matches!(first.kind, NodeKind::MatchRest(_)) {
+                self.node(
+                    NodeKind::ArrayPattern {
+                        constant: None,
+                        elements: vec![first],
+                    },
+                    start,
+                )
+            } else {
+                first
+            }
+        };
+        self.in_pattern = saved;
+        node
+    }
+
+    fn parse_pattern(&mut self) -> Node {
+        let start = self.current_start();
+        let mut left = self.parse_pattern_primary();
+        while self.eat_op("|") {
+            let right = self.parse_pattern_primary();
+            left = self.node(
+                NodeKind::MatchAlt {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                start,
+            );
+        }
+        if self.at_op("=>") && self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Identifier) {
+            self.advance();
+            let name = self.advance().text(self.src).to_string();
+            self.declare(&name);
+            left = self.node(
+                NodeKind::MatchAs {
+                    pattern: Box::new(left),
+                    name,
+                },
+                start,
+            );
+        }
+        left
+    }
+
+    fn parse_pattern_primary(&mut self) -> Node {
+        let start = self.current_start();
+        let Some(token) = self.peek() else {
+            return self.error_node();
+        };
+        let text = token.text(self.src);
+        match token.kind {
+            TokenKind::Punctuation if text == "[" => {
+                self.advance();
+                let elements = self.parse_array_pattern_items("]");
+                self.eat_punct("]");
+                self.array_or_find_pattern(None, elements, start)
+            }
+            TokenKind::Punctuation if text == "{" => {
+                self.advance();
+                let pairs = self.parse_hash_pattern_items(Some("}"));
+                self.eat_punct("}");
+                self.node(
+                    NodeKind::HashPattern {
+                        constant: None,
+                        pairs,
+                    },
+                    start,
+                )
+            }
+            TokenKind::Operator if text == "*" => {
+                self.advance();
+                let name = if self.at_kind(TokenKind::Identifier) {
+                    let name = self.advance().text(self.src).to_string();
+                    self.declare(&name);
+                    Some(name)
+                } else {
+                    None
+                };
+                self.node(NodeKind::MatchRest(name), start)
+            }
+            TokenKind::Operator if text == "^" => {
+                self.advance();
+                let pinned = if self.at_punct("(") {
+                    self.advance();
+                    let saved = std::mem::replace(&mut self.in_pattern, false);
+                    let inner = self.parse_expression_statement();
+                    self.in_pattern = saved;
+                    self.eat_punct(")");
+                    inner
+                } else {
+                    self.parse_primary_base()
+                };
+                self.node(NodeKind::Pin(Box::new(pinned)), start)
+            }
+            TokenKind::Identifier
+                if !self
+                    .peek_at(1)
+                    .is_some_and(|t| t.start == token.end && t.text(self.src) == "(") =>
+            {
+                self.advance();
+                self.declare(text);
+                self.node(NodeKind::MatchVar(text.to_string()), start)
+            }
+            _ if token.kind == TokenKind::Constant || self.at_op("::") => {
+                let constant = self.parse_constant_path();
+                if (self.at_punct("(") || self.at_punct("[")) && self.adjacent() {
+                    let closer = if self.advance().text(self.src) == "(" {
+                        ")"
+                    } else {
+                        "]"
+                    };
+                    if self.at_kind(TokenKind::Label) {
+                        let pairs = self.parse_hash_pattern_items(Some(closer));
+                        self.eat_punct(closer);
+                        return self.node(
+                            NodeKind::HashPattern {
+                                constant: Some(Box::new(constant)),
+                                pairs,
+                            },
+                            start,
+                        );
+                    }
+                    let elements = self.parse_array_pattern_items(closer);
+                    self.eat_punct(closer);
+                    return self.array_or_find_pattern(Some(Box::new(constant)), elements, start);
+                }
+                if self.at_op("..") || self.at_op("...") {
+                    let exclusive = self.advance().text(self.src) == "...";
+                    let to = if self.can_start_expression() {
+                        Some(Box::new(self.parse_binary(PREC_RANGE + 1)))
+                    } else {
+                        None
+                    };
+                    return self.node(
+                        NodeKind::Range {
+                            from: Some(Box::new(constant)),
+                            to,
+                            exclusive,
+                        },
+                        start,
+                    );
+                }
+                constant
+            }
+            _ => self.parse_binary(PREC_RANGE),
+        }
+    }
+
+    fn array_or_find_pattern(
+        &self,
+        constant: Option<Box<Node>>,
+        elements: Vec<Node>,
+        start: usize,
+    ) -> Node {
+        let is_find = elements.len() >= 2
+            && matches!(elements[0].kind, NodeKind::MatchRest(_))
+            && matches!(elements[elements.len() - 1].kind, NodeKind::MatchRest(_));
+        self.node(
+            if is_find {
+                NodeKind::FindPattern { constant, elements }
+            } else {
+                NodeKind::ArrayPattern { constant, elements }
+            },
+            start,
+        )
+    }
+
+    fn parse_array_pattern_items(&mut self, closer: &str) -> Vec<Node> {
+        let mut elements = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.at_punct(closer) || self.at_eof() {
+                break;
+            }
+            let before = self.pos;
+            elements.push(self.parse_pattern());
+            if self.pos == before {
+                elements.push(self.error_node());
+            }
+            self.skip_newlines();
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+        elements
+    }
+
+    fn parse_hash_pattern_items(&mut self, closer: Option<&str>) -> Vec<Node> {
+        let mut pairs = Vec::new();
+        loop {
+            if closer.is_some() {
+                self.skip_newlines();
+            }
+            let at_end = |p: &Self| {
+                closer.is_some_and(|c| p.at_punct(c))
+                    || p.at_eof()
+                    || p.at_kind(TokenKind::Newline)
+                    || p.at_punct(";")
+                    || p.at_kw("then")
+                    || p.at_kw("if")
+                    || p.at_kw("unless")
+                    || p.at_punct(",")
+            };
+            if at_end(self) {
+                break;
+            }
+            let start = self.current_start();
+            if self.at_kind(TokenKind::Label) {
+                let label = self.advance();
+                let name = label.text(self.src).trim_end_matches(':').to_string();
+                if at_end(self) || closer.is_none() && self.at_punct(",") {
+                    self.declare(&name);
+                    pairs.push(Node::new(NodeKind::MatchVar(name), label.start, label.end));
+                } else {
+                    let key = Node::new(NodeKind::Sym { name }, label.start, label.end);
+                    let value = self.parse_pattern();
+                    pairs.push(self.node(
+                        NodeKind::Pair {
+                            key: Box::new(key),
+                            value: Box::new(value),
+                        },
+                        start,
+                    ));
+                }
+            } else if self.eat_op("**") {
+                if self.eat_kw("nil") {
+                    pairs.push(self.node(NodeKind::MatchNil, start));
+                } else {
+                    let name = if self.at_kind(TokenKind::Identifier) {
+                        let name = self.advance().text(self.src).to_string();
+                        self.declare(&name);
+                        Some(name)
+                    } else {
+                        None
+                    };
+                    pairs.push(self.node(NodeKind::MatchRest(name), start));
+                }
+            } else if self.at_kind(TokenKind::StringBegin) {
+                let key = self.parse_string_like();
+                self.eat_op(":");
+                let value = self.parse_pattern();
+                pairs.push(self.node(
+                    NodeKind::Pair {
+                        key: Box::new(key),
+                        value: Box::new(value),
+                    },
+                    start,
+                ));
+            } else {
+                pairs.push(self.error_node());
+            }
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+        pairs
+    }
+}
+
+fn send(receiver: Option<Node>, method: &str, arguments: Vec<Node>, parenthesized: bool) -> NodeKind {
+    NodeKind::Send {
+        receiver: receiver.map(Box::new),
+        method: method.to_string(),
+        arguments,
+        safe_navigation: false,
+        parenthesized,
+    }
+}
+
+fn unary_send(operand: Node, method: &str) -> NodeKind {
+    send(Some(operand), method, Vec::new(), false)
+}
+
+/// Moves accumulated `key: value` pairs into a brace-less hash argument.
+fn flush_pairs(arguments: &mut Vec<Node>, pairs: &mut Vec<Node>) {
+    if pairs.is_empty() {
+        return;
+    }
+    let start = pairs[0].start;
+    let end = pairs[pairs.len() - 1].end;
+    arguments.push(Node::new(
+        NodeKind::Hash {
+            pairs: std::mem::take(pairs),
+            braces: false,
+        },
+        start,
+        end,
+    ));
+}
+
+fn is_identifier_name(name: &str) -> bool {
+    name.bytes()
+        .next()
+        .is_some_and(|b| b.is_ascii_lowercase() || b == b'_' || b >= 0x80)
+        && !name.ends_with(['?', '!'])
+}
+
+fn is_string_like(node: &Node) -> bool {
+    matches!(node.kind, NodeKind::Str { heredoc: false, .. })
+}
+
+/// Whether the node is a receiver-less call without parentheses, which
+/// makes a following ` [` an argument rather than an index.
+fn is_command_call(node: &Node) -> bool {
+    matches!(
+        &node.kind,
+        NodeKind::Send {
+            parenthesized: false,
+            method,
+            ..
+        } if is_identifier_name(method) || method.ends_with(['?', '!'])
+    )
+}
+
+fn takes_block(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::Send { method, .. } => {
+            !method.ends_with('=') || matches!(method.as_str(), "==" | "===" | "!=" | "<=" | ">=")
+        }
+        NodeKind::Super { .. } | NodeKind::Lambda => true,
+        _ => false,
+    }
+}
+
+fn is_assignable(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::Splat(_) | NodeKind::Mlhs(_) => true,
+        NodeKind::Parens(items) => items.iter().all(is_assignable),
+        _ => is_single_assignable(node),
+    }
+}
+
+fn is_single_assignable(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::Lvar(_)
+        | NodeKind::Ivar(_)
+        | NodeKind::Cvar(_)
+        | NodeKind::Gvar(_)
+        | NodeKind::Const { .. } => true,
+        NodeKind::Send {
+            method,
+            arguments,
+            parenthesized: false,
+            ..
+        } => {
+            method == "[]" || (arguments.is_empty() && is_identifier_name(method))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn sexp(source: &str) -> String {
+        let tokens = tokenize(source);
+        let root = parse(source, &tokens);
+        match &root.kind {
+            NodeKind::Statements(statements) if statements.len() == 1 => {
+                statements[0].to_sexp(source)
+            }
+            _ => root.to_sexp(source),
+        }
+    }
+
+    #[test]
+    fn test_method_definition() {
+        assert_eq!(
+            sexp("def foo(a, b = 1, *rest, key:, &blk)\n  a + b\nend"),
+            "(def foo (args (arg a) (optarg b (int \"1\")) (restarg rest) (kwarg key) \
+             (blockarg blk)) (send :+ (lvar a) (lvar b)))"
+        );
+        assert_eq!(
+            sexp("def self.call = new.call"),
+            "(defs call (self) (send :call (send nil :new)))"
+        );
+    }
+
+    #[test]
+    fn test_commands_and_locals() {
+        assert_eq!(
+            sexp("puts x"),
+            "(send nil :puts (send nil :x))"
+        );
+        assert_eq!(
+            sexp("x = 1\nputs x"),
+            "(begin (asgn (lvar x) (int \"1\")) (send nil :puts (lvar x)))"
+        );
+        assert_eq!(
+            sexp("foo a, key: 1"),
+            "(send nil :foo (send nil :a) (hash (pair (sym :key) (int \"1\"))))"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            sexp("items.each do |item|\n  item.save\nend"),
+            "(block (send :each (send nil :items)) (args (arg item)) (send :save (lvar item)))"
+        );
+        assert_eq!(
+            sexp("it 'works' do\nend"),
+            "(block (send nil :it (str \"'works'\")))"
+        );
+        assert_eq!(
+            sexp("list.map { |x| x * 2 }"),
+            "(block (send :map (send nil :list)) (args (arg x)) (send :* (lvar x) (int \"2\")))"
+        );
+    }
+
+    #[test]
+    fn test_class_and_module() {
+        assert_eq!(
+            sexp("module A\n  class B < C::D\n    attr_reader :x\n  end\nend"),
+            "(module (const A) (class (const B) (const D (const C)) \
+             (send nil :attr_reader (sym :x))))"
+        );
+        assert_eq!(
+            sexp("class << self\n  def x; end\nend"),
+            "(sclass (self) (def x))"
+        );
+    }
+
+    #[test]
+    fn test_conditionals() {
+        assert_eq!(
+            sexp("if a\n  b\nelsif c\n  d\nelse\n  e\nend"),
+            "(if (send nil :a) (send nil :b) (if (send nil :c) (send nil :d) (send nil :e)))"
+        );
+        assert_eq!(
+            sexp("return unless ok?"),
+            "(if (send nil :ok?) (return))"
+        );
+        assert_eq!(
+            sexp("a ? b : c"),
+            "(if (send nil :a) (send nil :b) (send nil :c))"
+        );
+    }
+
+    #[test]
+    fn test_case_when() {
+        assert_eq!(
+            sexp("case x\nwhen 1, 2 then :a\nelse :b\nend"),
+            "(case (send nil :x) (when (int \"1\") (int \"2\") (sym :a)) (sym :b))"
+        );
+    }
+
+    #[test]
+    fn test_rescue_ensure() {
+        assert_eq!(
+            sexp("begin\n  work\nrescue Foo, Bar => e\n  log e\nelse\n  ok\nensure\n  done\nend"),
+            "(kwbegin (ensure (rescue (send nil :work) (resbody (const Foo) (const Bar) \
+             (lvar e) (send nil :log (lvar e))) (send nil :ok)) (send nil :done)))"
+        );
+        assert_eq!(
+            sexp("x = y rescue nil"),
+            "(asgn (lvar x) (rescue (send nil :y) (resbody (nil))))"
+        );
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert_eq!(
+            sexp("case v\nin {name: String => n, age:}\n  n\nin [1, *rest] if rest.any?\nend"),
+            "(case_match (send nil :v) (in_pattern (hash_pattern (pair (sym :name) \
+             (match_as n (const String))) (match_var age)) (lvar n)) (in_pattern \
+             (array_pattern (int \"1\") (match_rest rest)) (if_guard (send :any? (lvar rest)))))"
+        );
+        assert_eq!(
+            sexp("config => {db:}"),
+            "(match_pattern (send nil :config) (hash_pattern (match_var db)))"
+        );
+    }
+
+    #[test]
+    fn test_strings_and_heredocs() {
+        assert_eq!(
+            sexp("foo(<<~SQL, \"a#{b}\")\n  SELECT #{cols}\nSQL\n"),
+            "(send nil :foo (dstr (str_part \"  SELECT \") (interpolation (send nil :cols)) \
+             (str_part \"\\n\")) (dstr (str_part \"a\") (interpolation (send nil :b))))"
+        );
+        assert_eq!(
+            sexp("%w[a b]"),
+            "(array (str \"a\") (str \"b\"))"
+        );
+    }
+
+    #[test]
+    fn test_multiple_assignment_and_op_assign() {
+        assert_eq!(
+            sexp("a, b = b, a"),
+            "(masgn (mlhs (lvar a) (lvar b)) (array (lvar b) (lvar a)))"
+        );
+        assert_eq!(
+            sexp("@x ||= {}"),
+            "(op_asgn || (ivar @x) (hash))"
+        );
+        assert_eq!(
+            sexp("self.name = 'x'"),
+            "(send :name= (self) (str \"'x'\"))"
+        );
+    }
+
+    #[test]
+    fn test_leading_dot_chain() {
+        assert_eq!(
+            sexp("foo\n  .bar\n  # note\n  &.baz"),
+            "(csend :baz (send :bar (send nil :foo)))"
+        );
+    }
+
+    #[test]
+    fn test_node_ranges() {
+        let source = "class Foo\n  def bar\n    baz(1)\n  end\nend\n";
+        let tokens = tokenize(source);
+        let root = parse(source, &tokens);
+        let mut defs = Vec::new();
+        root.walk(&mut |n| {
+            if matches!(n.kind, NodeKind::Def { .. }) {
+                defs.push(n.source(source));
+            }
+        });
+        assert_eq!(defs, vec!["def bar\n    baz(1)\n  end"]);
+    }
+
+    #[test]
+    fn test_recovers_from_errors() {
+        let source = "def foo(\n  ) ) end end\nputs 1\n";
+        let tokens = tokenize(source);
+        let root = parse(source, &tokens);
+        assert!(root.source(source).ends_with("puts 1\n"));
+    }
+
+    #[test]
+    fn test_deep_nesting_is_bounded() {
+        let source = "[".repeat(2000) + &"foo(".repeat(2000) + &"-".repeat(2000);
+        let tokens = tokenize(&source);
+        let root = parse(&source, &tokens);
+        assert_eq!(root.end, source.len());
+    }
+}
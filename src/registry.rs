@@ -1,14 +1,23 @@
 //! Cop registry for managing and filtering cops.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
-use crate::cop::Cop;
+use crate::config::{CopConfig, FileFilter};
+use crate::cop::{Cop, Severity};
 use crate::cops;
 
 /// Registry that holds all cops and manages which ones are enabled.
 pub struct CopRegistry {
     cops: Vec<Box<dyn Cop>>,
     disabled: HashSet<String>,
+    /// Per-cop Include/Exclude patterns limiting which files a cop runs on.
+    path_filters: HashMap<String, FileFilter>,
+    /// Severities configured in place of a cop's default.
+    severities: HashMap<String, Severity>,
+    /// Ruby version of the inspected code; cops that don't support it are
+    /// skipped.
+    target_ruby_version: Option<f64>,
 }
 
 impl CopRegistry {
@@ -17,6 +26,9 @@ impl CopRegistry {
         Self {
             cops: cops::all_cops(),
             disabled: HashSet::new(),
+            path_filters: HashMap::new(),
+            severities: HashMap::new(),
+            target_ruby_version: None,
         }
     }
 
@@ -30,15 +42,86 @@ impl CopRegistry {
         self.disabled.remove(name);
     }
 
-    /// Returns references to all currently enabled cops.
+    /// Returns references to all currently enabled cops that support the
+    /// target Ruby version.
     pub fn enabled_cops(&self) -> Vec<&dyn Cop> {
         self.cops
             .iter()
-            .filter(|cop| !self.disabled.contains(cop.name()))
+            .filter(|cop| !self.disabled.contains(cop.name()) && self.supports_target(cop.as_ref()))
             .map(|cop| cop.as_ref())
             .collect()
     }
 
+    /// Sets the Ruby version the inspected code targets.
+    pub fn set_target_ruby_version(&mut self, version: f64) {
+        self.target_ruby_version = Some(version);
+    }
+
+    /// Returns the Ruby version the inspected code targets, if set.
+    pub fn target_ruby_version(&self) -> Option<f64> {
+        self.target_ruby_version
+    }
+
+    fn supports_target(&self, cop: &dyn Cop) -> bool {
+        let Some(target) = self.target_ruby_version else {
+            return true;
+        };
+        cop.minimum_target_ruby_version().is_none_or(|min| target >= min)
+            && cop.maximum_target_ruby_version().is_none_or(|max| target <= max)
+    }
+
+    /// Returns the enabled cops whose Include/Exclude patterns admit `path`.
+    pub fn enabled_cops_for(&self, path: &Path) -> Vec<&dyn Cop> {
+        self.enabled_cops()
+            .into_iter()
+            .filter(|cop| self.applies_to(cop.name(), path))
+            .collect()
+    }
+
+    /// Rebuilds the named cop from its configuration parameters.
+    pub fn configure(&mut self, name: &str, config: &CopConfig) -> Result<(), String> {
+        let Some(cop) = cops::from_config(name, config) else {
+            return Ok(());
+        };
+        let cop = cop?;
+        if let Some(slot) = self.cops.iter_mut().find(|c| c.name() == name) {
+            *slot = cop;
+        }
+        Ok(())
+    }
+
+    /// Restricts a cop to the files matched by `filter`.
+    pub fn set_path_filter(&mut self, name: &str, filter: FileFilter) {
+        self.path_filters.insert(name.to_string(), filter);
+    }
+
+    /// Checks if a cop's Include/Exclude patterns admit `path`.
+    pub fn applies_to(&self, name: &str, path: &Path) -> bool {
+        self.path_filters.get(name).is_none_or(|filter| filter.matches(path))
+    }
+
+    /// Overrides the severity a cop's offenses are reported with.
+    pub fn set_severity(&mut self, name: &str, severity: Severity) {
+        self.severities.insert(name.to_string(), severity);
+    }
+
+    /// Returns the configured severity of the named cop, if overridden.
+    pub fn severity_override(&self, name: &str) -> Option<Severity> {
+        self.severities.get(name).copied()
+    }
+
+    /// Returns the effective severity of the named cop: the configured
+    /// override, else the cop's default. `None` for unknown cops.
+    pub fn severity_of(&self, name: &str) -> Option<Severity> {
+        self.severity_override(name)
+            .or_else(|| self.cop(name).map(|cop| cop.severity()))
+    }
+
+    /// Looks up a cop by name, whether enabled or not.
+    pub fn cop(&self, name: &str) -> Option<&dyn Cop> {
+        self.cops.iter().find(|cop| cop.name() == name).map(|cop| cop.as_ref())
+    }
+
     /// Returns all cop names (both enabled and disabled).
     pub fn cop_names(&self) -> Vec<&str> {
         self.cops.iter().map(|cop| cop.name()).collect()
@@ -138,4 +221,62 @@ mod tests {
         // was disabled. This is arguably a quirk but reflects current behavior.
         assert_eq!(registry.enabled_count(), initial_count - 1);
     }
+
+    #[test]
+    fn test_enabled_cops_for_path() {
+        let mut registry = CopRegistry::new();
+        let exclude = vec!["spec/**/*".to_string()];
+        registry.set_path_filter(
+            "Layout/TrailingWhitespace",
+            FileFilter::new(Path::new("/project"), &[], &exclude).unwrap(),
+        );
+        let include = vec!["**/*_spec.rb".to_string()];
+        registry.set_path_filter(
+            "Style/StringLiterals",
+            FileFilter::new(Path::new("/project"), &include, &[]).unwrap(),
+        );
+
+        let names = |path: &str| -> Vec<String> {
+            registry
+                .enabled_cops_for(Path::new(path))
+                .iter()
+                .map(|cop| cop.name().to_string())
+                .collect()
+        };
+        let spec = names("/project/spec/foo_spec.rb");
+        let app = names("/project/app/foo.rb");
+
+        assert!(!spec.contains(&"Layout/TrailingWhitespace".to_string()));
+        assert!(spec.contains(&"Style/StringLiterals".to_string()));
+        assert!(app.contains(&"Layout/TrailingWhitespace".to_string()));
+        assert!(!app.contains(&"Style/StringLiterals".to_string()));
+        assert_eq!(app.len(), registry.enabled_count() - 1);
+    }
+
+    #[test]
+    fn test_target_ruby_version_gates_cops() {
+        let mut registry = CopRegistry::new();
+        let names = |registry: &CopRegistry| -> Vec<String> {
+            registry.enabled_cops().iter().map(|cop| cop.name().to_string()).collect()
+        };
+        assert!(names(&registry).contains(&"Style/EndlessMethod".to_string()));
+
+        registry.set_target_ruby_version(2.7);
+        let old = names(&registry);
+        assert!(!old.contains(&"Style/EndlessMethod".to_string()));
+        assert!(old.contains(&"Style/ArgumentsForwarding".to_string()));
+
+        registry.set_target_ruby_version(3.4);
+        assert!(names(&registry).contains(&"Style/ItBlockParameter".to_string()));
+    }
+
+    #[test]
+    fn test_severity_override() {
+        let mut registry = CopRegistry::new();
+        assert_eq!(registry.severity_of("Lint/Debugger"), Some(Severity::Warning));
+
+        registry.set_severity("Lint/Debugger", Severity::Error);
+        assert_eq!(registry.severity_of("Lint/Debugger"), Some(Severity::Error));
+        assert_eq!(registry.severity_of("NonExistent/Cop"), None);
+    }
 }
@@ -1,16 +1,32 @@
 //! Parallel file processing and linting engine.
 
+use std::collections::HashSet;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use rayon::prelude::*;
 
+use crate::baseline;
+use crate::cache::{ResultCache, MAX_FILES_IN_CACHE};
+use crate::config_store::ConfigStore;
+use crate::cop::{self, Cop};
+use crate::cops::lint_extra::RedundantCopDisableDirective;
+use crate::corrector::{self, AutocorrectMode};
+use crate::directive::Directives;
 use crate::offense::Offense;
 use crate::registry::CopRegistry;
 use crate::source::SourceFile;
 
+/// Upper bound on lint-and-correct passes over one file, guarding against
+/// cops whose corrections undo each other.
+const MAX_CORRECTION_PASSES: usize = 200;
+
 /// The main linting runner.
 pub struct Runner {
-    registry: CopRegistry,
+    configs: ConfigStore,
+    autocorrect: Option<AutocorrectMode>,
+    cache: Option<ResultCache>,
 }
 
 /// Result of checking a single file.
@@ -18,6 +34,17 @@ pub struct Runner {
 pub struct FileResult {
     pub path: PathBuf,
     pub offenses: Vec<Offense>,
+    /// The text the offenses point into, for formatters that quote it.
+    /// Only kept for files with offenses.
+    pub source: Option<Arc<str>>,
+}
+
+impl FileResult {
+    /// The result of `path`, keeping `content` if there are offenses in it.
+    fn new(path: PathBuf, offenses: Vec<Offense>, content: &str) -> Self {
+        let source = (!offenses.is_empty()).then(|| Arc::from(content));
+        Self { path, offenses, source }
+    }
 }
 
 /// Result of a complete linting run.
@@ -28,10 +55,44 @@ pub struct RunResult {
     pub total_offenses: usize,
 }
 
+impl RunResult {
+    /// Number of offenses left after autocorrection.
+    pub fn uncorrected_count(&self) -> usize {
+        self.file_results
+            .iter()
+            .flat_map(|r| &r.offenses)
+            .filter(|o| !o.corrected)
+            .count()
+    }
+}
+
 impl Runner {
-    /// Creates a new runner with the given cop registry.
+    /// Creates a new runner that checks every file with the given registry.
     pub fn new(registry: CopRegistry) -> Self {
-        Self { registry }
+        Self::with_configs(ConfigStore::fixed(registry))
+    }
+
+    /// Creates a runner that checks each file with the registry of the
+    /// config governing it.
+    pub fn with_configs(configs: ConfigStore) -> Self {
+        Self {
+            configs,
+            autocorrect: None,
+            cache: None,
+        }
+    }
+
+    /// Enables autocorrection; corrected files are written back to disk.
+    pub fn with_autocorrect(mut self, mode: AutocorrectMode) -> Self {
+        self.autocorrect = Some(mode);
+        self
+    }
+
+    /// Reuses the offenses of unchanged files from `cache`. Runs that
+    /// autocorrect always lint.
+    pub fn with_cache(mut self, cache: ResultCache) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     /// Runs all enabled cops on the given files in parallel.
@@ -44,6 +105,10 @@ impl Runner {
         // Sort by path for consistent output
         file_results.sort_by(|a, b| a.path.cmp(&b.path));
 
+        if let Some(cache) = &self.cache {
+            cache.prune(MAX_FILES_IN_CACHE);
+        }
+
         let total_offenses = file_results.iter().map(|r| r.offenses.len()).sum();
         let total_files = file_results.len();
 
@@ -54,7 +119,24 @@ impl Runner {
         }
     }
 
-    /// Checks a single file with all enabled cops.
+    /// Runs all enabled cops on sources held in memory, such as staged
+    /// content, reporting them under their paths. Corrections are not
+    /// written anywhere.
+    pub fn run_sources(&self, sources: Vec<SourceFile>) -> RunResult {
+        let mut file_results: Vec<FileResult> = sources
+            .into_par_iter()
+            .filter_map(|source| self.check_source(source).map(|(result, _)| result))
+            .collect();
+        file_results.sort_by(|a, b| a.path.cmp(&b.path));
+
+        RunResult {
+            total_offenses: file_results.iter().map(|r| r.offenses.len()).sum(),
+            total_files: file_results.len(),
+            file_results,
+        }
+    }
+
+    /// Checks a single file, writing back any corrections.
     fn check_file(&self, path: &Path) -> Option<FileResult> {
         // Skip if not a file
         if !path.is_file() {
@@ -67,12 +149,58 @@ impl Runner {
             Err(_) => return None, // Skip files that can't be read
         };
 
-        // Run all enabled cops
-        let enabled_cops = self.registry.enabled_cops();
-        let mut offenses: Vec<Offense> = enabled_cops
-            .iter()
-            .flat_map(|cop| cop.check(&source))
-            .collect();
+        // Unchanged files under an unchanged config keep their offenses
+        let cache = self.cache.as_ref().filter(|_| self.autocorrect.is_none());
+        let resolved = cache.and_then(|_| self.configs.for_file(path).ok());
+        if let (Some(cache), Some(resolved)) = (cache, resolved) {
+            let key = cache.key(&resolved, path, &source.content);
+            if let Some(offenses) = cache.load(&key) {
+                return Some(FileResult::new(path.to_path_buf(), offenses, &source.content));
+            }
+            let (result, _) = self.check_source(source)?;
+            cache.save(&key, &result.offenses);
+            return Some(result);
+        }
+
+        let original = source.content.clone();
+        let (result, corrected) = self.check_source(source)?;
+        if corrected.content != original {
+            if let Err(e) = fs::write(path, &corrected.content) {
+                eprintln!("Error writing {}: {}", path.display(), e);
+            }
+        }
+        Some(result)
+    }
+
+    /// Checks `source` with the enabled cops in scope for its path. Returns
+    /// the offenses along with the source as corrected, if autocorrecting.
+    pub fn check_source(&self, source: SourceFile) -> Option<(FileResult, SourceFile)> {
+        let path = source.path.clone();
+        let resolved = match self.configs.for_file(&path) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                eprintln!("Error loading config for {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let registry = &resolved.registry;
+
+        // Run the enabled cops whose Include/Exclude patterns admit this file
+        let enabled_cops = registry.enabled_cops_for(&path);
+        let (source, mut offenses) = match self.autocorrect {
+            Some(mode) => autocorrect(&enabled_cops, source, mode),
+            None => {
+                let offenses = lint(&enabled_cops, &source);
+                (source, offenses)
+            }
+        };
+
+        // Report offenses with the configured severity of their cop
+        for offense in &mut offenses {
+            if let Some(severity) = registry.severity_override(&offense.cop_name) {
+                offense.severity = severity;
+            }
+        }
 
         // Sort offenses by location (line, then column)
         offenses.sort_by(|a, b| {
@@ -81,12 +209,86 @@ impl Runner {
                 .cmp(&b.location.line)
                 .then_with(|| a.location.column.cmp(&b.location.column))
         });
+        baseline::assign_fingerprints(&mut offenses, &path, &source);
+
+        Some((FileResult::new(path, offenses, &source.content), source))
+    }
+}
+
+/// Lints and corrects `source` until no more corrections apply. Returns the
+/// corrected source with the offenses fixed along the way followed by the
+/// ones that remain.
+pub fn autocorrect(
+    cops: &[&dyn Cop],
+    mut source: SourceFile,
+    mode: AutocorrectMode,
+) -> (SourceFile, Vec<Offense>) {
+    let correctable: HashSet<&str> = cops
+        .iter()
+        .filter(|cop| mode.allows(**cop))
+        .map(|cop| cop.name())
+        .collect();
+    let mut fixed = Vec::new();
+    for _ in 0..MAX_CORRECTION_PASSES {
+        let mut offenses = lint(cops, &source);
+        let content = corrector::apply(&source.content, &mut offenses, |o| {
+            correctable.contains(o.cop_name.as_str())
+        });
+        if content == source.content {
+            fixed.extend(offenses);
+            return (source, fixed);
+        }
+        fixed.extend(offenses.into_iter().filter(|o| o.corrected));
+        source = SourceFile::from_string(source.path, content);
+    }
+    fixed.extend(lint(cops, &source));
+    (source, fixed)
+}
+
+/// Runs `cops` on `source` and drops the offenses silenced by
+/// `# rubocop:disable` directives. When `Lint/RedundantCopDisableDirective`
+/// is among `cops`, disables that silenced nothing are reported; names of
+/// cops outside `cops` are left alone since they may belong to plugins or be
+/// turned off in configuration.
+pub fn lint(cops: &[&dyn Cop], source: &SourceFile) -> Vec<Offense> {
+    let offenses = cop::investigate(cops, source);
+    let directives = Directives::parse(source);
+    if directives.ranges.is_empty() {
+        return offenses;
+    }
 
-        Some(FileResult {
-            path: path.to_path_buf(),
-            offenses,
+    let mut used = vec![false; directives.ranges.len()];
+    let mut kept: Vec<Offense> = offenses
+        .into_iter()
+        .filter(|offense| {
+            let mut disabled = false;
+            for i in directives.disabling_ranges(&offense.cop_name, offense.location.line) {
+                used[i] = true;
+                disabled = true;
+            }
+            !disabled
         })
+        .collect();
+
+    let redundant = RedundantCopDisableDirective;
+    if cops.iter().any(|cop| cop.name() == redundant.name()) {
+        let known = |name: &str| {
+            name == "all"
+                || cops.iter().any(|cop| {
+                    cop.name() == name || cop.name().split('/').next() == Some(name)
+                })
+        };
+        for (i, range) in directives.ranges.iter().enumerate().filter(|(i, _)| !used[*i]) {
+            let entry = directives.range_name(range);
+            let silenced = directives
+                .disabling_ranges(redundant.name(), entry.line)
+                .any(|j| j != i);
+            if known(&entry.name) && entry.name != redundant.name() && !silenced {
+                kept.push(redundant.offense(entry));
+            }
+        }
     }
+    kept
 }
 
 #[cfg(test)]
@@ -192,4 +394,187 @@ mod tests {
 
         let _ = fs::remove_file(&file_path);
     }
+
+    #[test]
+    fn test_autocorrect_until_stable() {
+        let registry = CopRegistry::new();
+        let cops = registry.enabled_cops();
+        let source = SourceFile::from_string(PathBuf::from("t.rb"), "foo(a,b)   \n\n\n".into());
+        let (corrected, offenses) = autocorrect(&cops, source, AutocorrectMode::Safe);
+        assert_eq!(corrected.content, "foo(a, b)\n");
+        let fixed: Vec<&str> = offenses
+            .iter()
+            .filter(|o| o.corrected)
+            .map(|o| o.cop_name.as_str())
+            .collect();
+        assert!(fixed.contains(&"Layout/TrailingWhitespace"));
+        assert!(fixed.contains(&"Layout/SpaceAfterComma"));
+        assert!(fixed.contains(&"Layout/TrailingEmptyLines"));
+    }
+
+    #[test]
+    fn test_run_with_autocorrect_writes_file() {
+        use std::fs;
+
+        let file_path = std::env::temp_dir().join("test_runner_autocorrect.rb");
+        fs::write(&file_path, "x = [1,2]  \n").unwrap();
+
+        let runner = Runner::new(CopRegistry::new()).with_autocorrect(AutocorrectMode::Safe);
+        let result = runner.run(std::slice::from_ref(&file_path));
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "x = [1, 2]\n");
+        assert!(result.total_offenses >= 2);
+
+        let _ = fs::remove_file(&file_path);
+    }
+
+    #[test]
+    fn test_run_with_cache() {
+        use std::fs;
+
+        let root = std::env::temp_dir().join("oxicop_runner_cache");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let file_path = root.join("cached.rb");
+        fs::write(&file_path, "x = [1,2]\n").unwrap();
+
+        let runner = Runner::new(CopRegistry::new()).with_cache(ResultCache::new(Some(&root)));
+        let first = runner.run(std::slice::from_ref(&file_path));
+        let second = runner.run(std::slice::from_ref(&file_path));
+        let names = |result: &RunResult| -> Vec<String> {
+            result.file_results[0].offenses.iter().map(|o| o.cop_name.clone()).collect()
+        };
+        assert!(names(&first).contains(&"Layout/SpaceAfterComma".to_string()));
+        assert_eq!(names(&first), names(&second));
+        assert!(root.join("oxicop_cache").join(env!("CARGO_PKG_VERSION")).is_dir());
+
+        // Changed content misses the cache
+        fs::write(&file_path, "x = [1, 2]\n").unwrap();
+        let third = runner.run(std::slice::from_ref(&file_path));
+        assert!(!names(&third).contains(&"Layout/SpaceAfterComma".to_string()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_run_sources_in_memory() {
+        // The path needn't exist; the content is what's checked
+        let path = PathBuf::from("/nonexistent/staged.rb");
+        let source = SourceFile::from_string(path.clone(), "x = [1,2]  \n".into());
+        let runner = Runner::new(CopRegistry::new()).with_autocorrect(AutocorrectMode::Safe);
+        let result = runner.run_sources(vec![source]);
+
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.file_results[0].path, path);
+        assert!(result.file_results[0].offenses.iter().any(|o| o.cop_name == "Layout/SpaceAfterComma"));
+        // Formatters quote the corrected source
+        assert_eq!(result.file_results[0].source.as_deref(), Some("x = [1, 2]\n"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_lint_honors_directives() {
+        let registry = CopRegistry::new();
+        let cops = registry.enabled_cops();
+        let content = "x = 1   # rubocop:disable Layout/TrailingWhitespace \n\
+                       # rubocop:disable Layout\n\
+                       y = 2   \n\
+                       # rubocop:enable Layout\n\
+                       z = 3   \n";
+        let source = SourceFile::from_string(PathBuf::from("t.rb"), content.into());
+        let lines: Vec<usize> = lint(&cops, &source)
+            .iter()
+            .filter(|o| o.cop_name == "Layout/TrailingWhitespace")
+            .map(|o| o.location.line)
+            .collect();
+        assert_eq!(lines, vec![5]);
+    }
+
+    #[test]
+    fn test_lint_reports_redundant_disable() {
+        let registry = CopRegistry::new();
+        let cops = registry.enabled_cops();
+        let content = "x = 1 # rubocop:disable Style/Documentation, Layout/TrailingWhitespace, Rails/Foo\n";
+        let source = SourceFile::from_string(PathBuf::from("t.rb"), content.into());
+        let redundant: Vec<String> = lint(&cops, &source)
+            .into_iter()
+            .filter(|o| o.cop_name == "Lint/RedundantCopDisableDirective")
+            .map(|o| o.message)
+            .collect();
+        assert_eq!(
+            redundant,
+            vec![
+                "Unnecessary disabling of Style/Documentation.",
+                "Unnecessary disabling of Layout/TrailingWhitespace.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_autocorrect_skips_disabled_offenses() {
+        let registry = CopRegistry::new();
+        let cops = registry.enabled_cops();
+        let content = "# rubocop:disable Layout/SpaceAfterComma\nfoo(a,b)\n# rubocop:enable Layout/SpaceAfterComma\nfoo(a,b)\n";
+        let source = SourceFile::from_string(PathBuf::from("t.rb"), content.into());
+        let (corrected, _) = autocorrect(&cops, source, AutocorrectMode::Safe);
+        assert_eq!(
+            corrected.content,
+            "# rubocop:disable Layout/SpaceAfterComma\nfoo(a,b)\n# rubocop:enable Layout/SpaceAfterComma\nfoo(a, b)\n"
+        );
+    }
+
+    #[test]
+    fn test_check_file_respects_cop_path_filters() {
+        use crate::config::FileFilter;
+
+        let dir = std::env::temp_dir().join("oxicop_runner_cop_filters");
+        fs::create_dir_all(dir.join("spec")).unwrap();
+        let spec = dir.join("spec/a_spec.rb");
+        let app = dir.join("a.rb");
+        fs::write(&spec, "x = 1   \n").unwrap();
+        fs::write(&app, "x = 1   \n").unwrap();
+
+        let mut registry = CopRegistry::new();
+        let exclude = vec!["spec/**/*".to_string()];
+        registry.set_path_filter(
+            "Layout/TrailingWhitespace",
+            FileFilter::new(&dir, &[], &exclude).unwrap(),
+        );
+        let runner = Runner::new(registry);
+
+        let has_trailing = |path: &Path| {
+            runner
+                .check_file(path)
+                .unwrap()
+                .offenses
+                .iter()
+                .any(|o| o.cop_name == "Layout/TrailingWhitespace")
+        };
+        assert!(!has_trailing(&spec));
+        assert!(has_trailing(&app));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_check_file_applies_severity_override() {
+        use crate::cop::Severity;
+
+        let file_path = std::env::temp_dir().join("test_runner_severity.rb");
+        fs::write(&file_path, "x = 1   \n").unwrap();
+
+        let mut registry = CopRegistry::new();
+        registry.set_severity("Layout/TrailingWhitespace", Severity::Error);
+        let runner = Runner::new(registry);
+
+        let result = runner.check_file(&file_path).unwrap();
+        let offense = result
+            .offenses
+            .iter()
+            .find(|o| o.cop_name == "Layout/TrailingWhitespace")
+            .unwrap();
+        assert_eq!(offense.severity, Severity::Error);
+
+        let _ = fs::remove_file(&file_path);
+    }
 }
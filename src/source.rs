@@ -1,6 +1,12 @@
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::ast::Node;
+use crate::lexer::{self, Token, TokenKind};
+use crate::offense::Location;
+use crate::parser;
 
 /// Represents a loaded Ruby source file with precomputed line data.
 #[derive(Debug, Clone)]
@@ -11,6 +17,12 @@ pub struct SourceFile {
     pub content: String,
     /// Lines split without trailing newline characters.
     pub lines: Vec<String>,
+    /// Lossless token stream covering the whole file.
+    pub tokens: Vec<Token>,
+    /// Byte offset at which each line starts.
+    line_offsets: Vec<usize>,
+    /// Syntax tree, parsed on first use.
+    ast: OnceLock<Node>,
 }
 
 impl SourceFile {
@@ -23,13 +35,26 @@ impl SourceFile {
     /// Create a source file from an in-memory string (useful for testing).
     pub fn from_string(path: PathBuf, content: String) -> Self {
         let lines = content.lines().map(String::from).collect();
+        let tokens = lexer::tokenize(&content);
+        let line_offsets = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
         Self {
             path,
             content,
             lines,
+            tokens,
+            line_offsets,
+            ast: OnceLock::new(),
         }
     }
 
+    /// Syntax tree of the file. Parsed lazily so cops that only look at
+    /// lines or tokens don't pay for it.
+    pub fn ast(&self) -> &Node {
+        self.ast.get_or_init(|| parser::parse(&self.content, &self.tokens))
+    }
+
     /// Number of lines in the file.
     pub fn line_count(&self) -> usize {
         self.lines.len()
@@ -49,41 +74,70 @@ impl SourceFile {
         }
     }
 
+    /// Byte offset of a 1-based line and character column. Columns past the
+    /// end of the line are clamped to the line end.
+    pub fn offset_of(&self, line_number: usize, column: usize) -> Option<usize> {
+        let line = self.line(line_number)?;
+        let line_start = self.line_offsets[line_number - 1];
+        let within = line
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(line.len(), |(i, _)| i);
+        Some(line_start + within)
+    }
+
+    /// 1-based line and character column of a byte offset.
+    pub fn position_of(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.content.len());
+        let line_index = self.line_offsets.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_offsets[line_index];
+        let column = self.content[line_start..offset].chars().count() + 1;
+        (line_index + 1, column)
+    }
+
+    /// Offense location for a node. Nodes spanning several lines are
+    /// highlighted up to the end of their first line.
+    pub fn location_of(&self, node: &Node) -> Location {
+        let (line, column) = self.position_of(node.start);
+        let first_line = self.content[node.start.min(self.content.len())..]
+            .split('\n')
+            .next()
+            .unwrap_or("");
+        let end = node.end.min(node.start + first_line.len());
+        let length = self.content[node.start..end].chars().count();
+        Location::new(line, column, length)
+    }
+
+    /// Returns the token covering a byte offset.
+    pub fn token_at_offset(&self, offset: usize) -> Option<&Token> {
+        let index = self.tokens.partition_point(|t| t.end <= offset);
+        self.tokens.get(index).filter(|t| t.start <= offset)
+    }
+
+    /// Returns the token at a 1-based line and character column.
+    pub fn token_at(&self, line_number: usize, column: usize) -> Option<&Token> {
+        self.token_at_offset(self.offset_of(line_number, column)?)
+    }
+
+    /// Returns the source text of a token.
+    pub fn token_text(&self, token: &Token) -> &str {
+        token.text(&self.content)
+    }
+
     /// Check whether a given column on a line is inside a string literal or comment.
-    /// This is a heuristic - not 100% accurate for heredocs or complex interpolation,
-    /// but good enough for most linting decisions.
+    /// The opening delimiter of a literal and the `#` of a comment are not
+    /// considered inside; code in `#{}` interpolation is not either.
     pub fn in_string_or_comment(&self, line_number: usize, column: usize) -> bool {
-        if let Some(line) = self.line(line_number) {
-            let chars: Vec<char> = line.chars().collect();
-            let mut in_single_quote = false;
-            let mut in_double_quote = false;
-            let mut escaped = false;
-
-            for (i, &ch) in chars.iter().enumerate() {
-                if i + 1 >= column {
-                    break;
-                }
-                if escaped {
-                    escaped = false;
-                    continue;
-                }
-                if ch == '\\' && in_double_quote {
-                    escaped = true;
-                    continue;
-                }
-                if ch == '#' && !in_single_quote && !in_double_quote {
-                    return true;
-                }
-                if ch == '\'' && !in_double_quote {
-                    in_single_quote = !in_single_quote;
-                }
-                if ch == '"' && !in_single_quote {
-                    in_double_quote = !in_double_quote;
-                }
+        let Some(offset) = self.offset_of(line_number, column) else {
+            return false;
+        };
+        match self.token_at_offset(offset) {
+            Some(token) if token.kind.is_literal_begin() || token.kind == TokenKind::Comment => {
+                offset > token.start
             }
-            in_single_quote || in_double_quote
-        } else {
-            false
+            Some(token) if token.kind == TokenKind::Character => offset > token.start,
+            Some(token) => token.kind.is_literal() || token.kind.is_comment(),
+            None => false,
         }
     }
 }
@@ -154,4 +208,52 @@ mod tests {
         // The whole string including escaped quotes is inside a string
         assert!(source.in_string_or_comment(1, 10));
     }
+
+    #[test]
+    fn test_in_heredoc_body() {
+        let source = test_source("x = <<~EOS\n  foo # bar\nEOS\ny = 1 # z\n");
+        assert!(source.in_string_or_comment(2, 3));
+        assert!(source.in_string_or_comment(2, 7));
+        assert!(!source.in_string_or_comment(4, 1));
+        assert!(source.in_string_or_comment(4, 8));
+    }
+
+    #[test]
+    fn test_interpolation_is_code() {
+        let source = test_source("\"a #{b} c\"\n");
+        assert!(source.in_string_or_comment(1, 2));
+        assert!(!source.in_string_or_comment(1, 6));
+        assert!(source.in_string_or_comment(1, 9));
+    }
+
+    #[test]
+    fn test_embedded_doc_and_percent_literals() {
+        let source = test_source("=begin\ndef x\n=end\n%w[# not comment]\n");
+        assert!(source.in_string_or_comment(2, 1));
+        assert!(source.in_string_or_comment(4, 5));
+        assert!(!source.in_string_or_comment(4, 1));
+    }
+
+    #[test]
+    fn test_token_at() {
+        let source = test_source("foo = 'bar'\n");
+        let token = source.token_at(1, 8).unwrap();
+        assert_eq!(token.kind, TokenKind::StringContent);
+        assert_eq!(source.token_text(token), "bar");
+        assert_eq!(source.token_at(1, 1).unwrap().kind, TokenKind::Identifier);
+        assert!(source.token_at(3, 1).is_none());
+    }
+
+    #[test]
+    fn test_ast_and_node_locations() {
+        let source = test_source("x = 1\nfoo(x) do\n  bar\nend\n");
+        let mut blocks = Vec::new();
+        source.ast().walk(&mut |node| {
+            if node.type_name() == "block" {
+                blocks.push(source.location_of(node));
+            }
+        });
+        assert_eq!(blocks, vec![Location::new(2, 1, 9)]);
+        assert_eq!(source.position_of(8), (2, 3));
+    }
 }
@@ -0,0 +1,170 @@
+//! Working out which Ruby version the inspected code targets.
+//!
+//! Sources are tried in RuboCop's order: `AllCops: TargetRubyVersion`, the
+//! project's gemspec `required_ruby_version`, `.ruby-version`,
+//! `.tool-versions` and `Gemfile.lock`'s `RUBY VERSION`, falling back to
+//! [`DEFAULT_VERSION`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::Config;
+
+/// Assumed when no source names a version.
+pub const DEFAULT_VERSION: f64 = 2.7;
+
+static RUBY_VERSION_FILE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A\s*(?:ruby-)?(\d+\.\d+)").unwrap());
+static TOOL_VERSIONS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*ruby\s+(\d+\.\d+)").unwrap());
+static LOCKFILE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^RUBY VERSION\s*\n\s+ruby (\d+\.\d+)").unwrap());
+static GEMSPEC: Lazy<Regex> = Lazy::new(|| Regex::new(r"required_ruby_version\s*=\s*(.+)").unwrap());
+static REQUIREMENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"['"]\s*(>=|~>|=|>|<=|<)?\s*(\d+)\.(\d+)"#).unwrap());
+
+/// Where a target version came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Config,
+    Gemspec(PathBuf),
+    RubyVersionFile(PathBuf),
+    ToolVersionsFile(PathBuf),
+    BundlerLockFile(PathBuf),
+    Default,
+}
+
+/// The Ruby version the inspected code targets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetRuby {
+    pub version: f64,
+    pub source: Source,
+}
+
+impl TargetRuby {
+    /// Resolves the target for `config`, looking for version files from the
+    /// config's directory upwards.
+    pub fn resolve(config: &Config) -> Self {
+        if let Some(version) = config.all_cops.as_ref().and_then(|a| a.target_ruby_version) {
+            return Self { version, source: Source::Config };
+        }
+        let dir = if config.base_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            config.base_dir.as_path()
+        };
+        Self::from_project(dir)
+    }
+
+    /// Infers the target from the files of the project around `dir`.
+    pub fn from_project(dir: &Path) -> Self {
+        let found = gemspec_version(dir)
+            .or_else(|| file_version(dir, ".ruby-version", &RUBY_VERSION_FILE, Source::RubyVersionFile))
+            .or_else(|| file_version(dir, ".tool-versions", &TOOL_VERSIONS, Source::ToolVersionsFile))
+            .or_else(|| file_version(dir, "Gemfile.lock", &LOCKFILE, Source::BundlerLockFile));
+        found.unwrap_or(Self {
+            version: DEFAULT_VERSION,
+            source: Source::Default,
+        })
+    }
+}
+
+/// Reads the version captured by `pattern` from the nearest `name` at or
+/// above `dir`.
+fn file_version(dir: &Path, name: &str, pattern: &Regex, source: fn(PathBuf) -> Source) -> Option<TargetRuby> {
+    let path = find_upwards(dir, name)?;
+    let content = fs::read_to_string(&path).ok()?;
+    let version = pattern.captures(&content)?[1].parse().ok()?;
+    Some(TargetRuby {
+        version,
+        source: source(path),
+    })
+}
+
+/// Reads the lowest version a gemspec in `dir` allows, from
+/// `required_ruby_version = '>= 3.1'` and its array and
+/// `Gem::Requirement.new(...)` forms.
+fn gemspec_version(dir: &Path) -> Option<TargetRuby> {
+    let mut gemspecs: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gemspec"))
+        .collect();
+    gemspecs.sort();
+
+    gemspecs.into_iter().find_map(|path| {
+        let content = fs::read_to_string(&path).ok()?;
+        let requirement = GEMSPEC.captures(&content)?;
+        let version = REQUIREMENT
+            .captures_iter(&requirement[1])
+            .filter(|c| !matches!(c.get(1).map(|op| op.as_str()), Some("<" | "<=")))
+            .filter_map(|c| format!("{}.{}", &c[2], &c[3]).parse::<f64>().ok())
+            .reduce(f64::min)?;
+        Some(TargetRuby {
+            version,
+            source: Source::Gemspec(path),
+        })
+    })
+}
+
+fn find_upwards(dir: &Path, name: &str) -> Option<PathBuf> {
+    let dir = std::path::absolute(dir).ok()?;
+    dir.ancestors().map(|d| d.join(name)).find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_version_files() {
+        let dir = project("oxicop_target_ruby_files", &[(".ruby-version", "ruby-3.2.2\n")]);
+        let target = TargetRuby::from_project(&dir.join("lib"));
+        assert_eq!(target.version, 3.2);
+        assert_eq!(target.source, Source::RubyVersionFile(dir.join(".ruby-version")));
+
+        let dir = project("oxicop_target_ruby_files", &[(".tool-versions", "nodejs 20.1.0\nruby 3.1.4\n")]);
+        assert_eq!(TargetRuby::from_project(&dir).version, 3.1);
+
+        let lockfile = "GEM\n  specs:\n\nRUBY VERSION\n   ruby 3.3.0p0\n\nBUNDLED WITH\n   2.5.3\n";
+        let dir = project("oxicop_target_ruby_files", &[("Gemfile.lock", lockfile)]);
+        assert_eq!(TargetRuby::from_project(&dir).version, 3.3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_gemspec_and_precedence() {
+        let gemspec = "Gem::Specification.new do |spec|\n  spec.required_ruby_version = ['>= 3.0', '< 4']\nend\n";
+        let dir = project(
+            "oxicop_target_ruby_gemspec",
+            &[("foo.gemspec", gemspec), (".ruby-version", "3.3.1\n")],
+        );
+        let target = TargetRuby::from_project(&dir);
+        assert_eq!(target.version, 3.0);
+        assert_eq!(target.source, Source::Gemspec(dir.join("foo.gemspec")));
+
+        let mut config: Config = serde_yaml::from_str("AllCops:\n  TargetRubyVersion: 2.6\n").unwrap();
+        config.base_dir = dir.clone();
+        assert_eq!(TargetRuby::resolve(&config).source, Source::Config);
+        assert_eq!(TargetRuby::resolve(&config).version, 2.6);
+
+        let _ = fs::remove_dir_all(&dir);
+        let dir = project("oxicop_target_ruby_gemspec", &[]);
+        assert_eq!(TargetRuby::from_project(&dir).version, DEFAULT_VERSION);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,259 @@
+//! Generating `.rubocop_todo.yml` for `--auto-gen-config`.
+//!
+//! Every cop with offenses gets an entry that silences them: cops with a
+//! `Max`-style limit have it raised to the worst value found, cops offending
+//! in up to `exclude_limit` files exclude those files, and the rest are
+//! disabled.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::{default_cop_config, TODO_FILE_NAME};
+use crate::registry::CopRegistry;
+use crate::runner::RunResult;
+
+/// Files a cop may offend in before it is disabled rather than excluded.
+pub const DEFAULT_EXCLUDE_LIMIT: usize = 15;
+
+/// Parameters that limit a measured value, raised instead of excluding.
+const LIMIT_PARAMS: &[&str] = &["Max", "LengthThreshold"];
+
+/// The measured value in messages like `Line is too long. [130/120]` or
+/// `Method has 14 lines (max 10)`.
+static MEASURED: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(\d+)/\d+\]|(\d+)(?: \w+)? \(max \d+\)").unwrap());
+
+/// The offenses of one cop across the run.
+#[derive(Default)]
+struct CopOffenses {
+    count: usize,
+    files: BTreeSet<String>,
+    worst: Option<usize>,
+}
+
+/// Renders the todo file silencing every uncorrected offense in `result`.
+/// Excluded paths are written relative to `base_dir`, where the file goes.
+pub fn generate(result: &RunResult, registry: &CopRegistry, base_dir: &Path, exclude_limit: usize) -> String {
+    let mut by_cop: BTreeMap<&str, CopOffenses> = BTreeMap::new();
+    for file_result in &result.file_results {
+        let path = relative_path(&file_result.path, base_dir);
+        for offense in file_result.offenses.iter().filter(|o| !o.corrected) {
+            let entry = by_cop.entry(&offense.cop_name).or_default();
+            entry.count += 1;
+            entry.files.insert(path.clone());
+            let measured = MEASURED
+                .captures(&offense.message)
+                .and_then(|c| c.get(1).or(c.get(2)))
+                .and_then(|m| m.as_str().parse().ok());
+            entry.worst = entry.worst.max(measured);
+        }
+    }
+
+    let mut out = format!(
+        "# This configuration was generated by\n\
+         # `oxicop --auto-gen-config`\n\
+         # using oxicop version {}.\n\
+         # The point is for the user to remove these configuration records\n\
+         # one by one as the offenses are removed from the code base.\n\
+         # Note that changes in the inspected code, or installation of new\n\
+         # versions of oxicop, may require this file to be generated again.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for (cop_name, offenses) in by_cop {
+        out.push_str(&format!("\n# Offense count: {}\n", offenses.count));
+        if let Some(cop) = registry.cop(cop_name).filter(|cop| cop.supports_autocorrect()) {
+            if cop.safe_autocorrect() {
+                out.push_str("# This cop supports safe autocorrection (--autocorrect).\n");
+            } else {
+                out.push_str("# This cop supports unsafe autocorrection (--autocorrect-all).\n");
+            }
+        }
+        out.push_str(&format!("{}:\n", cop_name));
+
+        let limit = limit_param(cop_name).zip(offenses.worst);
+        if let Some((param, worst)) = limit {
+            out.push_str(&format!("  {}: {}\n", param, worst));
+        } else if offenses.files.len() > exclude_limit {
+            out.push_str("  Enabled: false\n");
+        } else {
+            out.push_str("  Exclude:\n");
+            for file in &offenses.files {
+                out.push_str(&format!("    - '{}'\n", file.replace('\'', "''")));
+            }
+        }
+    }
+    out
+}
+
+/// Adds `todo` to the `inherit_from` of the config `existing`, or creates a
+/// config inheriting just it. Returns `None` if it's already inherited.
+pub fn add_inheritance(existing: Option<&str>, todo: &str) -> Option<String> {
+    let existing = existing.unwrap_or_default();
+    let mut files: Vec<String> = match serde_yaml::from_str::<serde_yaml::Value>(existing) {
+        Ok(config) => match config.get("inherit_from") {
+            Some(serde_yaml::Value::String(file)) => vec![file.clone()],
+            Some(serde_yaml::Value::Sequence(list)) => {
+                list.iter().filter_map(|f| f.as_str().map(str::to_string)).collect()
+            }
+            _ => Vec::new(),
+        },
+        Err(_) => Vec::new(),
+    };
+    if files.iter().any(|f| f == todo) {
+        return None;
+    }
+    files.insert(0, todo.to_string());
+    let inherit_from = if files.len() == 1 {
+        format!("inherit_from: {}\n", todo)
+    } else {
+        format!("inherit_from:\n  - {}\n", files.join("\n  - "))
+    };
+
+    // Drop the old inherit_from and put the new one at the top of the
+    // document
+    static OLD_INHERIT: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?m)^inherit_from: *(?:[^\n]+(?:\n|$)|(?:\n *- *[^\n]+)+\n?)").unwrap());
+    let rest = OLD_INHERIT.replace(existing, "");
+    let mut lines: Vec<&str> = if rest.trim().is_empty() {
+        Vec::new()
+    } else {
+        rest.split('\n').collect()
+    };
+    let at = lines.iter().position(|line| line.starts_with("---")).map_or(0, |i| i + 1);
+    // The blank lines left where the old one stood would double the one
+    // separating it from the rest
+    while lines.get(at).is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(at);
+    }
+    lines.insert(at, &inherit_from);
+    let config = lines.join("\n");
+    Some(format!("{}\n", config.trim_end_matches('\n')))
+}
+
+/// Where `.rubocop.yml` in `config_dir` finds a todo file written to
+/// `todo_dir`.
+pub fn todo_path_from(config_dir: &Path, todo_dir: &Path) -> String {
+    let todo = todo_dir.join(TODO_FILE_NAME);
+    match todo.strip_prefix(config_dir) {
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) => todo.to_string_lossy().into_owned(),
+    }
+}
+
+fn limit_param(cop_name: &str) -> Option<&'static str> {
+    let defaults = default_cop_config(cop_name)?;
+    LIMIT_PARAMS.iter().copied().find(|param| defaults.param(param).is_some())
+}
+
+fn relative_path(path: &Path, base_dir: &Path) -> String {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let relative = absolute.strip_prefix(base_dir).unwrap_or(path);
+    relative.to_string_lossy().trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cop::Severity;
+    use crate::offense::{Location, Offense};
+    use crate::runner::FileResult;
+    use std::path::PathBuf;
+
+    fn offense(cop: &str, message: &str) -> Offense {
+        Offense::new(cop, message, Severity::Convention, Location::new(1, 1, 1))
+    }
+
+    fn result(files: Vec<(&str, Vec<Offense>)>) -> RunResult {
+        let file_results: Vec<FileResult> = files
+            .into_iter()
+            .map(|(path, offenses)| FileResult {
+                path: PathBuf::from(path),
+                offenses,
+                source: None,
+            })
+            .collect();
+        RunResult {
+            total_files: file_results.len(),
+            total_offenses: file_results.iter().map(|r| r.offenses.len()).sum(),
+            file_results,
+        }
+    }
+
+    #[test]
+    fn test_generate_entries() {
+        let result = result(vec![
+            (
+                "/project/app/a.rb",
+                vec![
+                    offense("Metrics/MethodLength", "Method has 14 lines (max 10)"),
+                    offense("Layout/LineLength", "Line is too long. [130/120]"),
+                    offense("Style/NegatedIf", "Favor unless over if for negative conditions."),
+                    offense("Lint/Debugger", "Remove debugger entry point `pry`."),
+                ],
+            ),
+            (
+                "/project/app/b.rb",
+                vec![
+                    offense("Metrics/MethodLength", "Method has 31 lines (max 10)"),
+                    offense("Lint/Debugger", "Remove debugger entry point `pry`."),
+                ],
+            ),
+        ]);
+        let todo = generate(&result, &CopRegistry::new(), Path::new("/project"), 1);
+
+        assert!(todo.starts_with("# This configuration was generated by\n# `oxicop --auto-gen-config`\n"));
+        assert!(todo.contains("\n# Offense count: 2\nMetrics/MethodLength:\n  Max: 31\n"));
+        assert!(todo.contains("Layout/LineLength:\n  Max: 130\n"));
+        assert!(todo.contains("\n# Offense count: 1\nStyle/NegatedIf:\n  Exclude:\n    - 'app/a.rb'\n"));
+        assert!(todo.contains("\n# Offense count: 2\nLint/Debugger:\n  Enabled: false\n"));
+        // Entries are sorted by cop name.
+        assert!(todo.find("Layout/LineLength").unwrap() < todo.find("Lint/Debugger").unwrap());
+    }
+
+    #[test]
+    fn test_generate_notes_autocorrect() {
+        let result = result(vec![(
+            "/project/a.rb",
+            vec![offense("Layout/TrailingWhitespace", "Trailing whitespace detected.")],
+        )]);
+        let todo = generate(&result, &CopRegistry::new(), Path::new("/project"), 15);
+        assert!(todo.contains(
+            "# This cop supports safe autocorrection (--autocorrect).\nLayout/TrailingWhitespace:\n  Exclude:\n    - 'a.rb'\n"
+        ));
+    }
+
+    #[test]
+    fn test_add_inheritance() {
+        assert_eq!(
+            add_inheritance(None, ".rubocop_todo.yml").unwrap(),
+            "inherit_from: .rubocop_todo.yml\n"
+        );
+        assert_eq!(
+            add_inheritance(Some("AllCops:\n  NewCops: enable\n"), ".rubocop_todo.yml").unwrap(),
+            "inherit_from: .rubocop_todo.yml\n\nAllCops:\n  NewCops: enable\n"
+        );
+        assert_eq!(
+            add_inheritance(Some("inherit_from: base.yml\nStyle/Foo:\n  Enabled: false\n"), ".rubocop_todo.yml")
+                .unwrap(),
+            "inherit_from:\n  - .rubocop_todo.yml\n  - base.yml\n\nStyle/Foo:\n  Enabled: false\n"
+        );
+        assert_eq!(
+            add_inheritance(Some("---\ninherit_from:\n  - a.yml\n  - b.yml\n"), ".rubocop_todo.yml").unwrap(),
+            "---\ninherit_from:\n  - .rubocop_todo.yml\n  - a.yml\n  - b.yml\n"
+        );
+        assert_eq!(
+            add_inheritance(Some("inherit_from: base.yml\n\nStyle/Foo:\n  Enabled: false\n\n"), ".rubocop_todo.yml")
+                .unwrap(),
+            "inherit_from:\n  - .rubocop_todo.yml\n  - base.yml\n\nStyle/Foo:\n  Enabled: false\n"
+        );
+        // A single value on the last line, with no trailing newline
+        assert_eq!(
+            add_inheritance(Some("Style/Foo:\n  Enabled: false\ninherit_from: base.yml"), ".rubocop_todo.yml")
+                .unwrap(),
+            "inherit_from:\n  - .rubocop_todo.yml\n  - base.yml\n\nStyle/Foo:\n  Enabled: false\n"
+        );
+        assert!(add_inheritance(Some("inherit_from: .rubocop_todo.yml\n"), ".rubocop_todo.yml").is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Runs the `oxicop` binary on scratch projects.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+/// Creates a fresh directory under the temp dir holding `files`.
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// Runs oxicop in `dir`, keeping results out of the user's cache.
+fn oxicop(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_oxicop"))
+        .args(["--cache", "false"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("run oxicop")
+}
+
+/// Runs oxicop with `input` on its stdin.
+fn oxicop_with_stdin(dir: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_oxicop"))
+        .args(["--cache", "false"])
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run oxicop");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// The paths of the files with offenses in `--format json` output.
+fn offending_paths(output: &Output) -> Vec<String> {
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut paths: Vec<String> = report["offenses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|offense| offense["path"].as_str().unwrap().to_string())
+        .collect();
+    paths.dedup();
+    paths
+}
+
+#[test]
+fn test_discovery_without_config_uses_default_include_and_exclude() {
+    let source = "x = 1\n";
+    let dir = project(
+        "oxicop_cli_default_discovery",
+        &[
+            ("Gemfile", source),
+            ("Rakefile", source),
+            ("app.gemspec", source),
+            ("lib/tasks/db.rake", source),
+            ("lib/c.rb", source),
+            ("README", source),
+            ("vendor/bundle/a.rb", source),
+            ("node_modules/pkg/b.rb", source),
+            ("tmp/c.rb", source),
+        ],
+    );
+
+    let output = oxicop(&dir, &["--format", "json", "."]);
+    assert_eq!(
+        offending_paths(&output),
+        ["./Gemfile", "./Rakefile", "./app.gemspec", "./lib/c.rb", "./lib/tasks/db.rake"]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_baseline_with_changed_lines_only_counts_fixed_offenses() {
+    let dir = project(
+        "oxicop_cli_baseline_changed_lines",
+        &[
+            (".rubocop.yml", "AllCops:\n  NewCops: disable\n"),
+            (
+                "app.rb",
+                "# frozen_string_literal: true\n\ndef a\n  binding.pry\nend\n\ndef b\n  binding.pry\nend\n",
+            ),
+        ],
+    );
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+    let args = ["--format", "json", "--baseline", "baseline.json"];
+    assert!(oxicop(&dir, &args).status.success());
+
+    // Fix the debugger call in `b` and add one to `a`, leaving the
+    // recorded one in `a` on an unchanged line
+    std::fs::write(
+        dir.join("app.rb"),
+        "# frozen_string_literal: true\n\ndef a\n  binding.pry\n  debugger\nend\n\ndef b\n  nil\nend\n",
+    )
+    .unwrap();
+    let output = oxicop(&dir, &[&args[..], &["--changed-lines-only"]].concat());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let lines: Vec<u64> = report["offenses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|offense| offense["line"].as_u64().unwrap())
+        .collect();
+    assert_eq!(lines, [5]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("1 offense in baseline.json fixed"), "{}", stderr);
+
+    // A new baseline can't be built from a partial run
+    let output = oxicop(&dir, &["--baseline", "new.json", "--changed-lines-only"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!dir.join("new.json").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_stdin_resolves_config_from_the_given_path() {
+    let dir = project(
+        "oxicop_cli_stdin_config",
+        &[
+            (".rubocop.yml", "AllCops:\n  NewCops: disable\n"),
+            ("legacy/.rubocop.yml", "inherit_from: ../.rubocop.yml\nLint/Debugger:\n  Enabled: false\n"),
+        ],
+    );
+    let source = "# frozen_string_literal: true\n\nbinding.pry\n";
+
+    // Neither file exists; only the content on stdin is linted
+    let output = oxicop_with_stdin(&dir, &["--format", "json", "--stdin", "app/a.rb"], source);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(offending_paths(&output), ["app/a.rb"]);
+
+    let output = oxicop_with_stdin(&dir, &["--format", "json", "--stdin", "legacy/a.rb"], source);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(offending_paths(&output).is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_stdin_exclude_needs_force_exclusion() {
+    let dir = project(
+        "oxicop_cli_stdin_exclude",
+        &[(".rubocop.yml", "AllCops:\n  NewCops: disable\n  Exclude: ['db/schema.rb']\n")],
+    );
+    let source = "binding.pry\n";
+
+    let output = oxicop_with_stdin(&dir, &["--format", "json", "--stdin", "db/schema.rb"], source);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(offending_paths(&output), ["db/schema.rb"]);
+
+    let args = ["--format", "json", "--force-exclusion", "--stdin", "db/schema.rb"];
+    let output = oxicop_with_stdin(&dir, &args, source);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(offending_paths(&output).is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_stdin_autocorrect_prints_corrected_source() {
+    let dir = project("oxicop_cli_stdin_autocorrect", &[(".rubocop.yml", "AllCops:\n  NewCops: disable\n")]);
+    let source = "# frozen_string_literal: true\n\nx = [1,2]   \n";
+
+    let output = oxicop_with_stdin(&dir, &["--stdin", "a.rb", "-a"], source);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let (report, corrected) = stdout.split_once("====================\n").unwrap();
+    assert!(report.contains("[Corrected]"), "{}", report);
+    assert_eq!(corrected, "# frozen_string_literal: true\n\nx = [1, 2]\n");
+
+    // Without -a the report stands alone
+    let output = oxicop_with_stdin(&dir, &["--stdin", "a.rb"], source);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8(output.stdout).unwrap().contains("===================="));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,168 @@
+//! Drives `oxicop lsp` over stdio the way an editor would.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+/// A scripted client talking to a spawned server.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    fn start(dir: &std::path::Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_oxicop"))
+            .arg("lsp")
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("start oxicop lsp");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self { child, stdin, stdout }
+    }
+
+    fn send(&mut self, message: Value) {
+        self.send_raw(&message.to_string());
+    }
+
+    fn send_raw(&mut self, body: &str) {
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, id: u64, method: &str, params: Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response = self.receive();
+        assert_eq!(response["id"], id);
+        response
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+fn codes(notification: &Value) -> Vec<String> {
+    assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+    notification["params"]["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_lsp_session() {
+    let dir = std::env::temp_dir().join("oxicop_lsp_session");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(".rubocop.yml"),
+        "AllCops:\n  NewCops: disable\nStyle/FrozenStringLiteralComment:\n  Enabled: false\n",
+    )
+    .unwrap();
+    let uri = format!("file://{}/app.rb", dir.display());
+
+    let mut client = Client::start(&dir);
+    let initialize = client.request(1, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(initialize["result"]["capabilities"]["textDocumentSync"]["change"], 1);
+    assert_eq!(initialize["result"]["serverInfo"]["name"], "oxicop");
+    client.notify("initialized", json!({}));
+
+    // The nearest .rubocop.yml applies to the unsaved buffer
+    client.notify(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": uri, "languageId": "ruby", "version": 1, "text": "foo(a,b)\n" } }),
+    );
+    let published = client.receive();
+    assert_eq!(published["params"]["uri"], uri.as_str());
+    assert_eq!(published["params"]["version"], 1);
+    assert_eq!(codes(&published), vec!["Layout/SpaceAfterComma"]);
+
+    let actions = client.request(
+        2,
+        "textDocument/codeAction",
+        json!({
+            "textDocument": { "uri": uri },
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 8 } },
+            "context": { "diagnostics": published["params"]["diagnostics"] },
+        }),
+    );
+    let actions = actions["result"].as_array().unwrap();
+    assert_eq!(actions[0]["title"], "Autocorrect Layout/SpaceAfterComma");
+    assert_eq!(
+        actions[0]["edit"]["changes"][uri.as_str()][0],
+        json!({
+            "range": { "start": { "line": 0, "character": 6 }, "end": { "line": 0, "character": 6 } },
+            "newText": " ",
+        })
+    );
+    assert_eq!(actions[1]["title"], "Disable Layout/SpaceAfterComma for this line");
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "foo(a, b)\n" }],
+        }),
+    );
+    let published = client.receive();
+    assert_eq!(published["params"]["version"], 2);
+    assert!(codes(&published).is_empty());
+
+    client.notify("textDocument/didClose", json!({ "textDocument": { "uri": uri } }));
+    assert!(codes(&client.receive()).is_empty());
+
+    let shutdown = client.request(3, "shutdown", Value::Null);
+    assert_eq!(shutdown["result"], Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.child.wait().unwrap().success());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_lsp_invalid_json_keeps_serving() {
+    let dir = std::env::temp_dir().join("oxicop_lsp_invalid_json");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut client = Client::start(&dir);
+    client.request(1, "initialize", json!({ "capabilities": {} }));
+    client.send_raw("{not json");
+    let error = client.receive();
+    assert_eq!(error["id"], Value::Null);
+    assert_eq!(error["error"]["code"], -32700);
+
+    let shutdown = client.request(2, "shutdown", Value::Null);
+    assert_eq!(shutdown["result"], Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.child.wait().unwrap().success());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}